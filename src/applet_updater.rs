@@ -1,142 +1,81 @@
-use std::{io::Read, path::PathBuf, process::Stdio, sync::Arc};
+use std::sync::Arc;
 
-use glob::glob;
-use tokio::{
-    process::Command,
-    sync::{
-        mpsc::{self, UnboundedSender},
-        Mutex,
-    },
-};
-use tracing::{instrument, log::error};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::instrument;
 
 use crate::{
-    device::card_device::CardDevice,
+    config::Config,
+    device::{card_device::CardDevice, card_device_type::CardDeviceType},
+    notifier::{self, Notification, Notifier},
     pubsub::{
-        message::Message, message_register::MessageRegister, message_state::MessageState,
-        message_topic::MessageTopic, try_downcast_ref::try_downcast_ref,
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
     },
 };
 
 #[instrument]
-pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "applet_updater").await;
 
-    pubsub_tx
-        .lock()
-        .await
-        .send(Arc::new(MessageRegister::new(
-            MessageTopic::AudioState,
-            Arc::new(tx),
-        )))
-        .unwrap();
+    let mut notifiers: Vec<Box<dyn Notifier>> =
+        config.notifiers.iter().map(notifier::from_config).collect();
 
     let mut default_source: Option<CardDevice> = None;
+    let mut default_sink: Option<CardDevice> = None;
 
     loop {
         if let Some(message) = rx.recv().await {
-            let mut maybe_app_path: Option<String> = None;
-            let mut volume_icon: Option<String> = None;
-            let mut volume: Option<f64> = None;
-
             if let Some(message) = try_downcast_ref!(message, MessageState) {
-                let sources = message.sources();
-                let new_default_source = sources.iter().find(|s| s.is_default).unwrap();
-
-                let update_applet = if let Some(ref default_source_) = default_source {
-                    new_default_source.index != default_source_.index
-                        || new_default_source.volume != default_source_.volume
-                        || new_default_source.is_muted != default_source_.is_muted
-                } else {
-                    true
-                };
-
-                if update_applet {
-                    default_source.replace(new_default_source.clone());
-
-                    volume_icon.replace(if new_default_source.is_muted {
-                        "microphone-sensitivity-muted-symbolic".to_owned()
-                    } else if new_default_source.volume < 25.0 {
-                        "microphone-sensitivity-low-symbolic".to_owned()
-                    } else if new_default_source.volume >= 25.0 || new_default_source.volume <= 75.0
-                    {
-                        "microphone-sensitivity-medium-symbolic".to_owned()
-                    } else {
-                        "microphone-sensitivity-high-symbolic".to_owned()
-                    });
-
-                    volume.replace(new_default_source.volume);
-
-                    let app_file_path_glob: PathBuf = [
-                        std::env::var("HOME").unwrap(),
-                        ".config".to_owned(),
-                        "xfce4".to_owned(),
-                        "panel".to_owned(),
-                        "**".to_owned(),
-                        "*.desktop".to_owned(),
-                    ]
-                    .iter()
-                    .collect();
-
-                    let maybe_app_path_ =
-                        glob(app_file_path_glob.to_str().unwrap())
-                            .unwrap()
-                            .find(|path| {
-                                let mut file = std::fs::File::open(path.as_ref().unwrap()).unwrap();
-                                let mut buf = String::new();
-                                file.read_to_string(&mut buf).unwrap();
-                                buf.contains("Name=toggle_microphone")
-                            });
-
-                    if let Some(path) = maybe_app_path_ {
-                        maybe_app_path.replace(path.unwrap().to_str().unwrap().to_owned());
+                let notifications = [
+                    update_default(
+                        CardDeviceType::Source,
+                        &message.sources(),
+                        &mut default_source,
+                    ),
+                    update_default(CardDeviceType::Sink, &message.sinks(), &mut default_sink),
+                ];
+
+                for notification in notifications.iter().flatten() {
+                    for notifier in notifiers.iter_mut() {
+                        notifier.notify(notification).await;
                     }
                 }
             }
+        }
+    }
+}
 
-            let mut tasks = Vec::new();
-
-            if let Some(path) = maybe_app_path {
-                let volume_icon = volume_icon.as_ref().unwrap().to_owned();
-
-                tasks.push(tokio::spawn(async move {
-                    let mut command = Command::new("sed")
-                        .args(&["-i", &format!("s/Icon=.*/Icon={}/", &volume_icon), &path])
-                        .stdout(Stdio::null())
-                        .stderr(Stdio::null())
-                        .spawn()
-                        .unwrap();
-
-                    let exit_status = command.wait().await.unwrap();
-
-                    if !exit_status.success() {
-                        error!("Could not set the applet icon to {}", &volume_icon);
-                    }
-                }));
-            }
-
-            if let (Some(volume_icon), Some(volume)) = (volume_icon, volume) {
-                let volume_icon = volume_icon.to_owned();
-
-                tasks.push(tokio::spawn(async move {
-                    let mut command = Command::new("notify-send")
-                        .args(&["-t", "1", "-i", &volume_icon, &format!("{}%", volume), ""])
-                        .stdout(Stdio::null())
-                        .stderr(Stdio::null())
-                        .spawn()
-                        .unwrap();
-
-                    let exit_status = command.wait().await.unwrap();
+fn update_default(
+    _type: CardDeviceType,
+    card_devices: &[CardDevice],
+    default_card_device: &mut Option<CardDevice>,
+) -> Option<Notification> {
+    let new_default_card_device = card_devices.iter().find(|c| c.is_default)?;
+
+    let (update_applet, device_changed) =
+        if let Some(ref default_card_device_) = default_card_device {
+            let device_changed = new_default_card_device.name != default_card_device_.name;
+
+            (
+                device_changed
+                    || new_default_card_device.index != default_card_device_.index
+                    || new_default_card_device.volume != default_card_device_.volume
+                    || new_default_card_device.is_muted != default_card_device_.is_muted,
+                device_changed,
+            )
+        } else {
+            (true, false)
+        };
+
+    if !update_applet {
+        return None;
+    }
 
-                    if !exit_status.success() {
-                        error!("Could not set the applet icon to {}", &volume_icon);
-                    }
-                }));
-            }
+    default_card_device.replace(new_default_card_device.clone());
 
-            for task in tasks {
-                task.await.unwrap();
-            }
-        }
-    }
+    Some(Notification::new(
+        _type,
+        new_default_card_device.clone(),
+        device_changed,
+    ))
 }
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+use crate::protocol::command::Command;
+
+pub async fn run(socket_path: Option<&Path>, command: &Command) {
+    let socket_path = socket_path.unwrap_or_else(|| {
+        eprintln!("XDG_RUNTIME_DIR is not set, pass the control socket path with --socket");
+
+        std::process::exit(1);
+    });
+
+    let stream = UnixStream::connect(socket_path)
+        .await
+        .unwrap_or_else(|error| {
+            eprintln!("Could not connect to {}: {}", socket_path.display(), error);
+
+            std::process::exit(1);
+        });
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let mut line = serde_json::to_string(command).unwrap();
+    line.push('\n');
+
+    writer.write_all(line.as_bytes()).await.unwrap();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let event: Value = serde_json::from_str(&line).unwrap();
+
+        match event["event"].as_str() {
+            Some("done") => return,
+            Some("microphone_test") => {
+                println!("{}", serde_json::to_string_pretty(&event).unwrap());
+
+                return;
+            }
+            Some("routing_graph") => {
+                let mut graph = event;
+
+                graph.as_object_mut().unwrap().remove("event");
+
+                println!("{}", serde_json::to_string_pretty(&graph).unwrap());
+
+                return;
+            }
+            Some("routing_graph_dot") => {
+                print!("{}", event["dot"].as_str().unwrap_or_default());
+
+                return;
+            }
+            Some("error") => {
+                eprintln!("{}", event["message"].as_str().unwrap_or_default());
+
+                std::process::exit(1);
+            }
+            _ => continue,
+        }
+    }
+
+    eprintln!("The control socket closed before the command finished");
+
+    std::process::exit(1);
+}
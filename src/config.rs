@@ -1,6 +1,111 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+
+use evdev::Key;
+
+use crate::{device::form_factor::FormFactor, protocol::command::Command};
 
 #[derive(Debug)]
 pub struct Config {
     pub server_addr: SocketAddr,
+    pub socket_path: Option<PathBuf>,
+    pub admin_token: Option<String>,
+    pub data_dir: PathBuf,
+    pub dbus: bool,
+    pub tray: bool,
+    pub notifiers: Vec<NotifierConfig>,
+    pub hotkeys: HotkeysConfig,
+    pub volume_limits: Vec<VolumeLimit>,
+    pub privacy: PrivacyConfig,
+    pub level_rate: f64,
+    pub max_listeners: usize,
+    pub simulate: bool,
+    pub command: Option<Command>,
+}
+
+#[derive(Debug)]
+pub enum NotifierConfig {
+    Xfce {
+        source_launcher: String,
+        sink_launcher: Option<String>,
+    },
+    Notifications {
+        timeout: i32,
+    },
+    Waybar {
+        path: String,
+    },
+    I3blocks {
+        path: String,
+    },
+}
+
+#[derive(Debug)]
+pub struct HotkeysConfig {
+    pub push_to_talk_key: Option<Key>,
+    pub toggle_mute_key: Option<Key>,
+    pub input_devices: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct PrivacyConfig {
+    pub enabled: bool,
+    pub sources: Vec<String>,
+    pub deny: Option<DenyPolicy>,
+    pub allowed_applications: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum DenyPolicy {
+    Kill,
+    Mute,
+}
+
+#[derive(Debug)]
+pub struct VolumeLimit {
+    pub target: VolumeLimitTarget,
+    pub min: Option<f64>,
+    pub max: f64,
+}
+
+#[derive(Debug)]
+pub enum VolumeLimitTarget {
+    FormFactor(FormFactor),
+    Device(String),
+}
+
+impl FromStr for VolumeLimit {
+    type Err = String;
+
+    fn from_str(limit: &str) -> Result<Self, Self::Err> {
+        let (target, range) = limit
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Expected TARGET=[MIN-]MAX in {:?}", limit))?;
+
+        let target = match target {
+            "internal" => VolumeLimitTarget::FormFactor(FormFactor::Internal),
+            "headphones" => VolumeLimitTarget::FormFactor(FormFactor::Headphones),
+            "webcam" => VolumeLimitTarget::FormFactor(FormFactor::Webcam),
+            "headset" => VolumeLimitTarget::FormFactor(FormFactor::Headset),
+            name => VolumeLimitTarget::Device(name.to_owned()),
+        };
+
+        let parse = |value: &str| {
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| (0.0..=100.0).contains(value))
+                .ok_or_else(|| format!("Invalid volume {:?} in {:?}", value, limit))
+        };
+
+        let (min, max) = match range.split_once('-') {
+            Some((min, max)) => (Some(parse(min)?), parse(max)?),
+            None => (None, parse(range)?),
+        };
+
+        if min.map_or(false, |min| min > max) {
+            return Err(format!("The minimum exceeds the maximum in {:?}", limit));
+        }
+
+        Ok(VolumeLimit { target, min, max })
+    }
 }
@@ -0,0 +1,120 @@
+use std::{fs::Permissions, os::unix::fs::PermissionsExt, sync::Arc};
+
+use tokio::{
+    io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+    sync::{mpsc::UnboundedSender, Mutex},
+};
+use tracing::{error, info, instrument, log::debug};
+
+use crate::{
+    config::Config,
+    protocol::{command, event::Event},
+    pubsub::{self, message::Message, message_topic::MessageTopic},
+};
+
+#[instrument]
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let socket_path = match config.socket_path {
+        Some(ref socket_path) => socket_path,
+        None => {
+            error!("Not starting the control socket: XDG_RUNTIME_DIR is not set and no --socket was given");
+
+            return;
+        }
+    };
+
+    if socket_path.exists() {
+        if UnixStream::connect(socket_path).await.is_ok() {
+            error!(
+                "Not starting the control socket: another cctl is listening on {}",
+                socket_path.display()
+            );
+
+            return;
+        }
+
+        if let Err(error) = std::fs::remove_file(socket_path) {
+            error!("Could not remove {}: {}", socket_path.display(), error);
+
+            return;
+        }
+    }
+
+    let listener = match UnixListener::bind(socket_path) {
+        Ok(listener) => listener,
+        Err(error) => {
+            error!("Could not bind {}: {}", socket_path.display(), error);
+
+            return;
+        }
+    };
+
+    if let Err(error) = std::fs::set_permissions(socket_path, Permissions::from_mode(0o600)) {
+        error!(
+            "Could not restrict the permissions of {}: {}",
+            socket_path.display(),
+            error
+        );
+
+        return;
+    }
+
+    info!("Listening on {}", socket_path.display());
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle_connection(stream, pubsub_tx.clone()));
+            }
+            Err(error) => error!("Could not accept a control socket connection: {}", error),
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe_topics(
+        &pubsub_tx,
+        &[MessageTopic::AudioState, MessageTopic::Recording],
+        "control_socket",
+    )
+    .await;
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    _ => return,
+                };
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                debug!("Executing a command from the control socket");
+
+                let event = command::execute_json(&line, true, pubsub_tx.clone()).await;
+
+                if write_event(&mut writer, &event).await.is_err() {
+                    return;
+                }
+            }
+            Some(message) = rx.recv() => {
+                if let Some(event) = Event::from_message(&message) {
+                    if write_event(&mut writer, &event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn write_event(writer: &mut OwnedWriteHalf, event: &Event) -> io::Result<()> {
+    writer.write_all(event.to_json().as_bytes()).await?;
+    writer.write_all(b"\n").await
+}
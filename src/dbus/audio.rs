@@ -0,0 +1,198 @@
+use std::{default::default, sync::Arc};
+
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, instrument};
+use zbus::{dbus_interface, fdo, Connection, ConnectionBuilder, SignalContext};
+
+use crate::{
+    device::{card_device::CardDevice, card_device_type::CardDeviceType},
+    protocol::command::{self, Command},
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+};
+
+const BUS_NAME: &str = "org.cctl.Audio";
+const OBJECT_PATH: &str = "/org/cctl/Audio";
+
+type DeviceProperty = (u64, String, String, f64, bool, bool);
+type CardProperty = (u64, String, String, String);
+
+struct AudioInterface {
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+    state: MessageState,
+}
+
+#[dbus_interface(name = "org.cctl.Audio")]
+impl AudioInterface {
+    async fn set_volume(&self, _type: &str, index: u64, volume: f64) -> fdo::Result<()> {
+        self.execute_command(Command::SetVolume {
+            _type: parse_type(_type)?,
+            index,
+            volume,
+        })
+        .await
+    }
+
+    async fn set_mute(&self, _type: &str, index: u64, mute: bool) -> fdo::Result<()> {
+        self.execute_command(Command::SetMute {
+            _type: parse_type(_type)?,
+            index,
+            mute,
+        })
+        .await
+    }
+
+    async fn set_default(&self, _type: &str, index: u64, name: String) -> fdo::Result<()> {
+        self.execute_command(Command::SetDefault {
+            _type: parse_type(_type)?,
+            index,
+            name,
+        })
+        .await
+    }
+
+    async fn set_profile(&self, index: u64, profile: &str) -> fdo::Result<()> {
+        self.execute_command(Command::SetProfile {
+            index,
+            profile: profile.into(),
+        })
+        .await
+    }
+
+    async fn move_client(&self, _type: &str, index: u64, name: String) -> fdo::Result<()> {
+        self.execute_command(Command::MoveClient {
+            _type: parse_type(_type)?,
+            index,
+            name,
+        })
+        .await
+    }
+
+    async fn execute(&self, command: &str) -> String {
+        command::execute_json(command, true, self.pubsub_tx.clone())
+            .await
+            .to_json()
+    }
+
+    #[dbus_interface(property)]
+    fn cards(&self) -> Vec<CardProperty> {
+        self.state
+            .cards()
+            .iter()
+            .map(|card| {
+                (
+                    card.index,
+                    card.name.clone(),
+                    card.description.clone(),
+                    card.active_profile.as_parsed().to_owned(),
+                )
+            })
+            .collect()
+    }
+
+    #[dbus_interface(property)]
+    fn sources(&self) -> Vec<DeviceProperty> {
+        self.state
+            .sources()
+            .iter()
+            .map(to_device_property)
+            .collect()
+    }
+
+    #[dbus_interface(property)]
+    fn sinks(&self) -> Vec<DeviceProperty> {
+        self.state.sinks().iter().map(to_device_property).collect()
+    }
+
+    #[dbus_interface(signal)]
+    async fn state_changed(ctxt: &SignalContext<'_>, timestamp: u64) -> zbus::Result<()>;
+}
+
+impl AudioInterface {
+    async fn execute_command(&self, command: Command) -> fdo::Result<()> {
+        command::execute(command, self.pubsub_tx.clone())
+            .await
+            .map(|_| ())
+            .map_err(|error| fdo::Error::Failed(error.to_string()))
+    }
+}
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "dbus").await;
+
+    let interface = AudioInterface {
+        pubsub_tx: pubsub_tx.clone(),
+        state: default(),
+    };
+
+    let connection = match connect(interface).await {
+        Ok(connection) => connection,
+        Err(error) => {
+            error!(
+                "Could not register {} on the session bus: {}",
+                BUS_NAME, error
+            );
+
+            return;
+        }
+    };
+
+    info!("Registered {} on the session bus", BUS_NAME);
+
+    let interface = connection
+        .object_server()
+        .interface::<_, AudioInterface>(OBJECT_PATH)
+        .await
+        .unwrap();
+
+    loop {
+        if let Some(message) = rx.recv().await {
+            if let Some(state) = try_downcast_ref!(message, MessageState) {
+                let mut audio_interface = interface.get_mut().await;
+
+                audio_interface.state = state.clone();
+
+                if let Err(error) = emit_state(&audio_interface, interface.signal_context()).await {
+                    error!(
+                        "Could not emit the audio state on the session bus: {}",
+                        error
+                    );
+                }
+            }
+        }
+    }
+}
+
+async fn connect(interface: AudioInterface) -> zbus::Result<Connection> {
+    ConnectionBuilder::session()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, interface)?
+        .build()
+        .await
+}
+
+async fn emit_state(interface: &AudioInterface, ctxt: &SignalContext<'_>) -> zbus::Result<()> {
+    interface.cards_changed(ctxt).await?;
+    interface.sources_changed(ctxt).await?;
+    interface.sinks_changed(ctxt).await?;
+
+    AudioInterface::state_changed(ctxt, interface.state.timestamp() as u64).await
+}
+
+fn parse_type(_type: &str) -> fdo::Result<CardDeviceType> {
+    CardDeviceType::try_from(_type).map_err(fdo::Error::InvalidArgs)
+}
+
+fn to_device_property(card_device: &CardDevice) -> DeviceProperty {
+    (
+        card_device.index,
+        card_device.name.clone(),
+        card_device.description.clone(),
+        card_device.volume,
+        card_device.is_muted,
+        card_device.is_default,
+    )
+}
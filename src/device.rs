@@ -7,9 +7,19 @@ pub mod card_profile;
 
 mod a2dp_codec;
 mod bluetooth_protocol;
-mod bus;
+pub mod bus;
 mod card_parser;
 mod device_state;
-mod form_factor;
+pub mod equalizer;
+pub mod form_factor;
+pub mod ladspa;
+pub mod loopback;
+pub mod module;
+pub mod noise_suppression;
+pub mod pacat;
+pub mod pacmd;
+pub mod parec;
 mod parse_card_devices;
 mod parse_cards;
+pub mod simulated_server;
+pub mod virtual_device;
@@ -1,9 +1,11 @@
 use string_enum_string::string_enum_string;
 
+use schemars::JsonSchema_repr;
 use serde_repr::Serialize_repr;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Clone, Debug, Serialize_repr)]
+#[derive(Clone, Debug, JsonSchema_repr, Serialize_repr)]
+#[schemars(description = "1 = SBC, 2 = AAC, 3 = AptX, 4 = AptXHD, 5 = LDAC")]
 #[string_enum_string]
 #[repr(u8)]
 pub enum A2DPCodec {
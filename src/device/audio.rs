@@ -1,13 +1,8 @@
-use std::process::Stdio;
-
-use tokio::{
-    io::{self, AsyncReadExt},
-    process::Command,
-};
+use tokio::io;
 use tracing::{error, instrument, log::info};
 
 use crate::device::{
-    audio_client, parse_card_devices::parse_card_devices, parse_cards::parse_cards,
+    audio_client, pacmd, parse_card_devices::parse_card_devices, parse_cards::parse_cards,
 };
 
 use super::{
@@ -56,16 +51,9 @@ pub async fn set_volume(
     let volume = ((((volume_percentage * 65535.0 / 100.0) * 10.0).round() / 10.0).round() as u64)
         .to_string();
 
-    let mut command = Command::new("pacmd")
-        .args(&[subcommand, index.to_string().as_str(), &volume])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
+    let (success, _) = pacmd::run(&[subcommand, index.to_string().as_str(), &volume]).await?;
 
-    if !exit_status.success() {
+    if !success {
         error!(
             "Could not set the volume of {} index {} to {}%",
             _type, index, volume_percentage
@@ -84,16 +72,9 @@ pub async fn toggle_mute(_type: CardDeviceType, index: u64, mute: bool) -> io::R
 
     let mute_value = if mute { "true" } else { "false" };
 
-    let mut command = Command::new("pacmd")
-        .args(&[subcommand, index.to_string().as_str(), mute_value])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
+    let (success, _) = pacmd::run(&[subcommand, index.to_string().as_str(), mute_value]).await?;
 
-    if !exit_status.success() {
+    if !success {
         error!(
             "Could not set mute of {} index {} to mute status {}",
             _type, index, mute_value
@@ -110,16 +91,9 @@ pub async fn set_default_card_device(_type: CardDeviceType, index: u64) -> io::R
         CardDeviceType::Sink => "set-default-sink",
     };
 
-    let mut command = Command::new("pacmd")
-        .args(&[subcommand, index.to_string().as_str()])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
+    let (success, _) = pacmd::run(&[subcommand, index.to_string().as_str()]).await?;
 
-    if !exit_status.success() {
+    if !success {
         error!(
             "Could not set the {} index {} as the default {}",
             _type, index, _type
@@ -131,20 +105,14 @@ pub async fn set_default_card_device(_type: CardDeviceType, index: u64) -> io::R
 
 #[instrument]
 pub async fn set_card_profile(index: u64, profile: CardProfile) -> io::Result<()> {
-    let mut command = Command::new("pacmd")
-        .args(&[
-            "set-card-profile",
-            index.to_string().as_str(),
-            profile.as_parsed(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
-
-    if !exit_status.success() {
+    let (success, _) = pacmd::run(&[
+        "set-card-profile",
+        index.to_string().as_str(),
+        profile.as_parsed(),
+    ])
+    .await?;
+
+    if !success {
         error!(
             "Could not set the card index {} to profile {}",
             index,
@@ -175,53 +143,27 @@ pub async fn move_audio_clients(_type: CardDeviceType, index: u64, name: &str) -
 
 #[instrument]
 async fn fetch_cards() -> io::Result<Vec<Card>> {
-    let mut command = Command::new("pacmd")
-        .args(&["list-cards"])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
+    let (success, output) = pacmd::run(&["list-cards"]).await?;
 
-    if !exit_status.success() {
+    if !success {
         error!("Could not get card information");
     }
 
-    let mut output = String::new();
-
-    if let Some(mut stdout) = command.stdout.take() {
-        stdout.read_to_string(&mut output).await?;
-    }
-
     Ok(parse_cards(&output))
 }
 
 #[instrument]
-async fn fetch_card_devices(_type: CardDeviceType) -> io::Result<Vec<CardDevice>> {
+pub async fn fetch_card_devices(_type: CardDeviceType) -> io::Result<Vec<CardDevice>> {
     let argument = match _type {
         CardDeviceType::Source => "list-sources",
         CardDeviceType::Sink => "list-sinks",
     };
 
-    let mut command = Command::new("pacmd")
-        .args(&[argument])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .unwrap();
-
-    let exit_status = command.wait().await?;
+    let (success, output) = pacmd::run(&[argument]).await?;
 
-    if !exit_status.success() {
+    if !success {
         error!("Could not get {} information", _type);
     }
 
-    let mut output = String::new();
-
-    if let Some(mut stdout) = command.stdout.take() {
-        stdout.read_to_string(&mut output).await?;
-    }
-
     Ok(parse_card_devices(&output))
 }
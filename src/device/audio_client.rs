@@ -1,44 +1,80 @@
-use std::{default::default, process::Stdio};
+use std::default::default;
 
 use regex::Regex;
-use tokio::{
-    io::{self, AsyncReadExt},
-    process::Command,
-};
+use serde::Serialize;
+use tokio::io;
 use tracing::{error, instrument};
 
-use super::card_device_type::CardDeviceType;
+use crate::util::unquote_parsed_string_value;
+
+use super::{card_device_type::CardDeviceType, pacmd};
+
+pub const INTERNAL_APPLICATION_NAME: &str = "cctl";
+pub const LISTENER_APPLICATION_NAME: &str = "cctl listener";
+pub const RECORDER_APPLICATION_NAME: &str = "cctl recorder";
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AudioClient {
+    pub index: u64,
+    pub card_device_index: u64,
+    pub card_device_name: String,
+    pub client_name: Option<String>,
+    pub application_name: Option<String>,
+    pub process_id: Option<u32>,
+    pub module_index: Option<u64>,
+    pub volume: f64,
+    pub is_muted: bool,
+}
+
+impl AudioClient {
+    pub fn is_monitor(&self) -> bool {
+        self.card_device_name.ends_with(".monitor")
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.application_name.as_deref() == Some(INTERNAL_APPLICATION_NAME)
+    }
+
+    pub fn is_cctl(&self) -> bool {
+        matches!(
+            self.application_name.as_deref(),
+            Some(INTERNAL_APPLICATION_NAME | LISTENER_APPLICATION_NAME | RECORDER_APPLICATION_NAME)
+        )
+    }
+}
 
 #[instrument]
-pub async fn fetch_client_indexes(_type: CardDeviceType) -> io::Result<Vec<(u64, u64)>> {
+pub async fn fetch_clients(_type: CardDeviceType) -> io::Result<Vec<AudioClient>> {
     let arg = if _type == CardDeviceType::Source {
         "list-source-outputs"
     } else {
         "list-sink-inputs"
     };
 
-    let mut command = Command::new("pacmd")
-        .args(&[arg])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .unwrap();
+    let (success, output) = pacmd::run(&[arg]).await?;
 
-    let exit_status = command.wait().await?;
-
-    if !exit_status.success() {
-        error!("Could not get {} client ids", _type);
+    if !success {
+        error!("Could not get {} clients", _type);
 
         return Ok(default());
     }
 
-    let mut output = String::new();
-
-    if let Some(mut stdout) = command.stdout.take() {
-        stdout.read_to_string(&mut output).await?;
-    }
+    Ok(parse_clients(&output))
+}
 
-    Ok(parse_client_indexes(&output))
+#[instrument]
+pub async fn fetch_client_indexes(_type: CardDeviceType) -> io::Result<Vec<(u64, u64)>> {
+    Ok(fetch_clients(_type)
+        .await?
+        .into_iter()
+        .filter(|client| {
+            !client.is_monitor()
+                && !client.is_cctl()
+                && client.client_name.is_some()
+                && client.client_name.as_deref() != Some("PulseAudio Volume Control")
+        })
+        .map(|client| (client.index, client.card_device_index))
+        .collect())
 }
 
 pub async fn set_client_card_device(
@@ -52,16 +88,10 @@ pub async fn set_client_card_device(
         "move-sink-input"
     };
 
-    let mut command = Command::new("pacmd")
-        .args(&[arg, client_index.to_string().as_str(), card_device_name])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .unwrap();
+    let (success, _) =
+        pacmd::run(&[arg, client_index.to_string().as_str(), card_device_name]).await?;
 
-    let exit_status = command.wait().await?;
-
-    if !exit_status.success() {
+    if !success {
         error!(
             "Could not set client index {} to {} {}",
             client_index, _type, card_device_name
@@ -71,11 +101,53 @@ pub async fn set_client_card_device(
     Ok(())
 }
 
-fn parse_client_indexes(text: &str) -> Vec<(u64, u64)> {
-    let mut indexes: Vec<(u64, u64)> = Vec::new();
-    let mut current_index: u64 = 0;
-    let mut current_source_sink_index: u64 = 0;
-    let mut is_monitor = false;
+pub async fn set_client_mute(
+    client_index: u64,
+    _type: CardDeviceType,
+    mute: bool,
+) -> io::Result<()> {
+    let arg = if _type == CardDeviceType::Source {
+        "set-source-output-mute"
+    } else {
+        "set-sink-input-mute"
+    };
+
+    let (success, _) = pacmd::run(&[
+        arg,
+        client_index.to_string().as_str(),
+        if mute { "1" } else { "0" },
+    ])
+    .await?;
+
+    if !success {
+        error!(
+            "Could not set the mute state of {} client index {} to {}",
+            _type, client_index, mute
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn kill_client(client_index: u64, _type: CardDeviceType) -> io::Result<()> {
+    let arg = if _type == CardDeviceType::Source {
+        "kill-source-output"
+    } else {
+        "kill-sink-input"
+    };
+
+    let (success, _) = pacmd::run(&[arg, client_index.to_string().as_str()]).await?;
+
+    if !success {
+        error!("Could not kill {} client index {}", _type, client_index);
+    }
+
+    Ok(())
+}
+
+fn parse_clients(text: &str) -> Vec<AudioClient> {
+    let mut clients: Vec<AudioClient> = Vec::new();
+    let mut current_client: Option<AudioClient> = None;
 
     text.lines().map(|line| line.trim()).for_each(|line| {
         let captures = Regex::new(r"(?P<key>(?:\*\s*)?[^:=]+?)\s*[:=]\s*(?P<value>.+$)")
@@ -83,50 +155,97 @@ fn parse_client_indexes(text: &str) -> Vec<(u64, u64)> {
             .captures(line);
 
         if let Some(captures) = captures {
+            let value = captures.name("value").unwrap().as_str();
+
             match captures.name("key").unwrap().as_str() {
                 "index" => {
-                    current_index = captures.name("value").unwrap().as_str().parse().unwrap();
+                    if current_client.is_some() {
+                        clients.push(current_client.take().unwrap());
+                    }
+
+                    current_client.replace(AudioClient {
+                        index: value.parse().unwrap(),
+                        ..default()
+                    });
                 }
                 "source" | "sink" => {
-                    let source_sink_captures =
-                        Regex::new(r"\s*(?P<index>[0-9]+)\s*<(?P<name>[^>]+)>")
+                    if let Some(ref mut current) = current_client {
+                        let source_sink_captures =
+                            Regex::new(r"\s*(?P<index>[0-9]+)\s*<(?P<name>[^>]+)>")
+                                .unwrap()
+                                .captures(value)
+                                .unwrap();
+
+                        current.card_device_index = source_sink_captures
+                            .name("index")
                             .unwrap()
-                            .captures(captures.name("value").unwrap().as_str())
+                            .as_str()
+                            .parse()
                             .unwrap();
 
-                    current_source_sink_index = source_sink_captures
-                        .name("index")
-                        .unwrap()
-                        .as_str()
-                        .parse()
-                        .unwrap();
-
-                    let source_sink_name: &str =
-                        source_sink_captures.name("name").unwrap().as_str();
-
-                    is_monitor = source_sink_name.ends_with(".monitor");
+                        current.card_device_name = source_sink_captures
+                            .name("name")
+                            .unwrap()
+                            .as_str()
+                            .to_owned();
+                    }
                 }
                 "client" => {
-                    if is_monitor {
-                        return;
-                    }
-
-                    let client_captures = Regex::new(r"\s*(?:[0-9]+)\s*<(?P<name>[^>]+)>")
-                        .unwrap()
-                        .captures(captures.name("value").unwrap().as_str())
-                        .unwrap();
+                    if let Some(ref mut current) = current_client {
+                        let client_captures = Regex::new(r"\s*(?:[0-9]+)\s*<(?P<name>[^>]+)>")
+                            .unwrap()
+                            .captures(value)
+                            .unwrap();
 
-                    if client_captures.name("name").unwrap().as_str() == "PulseAudio Volume Control"
-                    {
-                        return;
+                        current.client_name =
+                            Some(client_captures.name("name").unwrap().as_str().to_owned());
+                    }
+                }
+                "module" => {
+                    if let Some(ref mut current) = current_client {
+                        current.module_index = value.parse().ok();
+                    }
+                }
+                "volume" => {
+                    if let Some(ref mut current) = current_client {
+                        if let Some(volume_captures) = Regex::new(r"^[^:]+:\s*(?P<volume>[0-9]+).*")
+                            .unwrap()
+                            .captures(value)
+                        {
+                            let volume: f64 = volume_captures
+                                .name("volume")
+                                .unwrap()
+                                .as_str()
+                                .parse()
+                                .unwrap();
+
+                            current.volume = ((volume / 65535.0) * 100.0).round();
+                        }
+                    }
+                }
+                "muted" => {
+                    if let Some(ref mut current) = current_client {
+                        current.is_muted = value == "yes";
+                    }
+                }
+                "application.name" => {
+                    if let Some(ref mut current) = current_client {
+                        current.application_name = Some(unquote_parsed_string_value(value));
+                    }
+                }
+                "application.process.id" => {
+                    if let Some(ref mut current) = current_client {
+                        current.process_id = unquote_parsed_string_value(value).parse().ok();
                     }
-
-                    indexes.push((current_index, current_source_sink_index));
                 }
                 _ => (),
             }
         }
     });
 
-    indexes
+    if current_client.is_some() {
+        clients.push(current_client.take().unwrap());
+    }
+
+    clients
 }
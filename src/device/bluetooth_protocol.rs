@@ -1,8 +1,10 @@
 use string_enum_string::string_enum_string;
 
+use schemars::JsonSchema_repr;
 use serde_repr::Serialize_repr;
 
-#[derive(Clone, Debug, Serialize_repr)]
+#[derive(Clone, Debug, JsonSchema_repr, Serialize_repr)]
+#[schemars(description = "1 = HeadsetHeadUnit, 2 = A2DPSink")]
 #[repr(u8)]
 #[string_enum_string]
 pub enum BluetoothProtocol {
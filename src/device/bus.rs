@@ -1,9 +1,11 @@
 use string_enum_string::string_enum_string;
 
+use schemars::JsonSchema_repr;
 use serde_repr::Serialize_repr;
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Clone, Debug, PartialEq, Serialize_repr)]
+#[derive(Clone, Debug, JsonSchema_repr, PartialEq, Serialize_repr)]
+#[schemars(description = "1 = PCI, 2 = Bluetooth, 3 = USB")]
 #[repr(u8)]
 #[string_enum_string]
 pub enum Bus {
@@ -1,10 +1,11 @@
 use std::default::default;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::{bus::Bus, card_profile::CardProfile, form_factor::FormFactor};
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, JsonSchema, Serialize)]
 pub struct Card {
     pub index: u64,
     pub name: String,
@@ -1,5 +1,6 @@
 use std::default::default;
 
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use super::{
@@ -7,7 +8,18 @@ use super::{
     device_state::DeviceState, form_factor::FormFactor,
 };
 
-#[derive(Clone, Debug, Serialize)]
+const VIRTUAL_DRIVERS: [&str; 8] = [
+    "module-null-sink.c",
+    "module-combine-sink.c",
+    "module-remap-source.c",
+    "module-remap-sink.c",
+    "module-virtual-source.c",
+    "module-virtual-sink.c",
+    "module-echo-cancel.c",
+    "module-ladspa-sink.c",
+];
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
 pub struct CardDevice {
     pub index: u64,
     pub name: String,
@@ -22,6 +34,12 @@ pub struct CardDevice {
     pub a2dp_codec: Option<A2DPCodec>,
     pub form_factor: FormFactor,
     pub bus: Bus,
+    /// Whether the device is provided by a module, such as a null sink, instead of by a card
+    pub is_virtual: bool,
+    /// The channel positions, such as `front-left`
+    pub channel_map: Vec<String>,
+    /// The module owning the device
+    pub module_index: Option<u64>,
 }
 
 impl Default for CardDevice {
@@ -40,6 +58,13 @@ impl Default for CardDevice {
             a2dp_codec: default(),
             form_factor: default(),
             bus: default(),
+            is_virtual: default(),
+            channel_map: default(),
+            module_index: default(),
         }
     }
 }
+
+pub fn is_virtual_driver(driver: &str) -> bool {
+    VIRTUAL_DRIVERS.contains(&driver)
+}
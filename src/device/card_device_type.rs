@@ -1,10 +1,25 @@
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub enum CardDeviceType {
-    #[serde(alias = "source")]
+    #[serde(rename(serialize = "source"), alias = "source")]
+    #[schemars(rename = "source")]
     Source,
 
-    #[serde(alias = "sink")]
+    #[serde(rename(serialize = "sink"), alias = "sink")]
+    #[schemars(rename = "sink")]
     Sink,
 }
+
+impl TryFrom<&str> for CardDeviceType {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "Source" | "source" => Ok(CardDeviceType::Source),
+            "Sink" | "sink" => Ok(CardDeviceType::Sink),
+            _ => Err(format!("Unknown card device type {}", value)),
+        }
+    }
+}
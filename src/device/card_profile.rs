@@ -1,9 +1,13 @@
 use string_enum_string::string_enum_string;
 
+use schemars::JsonSchema_repr;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
 #[string_enum_string]
-#[derive(Clone, Debug, Deserialize_repr, Serialize_repr)]
+#[derive(Clone, Debug, Deserialize_repr, JsonSchema_repr, PartialEq, Serialize_repr)]
+#[schemars(
+    description = "1 = HeadsetHeadUnit, 2 = A2DPSinkSBC, 3 = A2DPSinkAAC, 4 = A2DPSinkAptX, 5 = A2DPSinkAptXHD, 6 = A2DPSinkLDAC, 7 = Off"
+)]
 #[repr(u8)]
 pub enum CardProfile {
     #[variant((display = "Headset Head Unit (HSP/HFP)", parse = "headset_head_unit"))]
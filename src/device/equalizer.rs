@@ -0,0 +1,168 @@
+use std::{f64::consts::LN_2, path::PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{self, ErrorKind};
+use tracing::instrument;
+
+use super::{card_device::CardDevice, ladspa, module};
+
+const PLUGIN_FILE_NAME: &str = "single_para_1203.so";
+const PLUGIN_LABEL: &str = "singlePara";
+const MAX_BANDS: usize = 10;
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[schemars(rename = "EqualizerBand")]
+pub struct Band {
+    /// Between 20 and 20000 Hz
+    pub frequency: f64,
+    /// Between -30 and 30 dB
+    pub gain_db: f64,
+    /// Between 0.3 and 20
+    pub q: f64,
+}
+
+impl Band {
+    fn bandwidth_octaves(&self) -> f64 {
+        2.0 / LN_2 * (1.0 / (2.0 * self.q)).asinh()
+    }
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct EqualizerChain {
+    pub sink_name: String,
+    pub equalized_sink_name: String,
+    pub preset: String,
+    pub bands: Vec<Band>,
+    /// The modules making up the chain, from the sink to the equalized sink
+    pub module_indexes: Vec<u64>,
+}
+
+pub fn equalized_sink_name(sink_name: &str) -> String {
+    format!("{}.equalized", sink_name)
+}
+
+pub fn plugin() -> Option<PathBuf> {
+    ladspa::find_plugin(PLUGIN_FILE_NAME)
+}
+
+pub fn validate_bands(bands: &[Band]) -> io::Result<()> {
+    if bands.is_empty() || bands.len() > MAX_BANDS {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("An equalizer has between 1 and {} bands", MAX_BANDS),
+        ));
+    }
+
+    for band in bands {
+        if !(20.0..=20000.0).contains(&band.frequency) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The frequency {} is not between 20 and 20000",
+                    band.frequency
+                ),
+            ));
+        }
+
+        if !(-30.0..=30.0).contains(&band.gain_db) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The gain {}dB is not between -30 and 30", band.gain_db),
+            ));
+        }
+
+        if !(0.3..=20.0).contains(&band.q) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("The Q {} is not between 0.3 and 20", band.q),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[instrument]
+pub async fn create_chain(
+    sink: &CardDevice,
+    preset: &str,
+    bands: &[Band],
+) -> io::Result<EqualizerChain> {
+    let plugin = plugin().ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::NotFound,
+            format!("The LADSPA plugin {} is not installed", PLUGIN_FILE_NAME),
+        )
+    })?;
+
+    validate_bands(bands)?;
+
+    let equalized_sink_name = equalized_sink_name(&sink.name);
+
+    if module::fetch_modules().await?.iter().any(|module| {
+        module.argument_value("sink_name").as_deref() == Some(equalized_sink_name.as_str())
+    }) {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("Sink {} is already equalized", sink.name),
+        ));
+    }
+
+    let description = module::sanitize_property(&sink.description);
+
+    let mut chain = EqualizerChain {
+        sink_name: sink.name.clone(),
+        equalized_sink_name,
+        preset: preset.to_owned(),
+        bands: bands.to_vec(),
+        module_indexes: Vec::new(),
+    };
+
+    let mut master = sink.name.clone();
+
+    for (i, band) in bands.iter().enumerate() {
+        let (sink_name, sink_description) = if i == bands.len() - 1 {
+            (
+                chain.equalized_sink_name.clone(),
+                format!("{} (equalized)", description),
+            )
+        } else {
+            (
+                format!("{}.equalizer_band{}", sink.name, i + 1),
+                format!("{} (equalizer band {})", description, i + 1),
+            )
+        };
+
+        let argument = format!(
+            "sink_name={} sink_master={} plugin={} label={} control={},{},{} \
+                sink_properties=\"device.description='{}'\"",
+            sink_name,
+            master,
+            plugin.display(),
+            PLUGIN_LABEL,
+            band.gain_db,
+            band.frequency,
+            band.bandwidth_octaves(),
+            sink_description
+        );
+
+        match module::load_module("module-ladspa-sink", &argument).await {
+            Ok(index) => chain.module_indexes.push(index),
+            Err(error) => {
+                remove_chain(&chain).await;
+
+                return Err(error);
+            }
+        }
+
+        master = sink_name;
+    }
+
+    Ok(chain)
+}
+
+#[instrument]
+pub async fn remove_chain(chain: &EqualizerChain) {
+    module::unload_loaded_modules(&chain.module_indexes).await;
+}
@@ -1,8 +1,10 @@
 use string_enum_string::string_enum_string;
 
+use schemars::JsonSchema_repr;
 use serde_repr::Serialize_repr;
 
-#[derive(Clone, Debug, Serialize_repr)]
+#[derive(Clone, Debug, JsonSchema_repr, PartialEq, Serialize_repr)]
+#[schemars(description = "1 = Internal, 2 = Headphones, 3 = Webcam, 4 = Headset")]
 #[repr(u8)]
 #[string_enum_string]
 pub enum FormFactor {
@@ -0,0 +1,18 @@
+use std::{env, path::PathBuf};
+
+const LADSPA_DIRS: [&str; 4] = [
+    "/usr/lib/ladspa",
+    "/usr/lib64/ladspa",
+    "/usr/local/lib/ladspa",
+    "/usr/lib/x86_64-linux-gnu/ladspa",
+];
+
+pub fn find_plugin(file_name: &str) -> Option<PathBuf> {
+    env::var("LADSPA_PATH")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .chain(LADSPA_DIRS)
+        .map(|dir| PathBuf::from(dir).join(file_name))
+        .find(|path| path.exists())
+}
@@ -0,0 +1,92 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::io::{self, ErrorKind};
+use tracing::instrument;
+
+use super::{audio, audio_client::INTERNAL_APPLICATION_NAME, module};
+
+const MODULE_NAME: &str = "module-loopback";
+pub const DEFAULT_LATENCY_MSEC: u32 = 50;
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Loopback {
+    pub module_index: u64,
+    pub source_name: Option<String>,
+    pub sink_name: Option<String>,
+    pub latency_msec: Option<u32>,
+    /// Whether cctl loaded the loopback, in which case it is removed when cctl exits
+    pub is_managed: bool,
+}
+
+#[instrument]
+pub async fn fetch_loopbacks() -> io::Result<Vec<Loopback>> {
+    Ok(module::fetch_modules()
+        .await?
+        .into_iter()
+        .filter(|module| module.name == MODULE_NAME)
+        .map(|module| Loopback {
+            module_index: module.index,
+            source_name: module.argument_value("source"),
+            sink_name: module.argument_value("sink"),
+            latency_msec: module
+                .argument_value("latency_msec")
+                .and_then(|latency| latency.parse().ok()),
+            is_managed: module.is_managed,
+        })
+        .collect())
+}
+
+#[instrument]
+pub async fn add_loopback(
+    source_name: &str,
+    sink_name: &str,
+    latency_msec: u32,
+) -> io::Result<u64> {
+    module::validate_argument_value("source name", source_name)?;
+    module::validate_argument_value("sink name", sink_name)?;
+
+    let (_, sources, sinks) = audio::fetch_devices().await;
+
+    let is_source = sources.iter().any(|source| source.name == source_name)
+        || source_name
+            .strip_suffix(".monitor")
+            .map_or(false, |name| sinks.iter().any(|sink| sink.name == name));
+
+    if !is_source {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("Source {} does not exist", source_name),
+        ));
+    }
+
+    if !sinks.iter().any(|sink| sink.name == sink_name) {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("Sink {} does not exist", sink_name),
+        ));
+    }
+
+    let argument = format!(
+        "source={} sink={} latency_msec={} source_dont_move=true sink_dont_move=true \
+            sink_input_properties=application.name={} source_output_properties=application.name={}",
+        source_name, sink_name, latency_msec, INTERNAL_APPLICATION_NAME, INTERNAL_APPLICATION_NAME
+    );
+
+    module::load_module(MODULE_NAME, &argument).await
+}
+
+#[instrument]
+pub async fn remove_loopback(module_index: u64) -> io::Result<()> {
+    if !fetch_loopbacks()
+        .await?
+        .iter()
+        .any(|loopback| loopback.module_index == module_index)
+    {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("Module index {} is not a loopback", module_index),
+        ));
+    }
+
+    module::unload_module(module_index).await
+}
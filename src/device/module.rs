@@ -0,0 +1,243 @@
+use std::{collections::HashSet, default::default, sync::Mutex as StdMutex};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::io::{self, ErrorKind};
+use tracing::{error, info, instrument};
+
+use super::pacmd;
+
+static MANAGED: Lazy<StdMutex<HashSet<u64>>> = Lazy::new(|| StdMutex::new(HashSet::new()));
+
+#[derive(Clone, Debug, Default, JsonSchema, Serialize)]
+pub struct Module {
+    pub index: u64,
+    pub name: String,
+    pub argument: String,
+    /// Whether cctl loaded the module, in which case it is unloaded when cctl exits
+    pub is_managed: bool,
+}
+
+impl Module {
+    pub fn arguments(&self) -> Vec<(String, String)> {
+        Regex::new(
+            r#"(?P<key>[^\s=]+)=(?:"(?P<quoted>[^"]*)"|'(?P<single_quoted>[^']*)'|(?P<value>\S*))"#,
+        )
+        .unwrap()
+        .captures_iter(&self.argument)
+        .map(|captures| {
+            let value = captures
+                .name("quoted")
+                .or_else(|| captures.name("single_quoted"))
+                .or_else(|| captures.name("value"))
+                .unwrap()
+                .as_str();
+
+            (captures["key"].to_owned(), value.to_owned())
+        })
+        .collect()
+    }
+
+    pub fn argument_value(&self, key: &str) -> Option<String> {
+        self.arguments()
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[instrument]
+pub async fn fetch_modules() -> io::Result<Vec<Module>> {
+    let (success, output) = pacmd::run(&["list-modules"]).await?;
+
+    if !success {
+        error!("Could not get module information");
+
+        return Ok(default());
+    }
+
+    Ok(parse_modules(&output))
+}
+
+pub fn validate_argument_value(key: &str, value: &str) -> io::Result<()> {
+    if value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '\'')
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The {} {:?} must not be empty or contain whitespace, control characters or quotes",
+                key, value
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn sanitize_property(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"' && *c != '\'')
+        .collect()
+}
+
+fn validate_argument(argument: &str) -> io::Result<()> {
+    let argument_regex =
+        Regex::new(r#"^\s*(?:[^\s="']+=(?:"[^"]*"|'[^']*'|[^\s"']*)(?:\s+|$))*$"#).unwrap();
+
+    if argument.chars().any(char::is_control) || !argument_regex.is_match(argument) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The module argument {:?} is not a list of key=value pairs",
+                argument
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[instrument]
+pub async fn load_module(name: &str, argument: &str) -> io::Result<u64> {
+    validate_argument_value("module name", name)?;
+    validate_argument(argument)?;
+
+    let (success, output) = pacmd::run(&["load-module", name, argument]).await?;
+
+    if !success || output.contains("failed") {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Could not load {} {}", name, argument),
+        ));
+    }
+
+    let index = fetch_modules()
+        .await?
+        .into_iter()
+        .filter(|module| module.name == name && module.argument == argument)
+        .map(|module| module.index)
+        .max()
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::Other,
+                format!("Could not find the loaded {} {}", name, argument),
+            )
+        })?;
+
+    MANAGED.lock().unwrap().insert(index);
+
+    info!("Loaded module {} {} as index {}", name, argument, index);
+
+    Ok(index)
+}
+
+#[instrument]
+pub async fn unload_module(index: u64) -> io::Result<()> {
+    let (success, output) = pacmd::run(&["unload-module", index.to_string().as_str()]).await?;
+
+    if !success || output.contains("failed") {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("Could not unload module index {}", index),
+        ));
+    }
+
+    MANAGED.lock().unwrap().remove(&index);
+
+    info!("Unloaded module index {}", index);
+
+    Ok(())
+}
+
+pub async fn unload_loaded_modules(indexes: &[u64]) {
+    let loaded_indexes: Vec<u64> = match fetch_modules().await {
+        Ok(modules) => modules.iter().map(|module| module.index).collect(),
+        Err(error) => {
+            error!("Could not fetch the modules: {}", error);
+
+            return;
+        }
+    };
+
+    for index in indexes.iter().rev() {
+        if !loaded_indexes.contains(index) {
+            MANAGED.lock().unwrap().remove(index);
+
+            continue;
+        }
+
+        if let Err(error) = unload_module(*index).await {
+            error!("{}", error);
+        }
+    }
+}
+
+pub fn is_managed(index: u64) -> bool {
+    MANAGED.lock().unwrap().contains(&index)
+}
+
+pub async fn unload_managed_modules() {
+    let indexes: Vec<u64> = MANAGED.lock().unwrap().iter().cloned().collect();
+
+    for index in indexes {
+        if let Err(error) = unload_module(index).await {
+            error!("{}", error);
+        }
+    }
+}
+
+fn parse_modules(text: &str) -> Vec<Module> {
+    let mut modules: Vec<Module> = Vec::new();
+    let mut current_module: Option<Module> = None;
+
+    let line_regex = Regex::new(r"^(?P<key>index|name|argument):\s*(?P<value>.*)$").unwrap();
+    let bracketed_regex = Regex::new(r"^<(?P<value>.*)>$").unwrap();
+
+    text.lines().map(|line| line.trim()).for_each(|line| {
+        if let Some(captures) = line_regex.captures(line) {
+            let value = captures.name("value").unwrap().as_str();
+            let value = bracketed_regex
+                .captures(value)
+                .map_or(value, |c| c.name("value").unwrap().as_str());
+
+            match captures.name("key").unwrap().as_str() {
+                "index" => {
+                    if current_module.is_some() {
+                        modules.push(current_module.take().unwrap());
+                    }
+
+                    let index = value.parse().unwrap();
+
+                    current_module.replace(Module {
+                        index,
+                        is_managed: is_managed(index),
+                        ..default()
+                    });
+                }
+                "name" => {
+                    if let Some(ref mut current) = current_module {
+                        current.name = value.to_owned();
+                    }
+                }
+                "argument" => {
+                    if let Some(ref mut current) = current_module {
+                        current.argument = value.to_owned();
+                    }
+                }
+                _ => (),
+            }
+        }
+    });
+
+    if current_module.is_some() {
+        modules.push(current_module.take().unwrap());
+    }
+
+    modules
+}
@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::io::{self, ErrorKind};
+use tracing::instrument;
+
+use super::{audio_client::INTERNAL_APPLICATION_NAME, card_device::CardDevice, ladspa, module};
+
+const RNNOISE_PLUGIN_FILE_NAME: &str = "librnnoise_ladspa.so";
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+#[schemars(rename = "NoiseSuppressionChain")]
+pub struct Chain {
+    pub source_name: String,
+    pub processed_source_name: String,
+    /// The sink to play on for its sound to be cancelled from the processed source
+    pub echo_cancel_sink_name: String,
+    pub rnnoise: bool,
+    /// The modules making up the chain, from the source to the processed source
+    pub module_indexes: Vec<u64>,
+}
+
+pub fn processed_source_name(source_name: &str) -> String {
+    format!("{}.processed", source_name)
+}
+
+pub fn rnnoise_plugin() -> Option<PathBuf> {
+    ladspa::find_plugin(RNNOISE_PLUGIN_FILE_NAME)
+}
+
+#[instrument]
+pub async fn create_chain(source: &CardDevice) -> io::Result<Chain> {
+    let processed_source_name = processed_source_name(&source.name);
+
+    if module::fetch_modules().await?.iter().any(|module| {
+        module.argument_value("source_name").as_deref() == Some(processed_source_name.as_str())
+    }) {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("Source {} is already processed", source.name),
+        ));
+    }
+
+    let rnnoise_plugin = rnnoise_plugin();
+    let description = module::sanitize_property(&source.description);
+
+    let (echo_cancel_source_name, echo_cancel_description) = if rnnoise_plugin.is_some() {
+        (
+            format!("{}.echo_cancel", source.name),
+            format!("{} (echo cancelled)", description),
+        )
+    } else {
+        (
+            processed_source_name.clone(),
+            format!("{} (processed)", description),
+        )
+    };
+    let echo_cancel_sink_name = format!("{}.echo_cancel_sink", source.name);
+
+    let mut chain = Chain {
+        source_name: source.name.clone(),
+        processed_source_name,
+        echo_cancel_sink_name,
+        rnnoise: rnnoise_plugin.is_some(),
+        module_indexes: Vec::new(),
+    };
+
+    let mut modules = vec![(
+        "module-echo-cancel",
+        format!(
+            "source_master={} source_name={} sink_name={} aec_method=webrtc \
+                aec_args=\"analog_gain_control=0 digital_gain_control=1 noise_suppression=1\" \
+                use_master_format=1 source_properties=\"device.description='{}'\" \
+                sink_properties=\"device.description='{} (echo cancellation)'\"",
+            source.name,
+            echo_cancel_source_name,
+            chain.echo_cancel_sink_name,
+            echo_cancel_description,
+            description
+        ),
+    )];
+
+    if let Some(rnnoise_plugin) = rnnoise_plugin {
+        let denoised_sink_name = format!("{}.denoised", source.name);
+        let rnnoise_sink_name = format!("{}.rnnoise", source.name);
+
+        modules.extend([
+            (
+                "module-null-sink",
+                format!(
+                    "sink_name={} rate=48000 sink_properties=\"device.description='{} (denoised)'\"",
+                    denoised_sink_name, description
+                ),
+            ),
+            (
+                "module-ladspa-sink",
+                format!(
+                    "sink_name={} sink_master={} plugin={} label=noise_suppressor_mono \
+                        sink_properties=\"device.description='{} (RNNoise)'\"",
+                    rnnoise_sink_name,
+                    denoised_sink_name,
+                    rnnoise_plugin.display(),
+                    description
+                ),
+            ),
+            (
+                "module-loopback",
+                format!(
+                    "source={} sink={} channels=1 source_dont_move=true sink_dont_move=true \
+                        sink_input_properties=application.name={} \
+                        source_output_properties=application.name={}",
+                    echo_cancel_source_name,
+                    rnnoise_sink_name,
+                    INTERNAL_APPLICATION_NAME,
+                    INTERNAL_APPLICATION_NAME
+                ),
+            ),
+            (
+                "module-remap-source",
+                format!(
+                    "master={}.monitor source_name={} \
+                        source_properties=\"device.description='{} (processed)'\"",
+                    denoised_sink_name, chain.processed_source_name, description
+                ),
+            ),
+        ]);
+    }
+
+    for (name, argument) in modules {
+        match module::load_module(name, &argument).await {
+            Ok(index) => chain.module_indexes.push(index),
+            Err(error) => {
+                remove_chain(&chain).await;
+
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(chain)
+}
+
+#[instrument]
+pub async fn remove_chain(chain: &Chain) {
+    module::unload_loaded_modules(&chain.module_indexes).await;
+}
@@ -0,0 +1,105 @@
+use std::{path::Path, process::Stdio, time::Duration};
+
+use tokio::{
+    io::{self, AsyncWriteExt, ErrorKind},
+    process::{Child, Command},
+    time::sleep,
+};
+
+use super::{audio_client::INTERNAL_APPLICATION_NAME, simulated_server};
+
+pub async fn play(
+    card_device_name: &str,
+    channels: u8,
+    rate: u32,
+    channel_map: Option<&str>,
+    data: &[u8],
+) -> io::Result<()> {
+    if simulated_server::is_enabled() {
+        sleep(Duration::from_secs_f64(
+            data.len() as f64 / (2.0 * channels as f64 * rate as f64),
+        ))
+        .await;
+
+        return Ok(());
+    }
+
+    let mut command = Command::new("pacat");
+
+    command
+        .arg("--playback")
+        .arg(format!("--device={}", card_device_name))
+        .arg("--raw")
+        .arg("--format=s16le")
+        .arg(format!("--rate={}", rate))
+        .arg(format!("--channels={}", channels))
+        .arg("--no-move")
+        .arg(format!(
+            "--property=application.name={}",
+            INTERNAL_APPLICATION_NAME
+        ));
+
+    if let Some(channel_map) = channel_map {
+        command.arg(format!("--channel-map={}", channel_map));
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().unwrap();
+
+    stdin.write_all(data).await?;
+
+    drop(stdin);
+
+    let status = child.wait().await?;
+
+    if !status.success() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            format!("Could not play on {}", card_device_name),
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn spawn_file(card_device_name: Option<&str>, path: &Path, volume: f64) -> io::Result<Child> {
+    if simulated_server::is_enabled() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            "Playing files is not available in simulation mode",
+        ));
+    }
+
+    let mut command = Command::new("pacat");
+
+    command
+        .arg("--playback")
+        .arg("--file-format")
+        .arg(format!(
+            "--volume={}",
+            (volume / 100.0 * 65536.0).round() as u32
+        ))
+        .arg("--no-move")
+        .arg(format!(
+            "--property=application.name={}",
+            INTERNAL_APPLICATION_NAME
+        ));
+
+    if let Some(card_device_name) = card_device_name {
+        command.arg(format!("--device={}", card_device_name));
+    }
+
+    command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+}
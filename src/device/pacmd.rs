@@ -0,0 +1,50 @@
+use std::{
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+use once_cell::sync::OnceCell;
+use tokio::{io, process::Command};
+
+use super::simulated_server;
+
+type Observer = fn(&str, Duration, bool);
+
+static OBSERVER: OnceCell<Observer> = OnceCell::new();
+
+pub fn set_observer(observer: Observer) {
+    OBSERVER.set(observer).ok();
+}
+
+pub async fn run(args: &[&str]) -> io::Result<(bool, String)> {
+    let started_at = Instant::now();
+
+    if simulated_server::is_enabled() {
+        let (success, output) = simulated_server::run(args);
+
+        observe(args[0], started_at, success);
+
+        return Ok((success, output));
+    }
+
+    let output = Command::new("pacmd")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+
+    let success = output.status.success();
+
+    observe(args[0], started_at, success);
+
+    Ok((
+        success,
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    ))
+}
+
+fn observe(subcommand: &str, started_at: Instant, success: bool) {
+    if let Some(observer) = OBSERVER.get() {
+        observer(subcommand, started_at.elapsed(), success);
+    }
+}
@@ -0,0 +1,38 @@
+use std::process::Stdio;
+
+use tokio::{
+    io::{self, ErrorKind},
+    process::{Child, Command},
+};
+
+use super::simulated_server;
+
+pub const SAMPLE_RATE: u32 = 16000;
+
+pub fn spawn(
+    card_device_name: &str,
+    channels: u8,
+    rate: u32,
+    application_name: &str,
+) -> io::Result<Child> {
+    if simulated_server::is_enabled() {
+        return Err(io::Error::new(
+            ErrorKind::Other,
+            "Recording is not available in simulation mode",
+        ));
+    }
+
+    Command::new("parec")
+        .arg(format!("--device={}", card_device_name))
+        .arg("--raw")
+        .arg("--format=s16le")
+        .arg(format!("--rate={}", rate))
+        .arg(format!("--channels={}", channels))
+        .arg("--no-move")
+        .arg(format!("--property=application.name={}", application_name))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+}
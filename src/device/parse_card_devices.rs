@@ -4,7 +4,7 @@ use regex::Regex;
 
 use crate::util::unquote_parsed_string_value;
 
-use super::card_device::CardDevice;
+use super::card_device::{self, CardDevice};
 
 pub fn parse_card_devices(text: &str) -> Vec<CardDevice> {
     let mut card_devices: Vec<CardDevice> = Vec::new();
@@ -40,7 +40,10 @@ pub fn parse_card_devices(text: &str) -> Vec<CardDevice> {
 
                     match _match {
                         "name" => current.name = value,
-                        "driver" => current.driver = value,
+                        "driver" => {
+                            current.is_virtual = card_device::is_virtual_driver(&value);
+                            current.driver = value;
+                        }
                         "device.description" => current.description = value,
                         _ => unreachable!(),
                     }
@@ -152,6 +155,34 @@ pub fn parse_card_devices(text: &str) -> Vec<CardDevice> {
 
                     current_card_device.replace(current);
                 }
+                "channel map" => {
+                    if current_card_device.is_none() {
+                        return;
+                    }
+
+                    let mut current: CardDevice = current_card_device.take().unwrap();
+
+                    current.channel_map = captures
+                        .name("value")
+                        .unwrap()
+                        .as_str()
+                        .split(',')
+                        .map(|position| position.trim().to_owned())
+                        .collect();
+
+                    current_card_device.replace(current);
+                }
+                "module" => {
+                    if current_card_device.is_none() {
+                        return;
+                    }
+
+                    let mut current: CardDevice = current_card_device.take().unwrap();
+
+                    current.module_index = captures.name("value").unwrap().as_str().parse().ok();
+
+                    current_card_device.replace(current);
+                }
                 "monitor_of" => {
                     current_card_device.take();
                 }
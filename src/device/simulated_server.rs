@@ -0,0 +1,1263 @@
+use std::{
+    collections::HashMap,
+    default::default,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex as StdMutex,
+    },
+};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::{card_device_type::CardDeviceType, module::Module};
+
+const STEREO: &str = "front-left,front-right";
+const BLUETOOTH_PROFILES: [(&str, &str); 4] = [
+    ("a2dp_sink_sbc", "High Fidelity Playback (A2DP Sink: SBC)"),
+    ("a2dp_sink_aac", "High Fidelity Playback (A2DP Sink: AAC)"),
+    ("headset_head_unit", "Headset Head Unit (HSP/HFP)"),
+    ("off", "Off"),
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SERVER: Lazy<StdMutex<Server>> = Lazy::new(|| StdMutex::new(Server::new()));
+#[cfg(test)]
+static TEST_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(default);
+
+struct SimulatedModule {
+    index: u64,
+    name: String,
+    argument: String,
+}
+
+struct SimulatedCard {
+    index: u64,
+    name: String,
+    description: String,
+    driver: &'static str,
+    bus: &'static str,
+    form_factor: &'static str,
+    profiles: Vec<(&'static str, &'static str)>,
+    active_profile: &'static str,
+    module_index: u64,
+}
+
+struct SimulatedDevice {
+    _type: CardDeviceType,
+    index: u64,
+    name: String,
+    description: String,
+    driver: String,
+    card_index: Option<u64>,
+    module_index: u64,
+    monitor_of: Option<u64>,
+    volume: u32,
+    is_muted: bool,
+    bus: Option<&'static str>,
+    form_factor: Option<&'static str>,
+    bluetooth_protocol: Option<&'static str>,
+    a2dp_codec: Option<&'static str>,
+    channel_map: String,
+}
+
+struct SimulatedStream {
+    _type: CardDeviceType,
+    index: u64,
+    device_index: u64,
+    driver: String,
+    module_index: u64,
+    client: Option<(u64, String)>,
+    application_name: Option<String>,
+    process_id: Option<u32>,
+    volume: u32,
+    is_muted: bool,
+}
+
+#[derive(Default)]
+struct Server {
+    modules: Vec<SimulatedModule>,
+    cards: Vec<SimulatedCard>,
+    devices: Vec<SimulatedDevice>,
+    streams: Vec<SimulatedStream>,
+    default_sink: Option<u64>,
+    default_source: Option<u64>,
+    next_module_index: u64,
+    next_card_index: u64,
+    next_sink_index: u64,
+    next_source_index: u64,
+    next_sink_input_index: u64,
+    next_source_output_index: u64,
+    next_client_index: u64,
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+pub async fn reset() -> tokio::sync::MutexGuard<'static, ()> {
+    let lock = TEST_LOCK.lock().await;
+
+    enable();
+    crate::storage::init_temporary();
+    *SERVER.lock().unwrap() = Server::new();
+
+    lock
+}
+
+#[cfg(test)]
+pub async fn wait_until<F, R>(mut condition: F)
+where
+    F: FnMut() -> R,
+    R: std::future::Future<Output = bool>,
+{
+    for _ in 0..100 {
+        if condition().await {
+            return;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    panic!("The simulated server never reached the expected state");
+}
+
+pub fn run(args: &[&str]) -> (bool, String) {
+    let mut server = SERVER.lock().unwrap();
+    let arg = |i: usize| args.get(i).copied().unwrap_or_default();
+    let _type = command_type(arg(0));
+
+    let result = match arg(0) {
+        "list-modules" => Ok(server.list_modules()),
+        "list-cards" => Ok(server.list_cards()),
+        "list-sinks" | "list-sources" => Ok(server.list_devices(_type)),
+        "list-sink-inputs" | "list-source-outputs" => Ok(server.list_streams(_type)),
+        "load-module" => server
+            .load_module(arg(1), &args.get(2..).unwrap_or_default().join(" "))
+            .map(|_| String::new())
+            .ok_or_else(|| "Module load failed.".to_owned()),
+        "unload-module" => arg(1)
+            .parse()
+            .ok()
+            .filter(|index| server.unload_module(*index))
+            .map(|_| String::new())
+            .ok_or_else(|| "Failed to unload module.".to_owned()),
+        "set-card-profile" => match server
+            .find_card(arg(1))
+            .map(|position| server.cards[position].index)
+        {
+            Some(index) if server.set_card_profile(index, arg(2)) => Ok(String::new()),
+            Some(_) => Err(format!("Failed to set card profile to '{}'.", arg(2))),
+            None => Err("No card found by this name or index.".to_owned()),
+        },
+        "set-sink-volume" | "set-source-volume" => {
+            match (server.find_device(_type, arg(1)), arg(2).parse()) {
+                (Some(position), Ok(volume)) => {
+                    server.devices[position].volume = volume;
+
+                    Ok(String::new())
+                }
+                (None, _) => Err(device_not_found(_type)),
+                (_, Err(_)) => Err("Failed to parse volume.".to_owned()),
+            }
+        }
+        "set-sink-mute" | "set-source-mute" => {
+            match (server.find_device(_type, arg(1)), parse_boolean(arg(2))) {
+                (Some(position), Some(mute)) => {
+                    server.devices[position].is_muted = mute;
+
+                    Ok(String::new())
+                }
+                (None, _) => Err(device_not_found(_type)),
+                (_, None) => Err("Failed to parse mute switch.".to_owned()),
+            }
+        }
+        "set-default-sink" | "set-default-source" => match server.find_device(_type, arg(1)) {
+            Some(position) => {
+                let index = server.devices[position].index;
+
+                server.set_default(_type, index);
+
+                Ok(String::new())
+            }
+            None => Err(device_not_found(_type)),
+        },
+        "move-sink-input" | "move-source-output" => {
+            match (
+                server.find_stream(_type, arg(1)),
+                server.find_device(_type, arg(2)),
+            ) {
+                (Some(stream_position), Some(device_position)) => {
+                    let index = server.devices[device_position].index;
+
+                    server.streams[stream_position].device_index = index;
+
+                    Ok(String::new())
+                }
+                (None, _) => Err(stream_not_found(_type)),
+                (_, None) => Err(device_not_found(_type)),
+            }
+        }
+        "set-sink-input-volume" | "set-source-output-volume" => {
+            match (server.find_stream(_type, arg(1)), arg(2).parse()) {
+                (Some(position), Ok(volume)) => {
+                    server.streams[position].volume = volume;
+
+                    Ok(String::new())
+                }
+                (None, _) => Err(stream_not_found(_type)),
+                (_, Err(_)) => Err("Failed to parse volume.".to_owned()),
+            }
+        }
+        "set-sink-input-mute" | "set-source-output-mute" => {
+            match (server.find_stream(_type, arg(1)), parse_boolean(arg(2))) {
+                (Some(position), Some(mute)) => {
+                    server.streams[position].is_muted = mute;
+
+                    Ok(String::new())
+                }
+                (None, _) => Err(stream_not_found(_type)),
+                (_, None) => Err("Failed to parse mute switch.".to_owned()),
+            }
+        }
+        "kill-sink-input" | "kill-source-output" => match server.find_stream(_type, arg(1)) {
+            Some(position) => {
+                server.streams.remove(position);
+
+                Ok(String::new())
+            }
+            None => Err(stream_not_found(_type)),
+        },
+        command => Err(format!("Unknown command: {}", command)),
+    };
+
+    match result {
+        Ok(output) => (true, output),
+        Err(error) => (false, format!("{}\n", error)),
+    }
+}
+
+pub fn connect_bluetooth_headset(address: &str, description: &str) -> String {
+    let mut server = SERVER.lock().unwrap();
+    let id = address.replace(':', "_");
+
+    let module_index = server.add_module(
+        "module-bluez5-device",
+        &format!("path=/org/bluez/hci0/dev_{}", id),
+    );
+    let card_index = next(&mut server.next_card_index);
+    let name = format!("bluez_card.{}", id);
+
+    server.cards.push(SimulatedCard {
+        index: card_index,
+        name: name.clone(),
+        description: description.to_owned(),
+        driver: "module-bluez5-device.c",
+        bus: "bluetooth",
+        form_factor: "headset",
+        profiles: BLUETOOTH_PROFILES.to_vec(),
+        active_profile: "off",
+        module_index,
+    });
+
+    server.set_card_profile(card_index, BLUETOOTH_PROFILES[0].0);
+
+    // What module-switch-on-connect does for a new Bluetooth sink
+    if let Some(index) = server
+        .devices
+        .iter()
+        .find(|device| {
+            device._type == CardDeviceType::Sink && device.card_index == Some(card_index)
+        })
+        .map(|device| device.index)
+    {
+        server.set_default(CardDeviceType::Sink, index);
+
+        for stream in server
+            .streams
+            .iter_mut()
+            .filter(|stream| stream._type == CardDeviceType::Sink && stream.client.is_some())
+        {
+            stream.device_index = index;
+        }
+    }
+
+    name
+}
+
+pub fn disconnect_card(name: &str) -> bool {
+    let mut server = SERVER.lock().unwrap();
+
+    match server.find_card(name) {
+        Some(index) => {
+            let module_index = server.cards[index].module_index;
+
+            server.unload_module(module_index)
+        }
+        None => false,
+    }
+}
+
+pub fn set_volume(_type: CardDeviceType, name: &str, volume_percentage: f64) -> bool {
+    let mut server = SERVER.lock().unwrap();
+
+    match server.find_device(_type, name) {
+        Some(position) => {
+            server.devices[position].volume = (volume_percentage / 100.0 * 65536.0).round() as u32;
+
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn start_stream(_type: CardDeviceType, application_name: &str, process_id: u32) -> Option<u64> {
+    SERVER
+        .lock()
+        .unwrap()
+        .add_client_stream(_type, application_name, process_id)
+}
+
+pub fn stop_stream(_type: CardDeviceType, index: u64) -> bool {
+    let mut server = SERVER.lock().unwrap();
+
+    match server.find_stream(_type, &index.to_string()) {
+        Some(position) => {
+            server.streams.remove(position);
+
+            true
+        }
+        None => false,
+    }
+}
+
+impl Server {
+    fn new() -> Self {
+        let mut server: Server = default();
+
+        for name in [
+            "module-device-restore",
+            "module-stream-restore",
+            "module-card-restore",
+            "module-udev-detect",
+            "module-bluetooth-discover",
+            "module-native-protocol-unix",
+            "module-switch-on-connect",
+        ] {
+            server.add_module(name, "");
+        }
+
+        server.add_alsa_card(
+            "pci-0000_00_1f.3",
+            "Built-in Audio",
+            "pci",
+            "internal",
+            true,
+        );
+        server.add_alsa_card(
+            "usb-046d_HD_Pro_Webcam_C920-02",
+            "HD Pro Webcam C920",
+            "usb",
+            "webcam",
+            false,
+        );
+        server.add_client_stream(CardDeviceType::Sink, "Firefox", 2412);
+
+        server
+    }
+
+    fn add_module(&mut self, name: &str, argument: &str) -> u64 {
+        let index = next(&mut self.next_module_index);
+
+        self.modules.push(SimulatedModule {
+            index,
+            name: name.to_owned(),
+            argument: argument.to_owned(),
+        });
+
+        index
+    }
+
+    fn add_alsa_card(
+        &mut self,
+        name: &str,
+        description: &str,
+        bus: &'static str,
+        form_factor: &'static str,
+        output: bool,
+    ) {
+        let module_index = self.add_module(
+            "module-alsa-card",
+            &format!(
+                "device_id=\"{}\" name=\"{}\" card_name=\"alsa_card.{}\"",
+                self.cards.len(),
+                name,
+                name
+            ),
+        );
+        let card_index = next(&mut self.next_card_index);
+        let profile = if output {
+            "output:analog-stereo+input:analog-stereo"
+        } else {
+            "input:analog-stereo"
+        };
+
+        self.cards.push(SimulatedCard {
+            index: card_index,
+            name: format!("alsa_card.{}", name),
+            description: description.to_owned(),
+            driver: "module-alsa-card.c",
+            bus,
+            form_factor,
+            profiles: vec![(profile, "Analog Stereo"), ("off", "Off")],
+            active_profile: profile,
+            module_index,
+        });
+
+        let card_device = |name: String| SimulatedDevice {
+            card_index: Some(card_index),
+            bus: Some(bus),
+            form_factor: Some(form_factor),
+            ..device(
+                &name,
+                &format!("{} Analog Stereo", description),
+                "module-alsa-card.c",
+                module_index,
+            )
+        };
+
+        if output {
+            self.add_device(
+                CardDeviceType::Sink,
+                card_device(format!("alsa_output.{}.analog-stereo", name)),
+            );
+        }
+
+        self.add_device(
+            CardDeviceType::Source,
+            card_device(format!("alsa_input.{}.analog-stereo", name)),
+        );
+        self.reconcile();
+    }
+
+    fn add_device(&mut self, _type: CardDeviceType, mut device: SimulatedDevice) -> u64 {
+        device._type = _type;
+        device.index = self.next_device_index(_type);
+
+        let index = device.index;
+
+        if _type == CardDeviceType::Sink {
+            let monitor = SimulatedDevice {
+                _type: CardDeviceType::Source,
+                index: self.next_device_index(CardDeviceType::Source),
+                name: format!("{}.monitor", device.name),
+                description: format!("Monitor of {}", device.description),
+                driver: device.driver.clone(),
+                card_index: device.card_index,
+                module_index: device.module_index,
+                monitor_of: Some(index),
+                volume: 65536,
+                is_muted: false,
+                bus: device.bus,
+                form_factor: device.form_factor,
+                bluetooth_protocol: None,
+                a2dp_codec: None,
+                channel_map: device.channel_map.clone(),
+            };
+
+            self.devices.push(device);
+            self.devices.push(monitor);
+        } else {
+            self.devices.push(device);
+        }
+
+        index
+    }
+
+    fn add_client_stream(
+        &mut self,
+        _type: CardDeviceType,
+        application_name: &str,
+        process_id: u32,
+    ) -> Option<u64> {
+        let device_index = self.default_index(_type)?;
+        let module_index = self
+            .modules
+            .iter()
+            .find(|module| module.name == "module-native-protocol-unix")
+            .map(|module| module.index)
+            .unwrap_or_default();
+        let client_index = next(&mut self.next_client_index);
+        let index = self.next_stream_index(_type);
+
+        self.streams.push(SimulatedStream {
+            _type,
+            index,
+            device_index,
+            driver: "protocol-native.c".to_owned(),
+            module_index,
+            client: Some((client_index, application_name.to_owned())),
+            application_name: Some(application_name.to_owned()),
+            process_id: Some(process_id),
+            volume: 65536,
+            is_muted: false,
+        });
+
+        Some(index)
+    }
+
+    fn add_module_stream(
+        &mut self,
+        _type: CardDeviceType,
+        device_index: u64,
+        module_index: u64,
+        module_name: &str,
+        application_name: Option<String>,
+    ) {
+        let index = self.next_stream_index(_type);
+
+        self.streams.push(SimulatedStream {
+            _type,
+            index,
+            device_index,
+            driver: format!("{}.c", module_name),
+            module_index,
+            client: None,
+            application_name,
+            process_id: None,
+            volume: 65536,
+            is_muted: false,
+        });
+    }
+
+    fn load_module(&mut self, name: &str, argument: &str) -> Option<u64> {
+        let arguments: HashMap<String, String> = Module {
+            argument: argument.to_owned(),
+            ..default()
+        }
+        .arguments()
+        .into_iter()
+        .collect();
+        let value = |key: &str| arguments.get(key).map(String::as_str);
+        let driver = format!("{}.c", name);
+
+        match name {
+            "module-null-sink" | "module-combine-sink" => {
+                let sink_name = value("sink_name").unwrap_or(if name == "module-null-sink" {
+                    "null"
+                } else {
+                    "combined"
+                });
+                let slaves = value("slaves")
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|slave| !slave.is_empty())
+                    .map(|slave| self.resolve(CardDeviceType::Sink, Some(slave)))
+                    .collect::<Option<Vec<(u64, String)>>>()?;
+
+                if self.find_device(CardDeviceType::Sink, sink_name).is_some() {
+                    return None;
+                }
+
+                let index = self.add_module(name, argument);
+                let description = property(value("sink_properties"), "device.description")
+                    .unwrap_or_else(|| {
+                        if name == "module-null-sink" {
+                            "Null Output".to_owned()
+                        } else {
+                            "Simultaneous Output".to_owned()
+                        }
+                    });
+
+                self.add_device(
+                    CardDeviceType::Sink,
+                    SimulatedDevice {
+                        channel_map: value("channel_map").unwrap_or(STEREO).to_owned(),
+                        ..device(sink_name, &description, &driver, index)
+                    },
+                );
+
+                for (slave_index, _) in slaves {
+                    self.add_module_stream(CardDeviceType::Sink, slave_index, index, name, None);
+                }
+
+                Some(index)
+            }
+            "module-remap-sink" | "module-ladspa-sink" | "module-remap-source" => {
+                let _type = if name == "module-remap-source" {
+                    CardDeviceType::Source
+                } else {
+                    CardDeviceType::Sink
+                };
+                let (master_index, master_name) =
+                    self.resolve(_type, value("sink_master").or_else(|| value("master")))?;
+                let (name_key, properties_key) = if _type == CardDeviceType::Source {
+                    ("source_name", "source_properties")
+                } else {
+                    ("sink_name", "sink_properties")
+                };
+                let device_name = value(name_key)
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("{}.remapped", master_name));
+
+                if self.find_device(_type, &device_name).is_some() {
+                    return None;
+                }
+
+                let master = &self.devices[self.find_device(_type, &master_name)?];
+                let description = property(value(properties_key), "device.description")
+                    .unwrap_or_else(|| format!("Remapped {}", master.description));
+                let channel_map = value("channel_map")
+                    .unwrap_or(&master.channel_map)
+                    .to_owned();
+                let index = self.add_module(name, argument);
+
+                self.add_device(
+                    _type,
+                    SimulatedDevice {
+                        channel_map,
+                        ..device(&device_name, &description, &driver, index)
+                    },
+                );
+                self.add_module_stream(_type, master_index, index, name, None);
+
+                Some(index)
+            }
+            "module-echo-cancel" => {
+                let (source_master_index, source_master_name) =
+                    self.resolve(CardDeviceType::Source, value("source_master"))?;
+                let (sink_master_index, sink_master_name) =
+                    self.resolve(CardDeviceType::Sink, value("sink_master"))?;
+                let source_name = value("source_name")
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("{}.echo-cancel", source_master_name));
+                let sink_name = value("sink_name")
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("{}.echo-cancel", sink_master_name));
+
+                if self
+                    .find_device(CardDeviceType::Source, &source_name)
+                    .is_some()
+                    || self.find_device(CardDeviceType::Sink, &sink_name).is_some()
+                {
+                    return None;
+                }
+
+                let index = self.add_module(name, argument);
+
+                for (_type, device_name, properties_key, master_index) in [
+                    (
+                        CardDeviceType::Source,
+                        source_name,
+                        "source_properties",
+                        source_master_index,
+                    ),
+                    (
+                        CardDeviceType::Sink,
+                        sink_name,
+                        "sink_properties",
+                        sink_master_index,
+                    ),
+                ] {
+                    let description = property(value(properties_key), "device.description")
+                        .unwrap_or_else(|| format!("{} (echo cancelled)", device_name));
+
+                    self.add_device(_type, device(&device_name, &description, &driver, index));
+                    self.add_module_stream(_type, master_index, index, name, None);
+                }
+
+                Some(index)
+            }
+            "module-loopback" => {
+                let (source_index, _) = self.resolve(CardDeviceType::Source, value("source"))?;
+                let (sink_index, _) = self.resolve(CardDeviceType::Sink, value("sink"))?;
+                let index = self.add_module(name, argument);
+
+                self.add_module_stream(
+                    CardDeviceType::Source,
+                    source_index,
+                    index,
+                    name,
+                    property(value("source_output_properties"), "application.name"),
+                );
+                self.add_module_stream(
+                    CardDeviceType::Sink,
+                    sink_index,
+                    index,
+                    name,
+                    property(value("sink_input_properties"), "application.name"),
+                );
+
+                Some(index)
+            }
+            _ => Some(self.add_module(name, argument)),
+        }
+    }
+
+    fn unload_module(&mut self, index: u64) -> bool {
+        let position = match self.modules.iter().position(|module| module.index == index) {
+            Some(position) => position,
+            None => return false,
+        };
+
+        self.modules.remove(position);
+        self.cards.retain(|card| card.module_index != index);
+        self.devices.retain(|device| device.module_index != index);
+        self.streams
+            .retain(|stream| stream.client.is_some() || stream.module_index != index);
+        self.reconcile();
+
+        true
+    }
+
+    fn set_card_profile(&mut self, card_index: u64, profile: &str) -> bool {
+        let card = match self.cards.iter_mut().find(|card| card.index == card_index) {
+            Some(card) => card,
+            None => return false,
+        };
+
+        let profile = match card.profiles.iter().find(|(name, _)| *name == profile) {
+            Some((name, _)) => *name,
+            None => return false,
+        };
+
+        card.active_profile = profile;
+
+        if card.bus != "bluetooth" {
+            return true;
+        }
+
+        let id = card.name.trim_start_matches("bluez_card.").to_owned();
+        let description = card.description.clone();
+        let module_index = card.module_index;
+
+        let defaults_on_card: Vec<CardDeviceType> = self
+            .devices
+            .iter()
+            .filter(|device| {
+                device.card_index == Some(card_index)
+                    && self.default_index(device._type) == Some(device.index)
+            })
+            .map(|device| device._type)
+            .collect();
+
+        self.devices
+            .retain(|device| device.card_index != Some(card_index));
+
+        let bluetooth_device =
+            |name: String, protocol: &'static str, codec: Option<&'static str>| SimulatedDevice {
+                card_index: Some(card_index),
+                bus: Some("bluetooth"),
+                form_factor: Some("headset"),
+                bluetooth_protocol: Some(protocol),
+                a2dp_codec: codec,
+                channel_map: if codec.is_some() { STEREO } else { "mono" }.to_owned(),
+                ..device(&name, &description, "module-bluez5-device.c", module_index)
+            };
+
+        let mut new_devices = Vec::new();
+
+        match profile {
+            "off" => (),
+            "headset_head_unit" => {
+                new_devices.push((
+                    CardDeviceType::Sink,
+                    bluetooth_device(
+                        format!("bluez_sink.{}.headset_head_unit", id),
+                        "headset_head_unit",
+                        None,
+                    ),
+                ));
+                new_devices.push((
+                    CardDeviceType::Source,
+                    bluetooth_device(
+                        format!("bluez_source.{}.headset_head_unit", id),
+                        "headset_head_unit",
+                        None,
+                    ),
+                ));
+            }
+            _ => new_devices.push((
+                CardDeviceType::Sink,
+                bluetooth_device(
+                    format!("bluez_sink.{}.a2dp_sink", id),
+                    "a2dp_sink",
+                    Some(if profile.ends_with("aac") {
+                        "AAC"
+                    } else {
+                        "SBC"
+                    }),
+                ),
+            )),
+        }
+
+        for (_type, device) in new_devices {
+            let index = self.add_device(_type, device);
+
+            if defaults_on_card.contains(&_type) {
+                self.set_default(_type, index);
+            }
+        }
+
+        self.reconcile();
+
+        true
+    }
+
+    fn reconcile(&mut self) {
+        loop {
+            for _type in [CardDeviceType::Sink, CardDeviceType::Source] {
+                if self
+                    .default_index(_type)
+                    .and_then(|index| self.find_device(_type, &index.to_string()))
+                    .is_none()
+                {
+                    let fallback = self
+                        .devices
+                        .iter()
+                        .filter(|device| device._type == _type)
+                        .min_by_key(|device| {
+                            (
+                                device.monitor_of.is_some(),
+                                device.card_index.is_none(),
+                                device.index,
+                            )
+                        })
+                        .map(|device| device.index);
+
+                    *self.default_mut(_type) = fallback;
+                }
+            }
+
+            let orphan = self.streams.iter().position(|stream| {
+                self.find_device(stream._type, &stream.device_index.to_string())
+                    .is_none()
+            });
+
+            let position = match orphan {
+                Some(position) => position,
+                None => break,
+            };
+
+            let stream = &self.streams[position];
+
+            if stream.client.is_none() {
+                let module_index = stream.module_index;
+
+                if !self.unload_module(module_index) {
+                    self.streams.remove(position);
+                }
+
+                continue;
+            }
+
+            match self.default_index(stream._type) {
+                Some(index) => self.streams[position].device_index = index,
+                None => {
+                    self.streams.remove(position);
+                }
+            }
+        }
+    }
+
+    fn set_default(&mut self, _type: CardDeviceType, index: u64) {
+        *self.default_mut(_type) = Some(index);
+    }
+
+    fn default_index(&self, _type: CardDeviceType) -> Option<u64> {
+        match _type {
+            CardDeviceType::Sink => self.default_sink,
+            CardDeviceType::Source => self.default_source,
+        }
+    }
+
+    fn default_mut(&mut self, _type: CardDeviceType) -> &mut Option<u64> {
+        match _type {
+            CardDeviceType::Sink => &mut self.default_sink,
+            CardDeviceType::Source => &mut self.default_source,
+        }
+    }
+
+    fn next_device_index(&mut self, _type: CardDeviceType) -> u64 {
+        match _type {
+            CardDeviceType::Sink => next(&mut self.next_sink_index),
+            CardDeviceType::Source => next(&mut self.next_source_index),
+        }
+    }
+
+    fn next_stream_index(&mut self, _type: CardDeviceType) -> u64 {
+        match _type {
+            CardDeviceType::Sink => next(&mut self.next_sink_input_index),
+            CardDeviceType::Source => next(&mut self.next_source_output_index),
+        }
+    }
+
+    fn resolve(&self, _type: CardDeviceType, name: Option<&str>) -> Option<(u64, String)> {
+        let position = match name {
+            Some(name) => self.find_device(_type, name)?,
+            None => self.find_device(_type, &self.default_index(_type)?.to_string())?,
+        };
+
+        Some((
+            self.devices[position].index,
+            self.devices[position].name.clone(),
+        ))
+    }
+
+    fn find_card(&self, name: &str) -> Option<usize> {
+        self.cards
+            .iter()
+            .position(|card| card.name == name || card.index.to_string() == name)
+    }
+
+    fn find_device(&self, _type: CardDeviceType, name: &str) -> Option<usize> {
+        let default_index = match (_type, name) {
+            (CardDeviceType::Sink, "@DEFAULT_SINK@")
+            | (CardDeviceType::Source, "@DEFAULT_SOURCE@") => self.default_index(_type),
+            _ => None,
+        };
+
+        self.devices.iter().position(|device| {
+            device._type == _type
+                && (device.name == name
+                    || device.index.to_string() == name
+                    || default_index == Some(device.index))
+        })
+    }
+
+    fn find_stream(&self, _type: CardDeviceType, index: &str) -> Option<usize> {
+        self.streams
+            .iter()
+            .position(|stream| stream._type == _type && stream.index.to_string() == index)
+    }
+
+    fn list_modules(&self) -> String {
+        let mut output = format!("{} module(s) loaded.\n", self.modules.len());
+
+        for module in &self.modules {
+            writeln!(output, "    index: {}", module.index).unwrap();
+            writeln!(output, "\tname: <{}>", module.name).unwrap();
+            writeln!(output, "\targument: <{}>", module.argument).unwrap();
+            writeln!(output, "\tused: -1").unwrap();
+            writeln!(output, "\tload once: no").unwrap();
+        }
+
+        output
+    }
+
+    fn list_cards(&self) -> String {
+        let mut output = format!("{} card(s) available.\n", self.cards.len());
+
+        for card in &self.cards {
+            writeln!(output, "    index: {}", card.index).unwrap();
+            writeln!(output, "\tname: <{}>", card.name).unwrap();
+            writeln!(output, "\tdriver: <{}>", card.driver).unwrap();
+            writeln!(output, "\towner module: {}", card.module_index).unwrap();
+            writeln!(output, "\tproperties:").unwrap();
+            writeln!(output, "\t\tdevice.description = \"{}\"", card.description).unwrap();
+            writeln!(output, "\t\tdevice.bus = \"{}\"", card.bus).unwrap();
+            writeln!(output, "\t\tdevice.form_factor = \"{}\"", card.form_factor).unwrap();
+            writeln!(output, "\tprofiles:").unwrap();
+
+            for (name, description) in &card.profiles {
+                writeln!(output, "\t\t{}: {}", name, description).unwrap();
+            }
+
+            writeln!(output, "\tactive profile: <{}>", card.active_profile).unwrap();
+
+            for (_type, title) in [
+                (CardDeviceType::Sink, "sinks"),
+                (CardDeviceType::Source, "sources"),
+            ] {
+                writeln!(output, "\t{}:", title).unwrap();
+
+                for device in self
+                    .devices
+                    .iter()
+                    .filter(|device| device._type == _type && device.card_index == Some(card.index))
+                {
+                    writeln!(
+                        output,
+                        "\t\t{}/#{}: {}",
+                        device.name, device.index, device.description
+                    )
+                    .unwrap();
+                }
+            }
+
+            writeln!(output, "\tports:").unwrap();
+        }
+
+        output
+    }
+
+    fn list_devices(&self, _type: CardDeviceType) -> String {
+        let mut devices: Vec<&SimulatedDevice> = self
+            .devices
+            .iter()
+            .filter(|device| device._type == _type)
+            .collect();
+
+        devices.sort_by_key(|device| device.index);
+
+        let mut output = format!("{} {}(s) available.\n", devices.len(), device_noun(_type));
+
+        for device in devices {
+            let is_default = self.default_index(_type) == Some(device.index);
+            let is_running = self.streams.iter().any(|stream| {
+                (stream._type == _type && stream.device_index == device.index)
+                    || (_type == CardDeviceType::Sink
+                        && stream._type == CardDeviceType::Source
+                        && self.devices.iter().any(|monitor| {
+                            monitor.monitor_of == Some(device.index)
+                                && monitor.index == stream.device_index
+                        }))
+            });
+
+            writeln!(
+                output,
+                "  {} index: {}",
+                if is_default { "*" } else { " " },
+                device.index
+            )
+            .unwrap();
+            writeln!(output, "\tname: <{}>", device.name).unwrap();
+            writeln!(output, "\tdriver: <{}>", device.driver).unwrap();
+            writeln!(
+                output,
+                "\tstate: {}",
+                if is_running { "RUNNING" } else { "SUSPENDED" }
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "\tvolume: {}",
+                format_volume(device.volume, &device.channel_map)
+            )
+            .unwrap();
+            writeln!(output, "\t        balance 0.00").unwrap();
+            writeln!(
+                output,
+                "\tmuted: {}",
+                if device.is_muted { "yes" } else { "no" }
+            )
+            .unwrap();
+
+            if let Some(monitor) = self
+                .devices
+                .iter()
+                .find(|monitor| monitor.monitor_of == Some(device.index))
+                .filter(|_| _type == CardDeviceType::Sink)
+            {
+                writeln!(output, "\tmonitor source: {}", monitor.index).unwrap();
+            }
+
+            if let Some(monitor_of) = device.monitor_of {
+                writeln!(output, "\tmonitor_of: {}", monitor_of).unwrap();
+            }
+
+            writeln!(
+                output,
+                "\tsample spec: s16le {}ch 44100Hz",
+                device.channel_map.split(',').count()
+            )
+            .unwrap();
+            writeln!(output, "\tchannel map: {}", device.channel_map).unwrap();
+
+            if let Some(card) = self
+                .cards
+                .iter()
+                .find(|card| Some(card.index) == device.card_index)
+            {
+                writeln!(output, "\tcard: {} <{}>", card.index, card.name).unwrap();
+            }
+
+            writeln!(output, "\tmodule: {}", device.module_index).unwrap();
+            writeln!(output, "\tproperties:").unwrap();
+            writeln!(
+                output,
+                "\t\tdevice.description = \"{}\"",
+                device.description
+            )
+            .unwrap();
+
+            for (key, value) in [
+                ("device.bus", device.bus),
+                ("device.form_factor", device.form_factor),
+                ("bluetooth.protocol", device.bluetooth_protocol),
+                ("bluetooth.a2dp_codec", device.a2dp_codec),
+            ] {
+                if let Some(value) = value {
+                    writeln!(output, "\t\t{} = \"{}\"", key, value).unwrap();
+                }
+            }
+        }
+
+        output
+    }
+
+    fn list_streams(&self, _type: CardDeviceType) -> String {
+        let streams: Vec<&SimulatedStream> = self
+            .streams
+            .iter()
+            .filter(|stream| stream._type == _type)
+            .collect();
+
+        let mut output = format!("{} {}(s) available.\n", streams.len(), stream_noun(_type));
+
+        for stream in streams {
+            let device = match self.find_device(_type, &stream.device_index.to_string()) {
+                Some(position) => &self.devices[position],
+                None => continue,
+            };
+
+            writeln!(output, "    index: {}", stream.index).unwrap();
+            writeln!(output, "\tdriver: <{}>", stream.driver).unwrap();
+            writeln!(output, "\tstate: RUNNING").unwrap();
+            writeln!(
+                output,
+                "\t{}: {} <{}>",
+                device_noun(_type),
+                device.index,
+                device.name
+            )
+            .unwrap();
+            writeln!(output, "\tvolume: {}", format_volume(stream.volume, STEREO)).unwrap();
+            writeln!(output, "\t        balance 0.00").unwrap();
+            writeln!(
+                output,
+                "\tmuted: {}",
+                if stream.is_muted { "yes" } else { "no" }
+            )
+            .unwrap();
+            writeln!(output, "\tsample spec: s16le 2ch 44100Hz").unwrap();
+            writeln!(output, "\tchannel map: {}", STEREO).unwrap();
+            writeln!(output, "\tmodule: {}", stream.module_index).unwrap();
+
+            if let Some((index, ref name)) = stream.client {
+                writeln!(output, "\tclient: {} <{}>", index, name).unwrap();
+            }
+
+            writeln!(output, "\tproperties:").unwrap();
+
+            if let Some(ref application_name) = stream.application_name {
+                writeln!(output, "\t\tapplication.name = \"{}\"", application_name).unwrap();
+            }
+
+            if let Some(process_id) = stream.process_id {
+                writeln!(output, "\t\tapplication.process.id = \"{}\"", process_id).unwrap();
+            }
+        }
+
+        output
+    }
+}
+
+fn device(name: &str, description: &str, driver: &str, module_index: u64) -> SimulatedDevice {
+    SimulatedDevice {
+        _type: CardDeviceType::Sink,
+        index: 0,
+        name: name.to_owned(),
+        description: description.to_owned(),
+        driver: driver.to_owned(),
+        card_index: None,
+        module_index,
+        monitor_of: None,
+        volume: 45875,
+        is_muted: false,
+        bus: None,
+        form_factor: None,
+        bluetooth_protocol: None,
+        a2dp_codec: None,
+        channel_map: STEREO.to_owned(),
+    }
+}
+
+fn next(counter: &mut u64) -> u64 {
+    let index = *counter;
+
+    *counter += 1;
+
+    index
+}
+
+fn command_type(command: &str) -> CardDeviceType {
+    if command.contains("source") {
+        CardDeviceType::Source
+    } else {
+        CardDeviceType::Sink
+    }
+}
+
+fn device_noun(_type: CardDeviceType) -> &'static str {
+    match _type {
+        CardDeviceType::Sink => "sink",
+        CardDeviceType::Source => "source",
+    }
+}
+
+fn stream_noun(_type: CardDeviceType) -> &'static str {
+    match _type {
+        CardDeviceType::Sink => "sink input",
+        CardDeviceType::Source => "source output",
+    }
+}
+
+fn device_not_found(_type: CardDeviceType) -> String {
+    format!("No {} found by this name or index.", device_noun(_type))
+}
+
+fn stream_not_found(_type: CardDeviceType) -> String {
+    format!("No {} found with this index.", stream_noun(_type))
+}
+
+fn parse_boolean(value: &str) -> Option<bool> {
+    match value {
+        "1" | "y" | "t" | "yes" | "true" | "on" => Some(true),
+        "0" | "n" | "f" | "no" | "false" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn property(properties: Option<&str>, key: &str) -> Option<String> {
+    let captures = Regex::new(&format!(
+        r#"{}=(?:'(?P<single_quoted>[^']*)'|"(?P<quoted>[^"]*)"|(?P<value>[^\s'"]+))"#,
+        regex::escape(key)
+    ))
+    .unwrap()
+    .captures(properties?)?;
+
+    captures
+        .name("single_quoted")
+        .or_else(|| captures.name("quoted"))
+        .or_else(|| captures.name("value"))
+        .map(|value| value.as_str().to_owned())
+}
+
+fn format_volume(volume: u32, channel_map: &str) -> String {
+    let decibels = if volume == 0 {
+        "-inf dB".to_owned()
+    } else {
+        format!("{:.2} dB", 60.0 * (volume as f64 / 65536.0).log10())
+    };
+
+    channel_map
+        .split(',')
+        .map(|position| {
+            format!(
+                "{}: {} / {:>3}% / {}",
+                position,
+                volume,
+                (volume as f64 / 65536.0 * 100.0).round(),
+                decibels
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",   ")
+}
@@ -0,0 +1,225 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io::{self, ErrorKind};
+use tracing::instrument;
+
+use super::{audio, module};
+
+const MODULE_NAMES: [&str; 4] = [
+    "module-combine-sink",
+    "module-null-sink",
+    "module-remap-source",
+    "module-remap-sink",
+];
+
+/// A `combine_sink` plays on all of its `sinks`, a `null_sink` discards what it is played, which
+/// can still be recorded from its monitor, and a `remap_source` or `remap_sink` exposes the
+/// `master_channel_map` channels of its `master` as `channel_map`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VirtualDevice {
+    CombineSink {
+        name: String,
+        description: Option<String>,
+        sinks: Vec<String>,
+    },
+    NullSink {
+        name: String,
+        description: Option<String>,
+    },
+    RemapSource {
+        name: String,
+        description: Option<String>,
+        master: String,
+        master_channel_map: String,
+        channel_map: String,
+    },
+    RemapSink {
+        name: String,
+        description: Option<String>,
+        master: String,
+        master_channel_map: String,
+        channel_map: String,
+    },
+}
+
+impl VirtualDevice {
+    pub fn name(&self) -> &str {
+        match self {
+            VirtualDevice::CombineSink { name, .. }
+            | VirtualDevice::NullSink { name, .. }
+            | VirtualDevice::RemapSource { name, .. }
+            | VirtualDevice::RemapSink { name, .. } => name,
+        }
+    }
+
+    fn validate(&self) -> io::Result<()> {
+        module::validate_argument_value("name", self.name())?;
+
+        match self {
+            VirtualDevice::CombineSink { sinks, .. } => {
+                for sink in sinks {
+                    module::validate_argument_value("sink name", sink)?;
+                }
+            }
+            VirtualDevice::NullSink { .. } => (),
+            VirtualDevice::RemapSource {
+                master,
+                master_channel_map,
+                channel_map,
+                ..
+            }
+            | VirtualDevice::RemapSink {
+                master,
+                master_channel_map,
+                channel_map,
+                ..
+            } => {
+                module::validate_argument_value("master", master)?;
+                module::validate_argument_value("master channel map", master_channel_map)?;
+                module::validate_argument_value("channel map", channel_map)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn module(&self) -> (&'static str, String) {
+        match self {
+            VirtualDevice::CombineSink {
+                name,
+                description,
+                sinks,
+            } => (
+                "module-combine-sink",
+                format!(
+                    "sink_name={} slaves={} {}",
+                    name,
+                    sinks.join(","),
+                    sink_properties(name, description)
+                ),
+            ),
+            VirtualDevice::NullSink { name, description } => (
+                "module-null-sink",
+                format!("sink_name={} {}", name, sink_properties(name, description)),
+            ),
+            VirtualDevice::RemapSource {
+                name,
+                description,
+                master,
+                master_channel_map,
+                channel_map,
+            } => (
+                "module-remap-source",
+                format!(
+                    "source_name={} master={} master_channel_map={} channel_map={} \
+                        source_properties=\"device.description='{}'\"",
+                    name,
+                    master,
+                    master_channel_map,
+                    channel_map,
+                    module::sanitize_property(description.as_deref().unwrap_or(name))
+                ),
+            ),
+            VirtualDevice::RemapSink {
+                name,
+                description,
+                master,
+                master_channel_map,
+                channel_map,
+            } => (
+                "module-remap-sink",
+                format!(
+                    "sink_name={} master={} master_channel_map={} channel_map={} {}",
+                    name,
+                    master,
+                    master_channel_map,
+                    channel_map,
+                    sink_properties(name, description)
+                ),
+            ),
+        }
+    }
+}
+
+#[instrument]
+pub async fn create(virtual_device: &VirtualDevice) -> io::Result<u64> {
+    virtual_device.validate()?;
+
+    let (_, sources, sinks) = audio::fetch_devices().await;
+
+    if sources
+        .iter()
+        .chain(sinks.iter())
+        .any(|card_device| card_device.name == virtual_device.name())
+    {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("Virtual device {} already exists", virtual_device.name()),
+        ));
+    }
+
+    let is_sink = |name: &str| sinks.iter().any(|sink| sink.name == name);
+    let is_source = |name: &str| {
+        sources.iter().any(|source| source.name == name)
+            || name.strip_suffix(".monitor").map_or(false, is_sink)
+    };
+
+    let missing = match virtual_device {
+        VirtualDevice::CombineSink {
+            sinks: sink_names, ..
+        } => sink_names
+            .iter()
+            .find(|sink_name| !is_sink(sink_name))
+            .map(|sink_name| ("Sink", sink_name)),
+        VirtualDevice::NullSink { .. } => None,
+        VirtualDevice::RemapSource { master, .. } => {
+            Some(("Source", master)).filter(|_| !is_source(master))
+        }
+        VirtualDevice::RemapSink { master, .. } => {
+            Some(("Sink", master)).filter(|_| !is_sink(master))
+        }
+    };
+
+    if let Some((kind, name)) = missing {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{} {} does not exist", kind, name),
+        ));
+    }
+
+    let (module_name, argument) = virtual_device.module();
+
+    module::load_module(module_name, &argument).await
+}
+
+#[instrument]
+pub async fn remove(name: &str) -> io::Result<()> {
+    let index = find_module(name).await?.ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::NotFound,
+            format!("Virtual device {} does not exist", name),
+        )
+    })?;
+
+    module::unload_module(index).await
+}
+
+async fn find_module(name: &str) -> io::Result<Option<u64>> {
+    Ok(module::fetch_modules()
+        .await?
+        .into_iter()
+        .filter(|module| module.is_managed && MODULE_NAMES.contains(&module.name.as_str()))
+        .find(|module| {
+            module.argument_value("sink_name").as_deref() == Some(name)
+                || module.argument_value("source_name").as_deref() == Some(name)
+        })
+        .map(|module| module.index))
+}
+
+fn sink_properties(name: &str, description: &Option<String>) -> String {
+    format!(
+        "sink_properties=\"device.description='{}'\"",
+        module::sanitize_property(description.as_deref().unwrap_or(name))
+    )
+}
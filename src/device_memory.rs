@@ -0,0 +1,302 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    default::default,
+    io::{self, ErrorKind},
+    sync::Arc,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info};
+
+use crate::{
+    device::{
+        audio, bus::Bus, card::Card, card_device::CardDevice, card_device_type::CardDeviceType,
+        card_profile::CardProfile,
+    },
+    device_monitor,
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+    storage,
+};
+
+const DEVICES_FILE_NAME: &str = "devices.json";
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct RememberedDevice {
+    /// `card:bluetooth:<address>`, `<type>:bluetooth:<address>` or `<type>:<name>`, where type is
+    /// source or sink
+    pub key: String,
+    pub description: String,
+    pub volume: Option<f64>,
+    pub is_muted: Option<bool>,
+    pub profile: Option<CardProfile>,
+    #[serde(default = "default_restore")]
+    pub restore: bool,
+}
+
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "device_memory").await;
+
+    let mut present: Option<HashSet<String>> = None;
+
+    loop {
+        if let Some(message) = rx.recv().await {
+            if let Some(state) = try_downcast_ref!(message, MessageState) {
+                let keys = state_keys(state);
+
+                let appeared: HashSet<String> = match present {
+                    Some(ref present) => keys.difference(present).cloned().collect(),
+                    None => keys.clone(),
+                };
+
+                present = Some(keys);
+
+                if !appeared.is_empty() {
+                    match restore(state, &appeared).await {
+                        Ok(true) => {
+                            device_monitor::publish_state(&pubsub_tx).await;
+
+                            continue;
+                        }
+                        Ok(false) => (),
+                        Err(error) => {
+                            error!("Could not restore devices: {}", error);
+
+                            continue;
+                        }
+                    }
+                }
+
+                if let Err(error) = remember(state).await {
+                    error!("Could not remember devices: {}", error);
+                }
+            }
+        }
+    }
+}
+
+pub async fn list() -> io::Result<Vec<RememberedDevice>> {
+    let devices: BTreeMap<String, RememberedDevice> = storage::load(DEVICES_FILE_NAME).await?;
+
+    Ok(devices.into_values().collect())
+}
+
+pub async fn set_restore(key: &str, restore: bool) -> io::Result<()> {
+    storage::update(
+        DEVICES_FILE_NAME,
+        |devices: &mut BTreeMap<String, RememberedDevice>| {
+            devices.get_mut(key).map(|device| device.restore = restore)
+        },
+    )
+    .await?
+    .ok_or_else(|| not_found(key))
+}
+
+pub async fn forget(key: &str) -> io::Result<()> {
+    storage::update(
+        DEVICES_FILE_NAME,
+        |devices: &mut BTreeMap<String, RememberedDevice>| devices.remove(key),
+    )
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| not_found(key))
+}
+
+async fn restore(state: &MessageState, appeared: &HashSet<String>) -> io::Result<bool> {
+    let devices: BTreeMap<String, RememberedDevice> = storage::load(DEVICES_FILE_NAME).await?;
+
+    let remembered = |key: &str| devices.get(key).filter(|device| device.restore);
+
+    let mut changed = false;
+
+    for card in state.cards().iter() {
+        let key = match card_key(card) {
+            Some(key) if appeared.contains(&key) => key,
+            _ => continue,
+        };
+
+        if let Some(profile) = remembered(&key).and_then(|device| device.profile.clone()) {
+            if profile.as_parsed() != card.active_profile.as_parsed() {
+                info!("Restoring the profile of {} to {}", card.name, profile);
+
+                audio::set_card_profile(card.index, profile).await?;
+
+                changed = true;
+            }
+        }
+    }
+
+    let (_, sources, sinks) = if changed {
+        audio::fetch_devices().await
+    } else {
+        (default(), state.sources().to_vec(), state.sinks().to_vec())
+    };
+
+    for (_type, card_devices) in [
+        (CardDeviceType::Source, &sources),
+        (CardDeviceType::Sink, &sinks),
+    ] {
+        for card_device in card_devices {
+            let key = card_device_key(_type, card_device);
+
+            if !appeared.contains(&key) {
+                continue;
+            }
+
+            let device = match remembered(&key) {
+                Some(device) => device,
+                None => continue,
+            };
+
+            if let Some(volume) = device.volume {
+                if (volume - card_device.volume).abs() >= 1.0 {
+                    info!("Restoring the volume of {} to {}", card_device.name, volume);
+
+                    audio::set_volume(_type, card_device.index, volume).await?;
+
+                    changed = true;
+                }
+            }
+
+            if let Some(is_muted) = device.is_muted {
+                if is_muted != card_device.is_muted {
+                    info!(
+                        "Restoring the mute state of {} to {}",
+                        card_device.name, is_muted
+                    );
+
+                    audio::toggle_mute(_type, card_device.index, is_muted).await?;
+
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    Ok(changed)
+}
+
+async fn remember(state: &MessageState) -> io::Result<()> {
+    let mut current: Vec<RememberedDevice> = Vec::new();
+
+    for card in state.cards().iter() {
+        if let Some(key) = card_key(card) {
+            current.push(RememberedDevice {
+                key,
+                description: card.description.clone(),
+                volume: None,
+                is_muted: None,
+                profile: Some(card.active_profile.clone()),
+                restore: true,
+            });
+        }
+    }
+
+    for (_type, card_devices) in [
+        (CardDeviceType::Source, state.sources()),
+        (CardDeviceType::Sink, state.sinks()),
+    ] {
+        for card_device in card_devices.iter() {
+            current.push(RememberedDevice {
+                key: card_device_key(_type, card_device),
+                description: card_device.description.clone(),
+                volume: Some(card_device.volume),
+                is_muted: Some(card_device.is_muted),
+                profile: None,
+                restore: true,
+            });
+        }
+    }
+
+    let devices: BTreeMap<String, RememberedDevice> = storage::load(DEVICES_FILE_NAME).await?;
+
+    let changed = current.iter().any(|device| {
+        devices.get(&device.key).map(|remembered| RememberedDevice {
+            restore: true,
+            ..remembered.clone()
+        }) != Some(device.clone())
+    });
+
+    if !changed {
+        return Ok(());
+    }
+
+    storage::update(
+        DEVICES_FILE_NAME,
+        |devices: &mut BTreeMap<String, RememberedDevice>| {
+            for device in current {
+                let restore = devices.get(&device.key).map_or(true, |d| d.restore);
+
+                devices.insert(device.key.clone(), RememberedDevice { restore, ..device });
+            }
+        },
+    )
+    .await
+}
+
+fn state_keys(state: &MessageState) -> HashSet<String> {
+    state
+        .cards()
+        .iter()
+        .filter_map(card_key)
+        .chain(
+            state
+                .sources()
+                .iter()
+                .map(|source| card_device_key(CardDeviceType::Source, source)),
+        )
+        .chain(
+            state
+                .sinks()
+                .iter()
+                .map(|sink| card_device_key(CardDeviceType::Sink, sink)),
+        )
+        .collect()
+}
+
+fn card_key(card: &Card) -> Option<String> {
+    if card.bus != Bus::Bluetooth {
+        return None;
+    }
+
+    bluetooth_address(&card.name).map(|address| format!("card:bluetooth:{}", address))
+}
+
+fn card_device_key(_type: CardDeviceType, card_device: &CardDevice) -> String {
+    let _type = match _type {
+        CardDeviceType::Source => "source",
+        CardDeviceType::Sink => "sink",
+    };
+
+    match bluetooth_address(&card_device.name) {
+        Some(address) if card_device.bus == Bus::Bluetooth => {
+            format!("{}:bluetooth:{}", _type, address)
+        }
+        _ => format!("{}:{}", _type, card_device.name),
+    }
+}
+
+fn bluetooth_address(name: &str) -> Option<String> {
+    let mut parts = name.split('.');
+
+    if !parts.next()?.starts_with("bluez_") {
+        return None;
+    }
+
+    Some(parts.next()?.replace('_', ":"))
+}
+
+fn default_restore() -> bool {
+    true
+}
+
+fn not_found(key: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("Device {} is not remembered", key),
+    )
+}
@@ -5,32 +5,47 @@ use tokio::{
     sync::{mpsc::UnboundedSender, Mutex},
     time::sleep,
 };
-use tracing::log::debug;
+use tracing::log::{debug, error};
 
 use crate::{
-    device::audio,
+    device::{audio, loopback},
     pubsub::{message::Message, message_state::MessageState, message_topic::MessageTopic},
+    recorder,
 };
 
 pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) -> io::Result<()> {
     loop {
         debug!("Fetching the state of audio devices in audio monitor");
 
-        let (cards, sources, sinks) = audio::fetch_devices().await;
-
-        pubsub_tx
-            .lock()
-            .await
-            .send(Arc::new((
-                MessageTopic::AudioState,
-                Arc::new(MessageState::new(
-                    Arc::new(cards),
-                    Arc::new(sources),
-                    Arc::new(sinks),
-                )),
-            )))
-            .unwrap();
+        publish_state(&pubsub_tx).await;
 
         sleep(Duration::from_secs(15)).await;
     }
 }
+
+pub async fn publish_state(pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>) -> MessageState {
+    let (cards, sources, sinks) = audio::fetch_devices().await;
+    let loopbacks = loopback::fetch_loopbacks().await.unwrap_or_else(|error| {
+        error!("Could not fetch the loopbacks: {}", error);
+
+        Vec::new()
+    });
+    let message_state = MessageState::new(
+        Arc::new(cards),
+        Arc::new(sources),
+        Arc::new(sinks),
+        Arc::new(loopbacks),
+        Arc::new(recorder::active()),
+    );
+
+    pubsub_tx
+        .lock()
+        .await
+        .send(Arc::new((
+            MessageTopic::AudioState,
+            Arc::new(message_state.clone()),
+        )))
+        .unwrap();
+
+    message_state
+}
@@ -0,0 +1,301 @@
+use std::{
+    convert::Infallible,
+    f64::consts::PI,
+    fmt::Display,
+    io::{self, ErrorKind},
+    str::FromStr,
+    time::Duration,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::AsyncReadExt,
+    process::Command,
+    time::{sleep, timeout},
+};
+use tracing::{info, instrument, warn};
+
+use crate::device::{
+    audio, audio_client::INTERNAL_APPLICATION_NAME, card_device::CardDevice,
+    card_device_type::CardDeviceType, pacat, parec,
+};
+
+const SAMPLE_RATE: u32 = 48000;
+const MAX_DURATION_SECONDS: f64 = 30.0;
+const DEFAULT_TONE_FREQUENCY: f64 = 440.0;
+const DEFAULT_TONE_SECONDS: f64 = 2.0;
+const DEFAULT_MICROPHONE_SECONDS: f64 = 5.0;
+const CLIPPING_THRESHOLD: i32 = i16::MAX as i32 - 1;
+const SPEECH_PROGRAMS: [&str; 2] = ["espeak-ng", "espeak"];
+
+/// The index or the name of a source or sink
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(untagged)]
+pub enum CardDeviceSelector {
+    Index(u64),
+    Name(String),
+}
+
+impl CardDeviceSelector {
+    fn matches(&self, card_device: &CardDevice) -> bool {
+        match self {
+            CardDeviceSelector::Index(index) => card_device.index == *index,
+            CardDeviceSelector::Name(name) => &card_device.name == name,
+        }
+    }
+}
+
+impl Display for CardDeviceSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CardDeviceSelector::Index(index) => write!(f, "index {}", index),
+            CardDeviceSelector::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+impl FromStr for CardDeviceSelector {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.parse()
+            .map(CardDeviceSelector::Index)
+            .unwrap_or_else(|_| CardDeviceSelector::Name(s.to_owned())))
+    }
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct MicrophoneTestResult {
+    pub source_name: String,
+    pub sink_name: String,
+    pub duration_seconds: f64,
+    /// From 0 to 1
+    pub peak: f64,
+    /// From 0 to 1
+    pub rms: f64,
+    pub clipped_samples: u64,
+    pub is_clipping: bool,
+}
+
+#[instrument]
+pub async fn play_tone(
+    sink: &CardDeviceSelector,
+    frequency: Option<f64>,
+    duration_seconds: Option<f64>,
+) -> io::Result<()> {
+    let sink = find(CardDeviceType::Sink, sink).await?;
+    let frequency = frequency.unwrap_or(DEFAULT_TONE_FREQUENCY);
+    let duration_seconds = validate_duration(duration_seconds.unwrap_or(DEFAULT_TONE_SECONDS))?;
+
+    if !(20.0..=20000.0).contains(&frequency) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("The frequency {} is not between 20 and 20000", frequency),
+        ));
+    }
+
+    info!(
+        "Playing a {}Hz tone for {}s on sink {}",
+        frequency, duration_seconds, sink.name
+    );
+
+    pacat::play(
+        &sink.name,
+        1,
+        SAMPLE_RATE,
+        None,
+        &tone(frequency, duration_seconds),
+    )
+    .await
+}
+
+#[instrument]
+pub async fn identify_channels(sink: &CardDeviceSelector) -> io::Result<()> {
+    let sink = find(CardDeviceType::Sink, sink).await?;
+
+    for (i, position) in sink.channel_map.iter().enumerate() {
+        let label = position.replace('-', " ");
+
+        info!("Identifying channel {} of sink {}", position, sink.name);
+
+        match speak(&label).await {
+            Some((rate, speech)) => {
+                pacat::play(&sink.name, 1, rate, Some(position), &speech).await?;
+            }
+            None => {
+                for _ in 0..=i {
+                    pacat::play(
+                        &sink.name,
+                        1,
+                        SAMPLE_RATE,
+                        Some(position),
+                        &tone(880.0, 0.2),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        sleep(Duration::from_millis(500)).await;
+    }
+
+    Ok(())
+}
+
+#[instrument]
+pub async fn test_microphone(
+    source: &CardDeviceSelector,
+    sink: Option<&CardDeviceSelector>,
+    duration_seconds: Option<f64>,
+) -> io::Result<MicrophoneTestResult> {
+    let source = find(CardDeviceType::Source, source).await?;
+    let sink = match sink {
+        Some(sink) => find(CardDeviceType::Sink, sink).await?,
+        None => {
+            let (_, _, sinks) = audio::fetch_devices().await;
+
+            sinks
+                .into_iter()
+                .find(|sink| sink.is_default)
+                .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "There is no default sink"))?
+        }
+    };
+    let duration_seconds =
+        validate_duration(duration_seconds.unwrap_or(DEFAULT_MICROPHONE_SECONDS))?;
+
+    info!(
+        "Recording {}s from source {} to play back on sink {}",
+        duration_seconds, source.name, sink.name
+    );
+
+    let mut child = parec::spawn(&source.name, 1, SAMPLE_RATE, INTERNAL_APPLICATION_NAME)?;
+    let mut stdout = child.stdout.take().unwrap();
+
+    let mut recording = vec![0u8; (duration_seconds * SAMPLE_RATE as f64) as usize * 2];
+
+    timeout(
+        Duration::from_secs_f64(duration_seconds + 5.0),
+        stdout.read_exact(&mut recording),
+    )
+    .await
+    .map_err(|_| {
+        io::Error::new(
+            ErrorKind::Other,
+            format!("Timed out recording from {}", source.name),
+        )
+    })??;
+
+    drop(child);
+
+    let samples: Vec<i32> = recording
+        .chunks_exact(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as i32)
+        .collect();
+
+    let peak = samples.iter().map(|s| s.abs()).max().unwrap_or_default() as f64 / 32768.0;
+    let rms = (samples
+        .iter()
+        .map(|s| (*s as f64 / 32768.0).powi(2))
+        .sum::<f64>()
+        / samples.len().max(1) as f64)
+        .sqrt();
+    let clipped_samples = samples
+        .iter()
+        .filter(|s| s.abs() >= CLIPPING_THRESHOLD)
+        .count() as u64;
+
+    if clipped_samples > 0 {
+        warn!(
+            "{} samples recorded from source {} are clipping",
+            clipped_samples, source.name
+        );
+    }
+
+    pacat::play(&sink.name, 1, SAMPLE_RATE, None, &recording).await?;
+
+    Ok(MicrophoneTestResult {
+        source_name: source.name,
+        sink_name: sink.name,
+        duration_seconds,
+        peak,
+        rms,
+        clipped_samples,
+        is_clipping: clipped_samples > 0,
+    })
+}
+
+async fn find(_type: CardDeviceType, selector: &CardDeviceSelector) -> io::Result<CardDevice> {
+    let (_, sources, sinks) = audio::fetch_devices().await;
+    let card_devices = if _type == CardDeviceType::Source {
+        sources
+    } else {
+        sinks
+    };
+
+    card_devices
+        .into_iter()
+        .find(|card_device| selector.matches(card_device))
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("{} {} does not exist", _type, selector),
+            )
+        })
+}
+
+fn validate_duration(duration_seconds: f64) -> io::Result<f64> {
+    if duration_seconds <= 0.0 || duration_seconds > MAX_DURATION_SECONDS {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The duration {}s is not between 0 and {}s",
+                duration_seconds, MAX_DURATION_SECONDS
+            ),
+        ));
+    }
+
+    Ok(duration_seconds)
+}
+
+fn tone(frequency: f64, duration_seconds: f64) -> Vec<u8> {
+    let sample_count = (duration_seconds * SAMPLE_RATE as f64) as usize;
+    let fade_count = (SAMPLE_RATE as usize / 100).min(sample_count / 2).max(1);
+
+    (0..sample_count)
+        .flat_map(|i| {
+            let fade = (i.min(sample_count - 1 - i) as f64 / fade_count as f64).min(1.0);
+            let value = (2.0 * PI * frequency * i as f64 / SAMPLE_RATE as f64).sin() * 0.5 * fade;
+
+            ((value * i16::MAX as f64) as i16).to_le_bytes()
+        })
+        .collect()
+}
+
+async fn speak(text: &str) -> Option<(u32, Vec<u8>)> {
+    for program in SPEECH_PROGRAMS {
+        if let Ok(output) = Command::new(program)
+            .arg("--stdout")
+            .arg(text)
+            .output()
+            .await
+        {
+            if output.status.success() {
+                return parse_wav(&output.stdout);
+            }
+        }
+    }
+
+    None
+}
+
+fn parse_wav(wav: &[u8]) -> Option<(u32, Vec<u8>)> {
+    if wav.len() < 44 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return None;
+    }
+
+    let rate = u32::from_le_bytes(wav[24..28].try_into().ok()?);
+    let data = wav.windows(4).position(|window| window == b"data")? + 8;
+
+    Some((rate, wav.get(data..)?.to_vec()))
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{self, ErrorKind},
+    sync::Arc,
+};
+
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::{
+        audio,
+        card_device::CardDevice,
+        card_device_type::CardDeviceType,
+        equalizer::{self, Band, EqualizerChain},
+    },
+    device_monitor,
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+    storage,
+};
+
+const EQUALIZER_FILE_NAME: &str = "equalizer.json";
+
+static EQUALIZERS: Lazy<Mutex<Equalizers>> = Lazy::new(|| Mutex::new(Equalizers::default()));
+
+#[derive(Default)]
+struct Equalizers {
+    chains: HashMap<String, EqualizerChain>,
+    failed: HashSet<String>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Settings {
+    #[serde(default)]
+    presets: BTreeMap<String, Vec<Band>>,
+    #[serde(default)]
+    devices: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[schemars(rename = "EqualizerPreset")]
+pub struct Preset {
+    pub name: String,
+    pub bands: Vec<Band>,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct EqualizerState {
+    /// Whether the singlePara LADSPA plugin of the SWH plugins is installed
+    pub plugin: bool,
+    pub presets: Vec<Preset>,
+    /// The preset of each sink, by sink name
+    pub devices: BTreeMap<String, String>,
+    pub chains: Vec<EqualizerChain>,
+}
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "equalizer").await;
+
+    while let Some(message) = rx.recv().await {
+        if let Some(state) = try_downcast_ref!(message, MessageState) {
+            let mut equalizers = EQUALIZERS.lock().await;
+
+            if update_chains(&mut equalizers, &state.sinks()).await {
+                device_monitor::publish_state(&pubsub_tx).await;
+            }
+        }
+    }
+}
+
+pub async fn state() -> io::Result<EqualizerState> {
+    let mut chains: Vec<EqualizerChain> =
+        EQUALIZERS.lock().await.chains.values().cloned().collect();
+
+    chains.sort_by(|a, b| a.sink_name.cmp(&b.sink_name));
+
+    let settings = load().await?;
+
+    Ok(EqualizerState {
+        plugin: equalizer::plugin().is_some(),
+        presets: presets(settings.presets),
+        devices: settings.devices,
+        chains,
+    })
+}
+
+pub async fn list_presets() -> io::Result<Vec<Preset>> {
+    Ok(presets(load().await?.presets))
+}
+
+pub async fn get_preset(name: &str) -> io::Result<Preset> {
+    load()
+        .await?
+        .presets
+        .remove(name)
+        .map(|bands| Preset {
+            name: name.to_owned(),
+            bands,
+        })
+        .ok_or_else(|| preset_not_found(name))
+}
+
+#[instrument]
+pub async fn save_preset(preset: Preset) -> io::Result<Preset> {
+    equalizer::validate_bands(&preset.bands)?;
+
+    storage::update(EQUALIZER_FILE_NAME, |settings: &mut Settings| {
+        settings
+            .presets
+            .insert(preset.name.clone(), preset.bands.clone());
+    })
+    .await?;
+
+    info!("Saved equalizer preset {}", preset.name);
+
+    apply().await;
+
+    Ok(preset)
+}
+
+pub async fn delete_preset(name: &str) -> io::Result<()> {
+    storage::update(EQUALIZER_FILE_NAME, |settings: &mut Settings| {
+        if let Some((sink_name, _)) = settings
+            .devices
+            .iter()
+            .find(|(_, preset)| preset.as_str() == name)
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Preset {} is used by sink {}", name, sink_name),
+            ));
+        }
+
+        settings
+            .presets
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| preset_not_found(name))
+    })
+    .await?
+}
+
+#[instrument]
+pub async fn set_preset(sink_name: &str, preset: Option<&str>) -> io::Result<()> {
+    if let Some(preset) = preset {
+        let (_, _, sinks) = audio::fetch_devices().await;
+
+        if !sinks.iter().any(|sink| sink.name == sink_name) {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Sink {} does not exist", sink_name),
+            ));
+        }
+
+        if EQUALIZERS.lock().await.chains.values().any(|chain| {
+            chain.equalized_sink_name == sink_name
+                || sink_name.starts_with(&format!("{}.equalizer_band", chain.sink_name))
+        }) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Sink {} is already an equalizer", sink_name),
+            ));
+        }
+
+        storage::update(EQUALIZER_FILE_NAME, |settings: &mut Settings| {
+            if !settings.presets.contains_key(preset) {
+                return Err(preset_not_found(preset));
+            }
+
+            settings
+                .devices
+                .insert(sink_name.to_owned(), preset.to_owned());
+
+            Ok(())
+        })
+        .await??;
+    } else {
+        storage::update(EQUALIZER_FILE_NAME, |settings: &mut Settings| {
+            settings.devices.remove(sink_name);
+        })
+        .await?;
+    }
+
+    apply().await;
+
+    Ok(())
+}
+
+async fn apply() {
+    let mut equalizers = EQUALIZERS.lock().await;
+    let (_, _, sinks) = audio::fetch_devices().await;
+
+    equalizers.failed.clear();
+
+    update_chains(&mut equalizers, &sinks).await;
+}
+
+async fn update_chains(equalizers: &mut Equalizers, sinks: &[CardDevice]) -> bool {
+    let settings = match load().await {
+        Ok(settings) => settings,
+        Err(error) => {
+            error!("Could not load the equalizer settings: {}", error);
+
+            return false;
+        }
+    };
+
+    let mut changed = false;
+
+    let default_sink = sinks.iter().find(|sink| sink.is_default);
+    let default_sink_name = default_sink.map(|sink| {
+        equalizers
+            .chains
+            .values()
+            .find(|chain| chain.equalized_sink_name == sink.name)
+            .map(|chain| chain.sink_name.clone())
+            .unwrap_or_else(|| sink.name.clone())
+    });
+
+    equalizers
+        .failed
+        .retain(|name| sinks.iter().any(|sink| &sink.name == name));
+
+    let stale: Vec<String> = equalizers
+        .chains
+        .values()
+        .filter(|chain| {
+            !sinks.iter().any(|sink| sink.name == chain.sink_name)
+                || settings.devices.get(&chain.sink_name) != Some(&chain.preset)
+                || settings.presets.get(&chain.preset) != Some(&chain.bands)
+        })
+        .map(|chain| chain.sink_name.clone())
+        .collect();
+
+    for name in stale {
+        let chain = equalizers.chains.remove(&name).unwrap();
+
+        info!("Tearing down the equalizer of sink {}", name);
+
+        equalizer::remove_chain(&chain).await;
+
+        changed = true;
+    }
+
+    let default_sink_name = match default_sink_name {
+        Some(default_sink_name) => default_sink_name,
+        None => return changed,
+    };
+
+    if !equalizers.chains.contains_key(&default_sink_name)
+        && !equalizers.failed.contains(&default_sink_name)
+    {
+        let preset = settings.devices.get(&default_sink_name).and_then(|preset| {
+            settings
+                .presets
+                .get(preset)
+                .map(|bands| (preset.as_str(), bands.as_slice()))
+        });
+
+        if let (Some((preset, bands)), Some(sink)) = (
+            preset,
+            sinks.iter().find(|sink| sink.name == default_sink_name),
+        ) {
+            match equalizer::create_chain(sink, preset, bands).await {
+                Ok(chain) => {
+                    info!(
+                        "Equalizing sink {} as {} with preset {}",
+                        chain.sink_name, chain.equalized_sink_name, chain.preset
+                    );
+
+                    equalizers.chains.insert(default_sink_name.clone(), chain);
+
+                    changed = true;
+                }
+                Err(error) => {
+                    error!("Could not equalize sink {}: {}", default_sink_name, error);
+
+                    equalizers.failed.insert(default_sink_name.clone());
+                }
+            }
+        }
+    }
+
+    let wanted_default_sink_name = equalizers
+        .chains
+        .get(&default_sink_name)
+        .map(|chain| chain.equalized_sink_name.clone())
+        .unwrap_or(default_sink_name);
+
+    if default_sink.map(|sink| &sink.name) != Some(&wanted_default_sink_name) {
+        let (_, _, sinks) = audio::fetch_devices().await;
+
+        match sinks
+            .iter()
+            .find(|sink| sink.name == wanted_default_sink_name)
+        {
+            Some(sink) if !sink.is_default => match set_default(sink).await {
+                Ok(()) => changed = true,
+                Err(error) => error!("Could not make sink {} the default: {}", sink.name, error),
+            },
+            Some(_) => (),
+            None => error!("Could not find sink {}", wanted_default_sink_name),
+        }
+    }
+
+    changed
+}
+
+async fn set_default(sink: &CardDevice) -> io::Result<()> {
+    audio::set_default_card_device(CardDeviceType::Sink, sink.index).await?;
+    audio::move_audio_clients(CardDeviceType::Sink, sink.index, &sink.name).await
+}
+
+fn presets(presets: BTreeMap<String, Vec<Band>>) -> Vec<Preset> {
+    presets
+        .into_iter()
+        .map(|(name, bands)| Preset { name, bands })
+        .collect()
+}
+
+fn preset_not_found(name: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("Equalizer preset {} does not exist", name),
+    )
+}
+
+async fn load() -> io::Result<Settings> {
+    storage::load(EQUALIZER_FILE_NAME).await
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use evdev::{Device, InputEventKind, Key};
+use tokio::sync::{
+    mpsc::{self, UnboundedSender},
+    Mutex,
+};
+use tracing::{error, info, instrument};
+
+use crate::{
+    config::Config,
+    device::{audio, card_device::CardDevice, card_device_type::CardDeviceType},
+    protocol::command::{self, Command},
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+};
+
+const KEY_RELEASED: i32 = 0;
+const KEY_PRESSED: i32 = 1;
+
+#[instrument]
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let push_to_talk_key = config.hotkeys.push_to_talk_key;
+    let toggle_mute_key = config.hotkeys.toggle_mute_key;
+
+    let keys: Vec<Key> = [push_to_talk_key, toggle_mute_key]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    if keys.is_empty() {
+        return;
+    }
+
+    let (key_tx, mut key_rx) = mpsc::unbounded_channel::<(Key, i32)>();
+
+    for device in open_devices(&config, &keys) {
+        tokio::spawn(read_keys(device, keys.clone(), key_tx.clone()));
+    }
+
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "hotkeys").await;
+
+    let mut default_source = fetch_default_source().await;
+    let mut is_push_to_talk_held = false;
+
+    if push_to_talk_key.is_some() {
+        if let Some(ref source) = default_source {
+            set_mute(source, true, &pubsub_tx).await;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            Some(message) = rx.recv() => {
+                if let Some(state) = try_downcast_ref!(message, MessageState) {
+                    let source = state.sources().iter().find(|s| s.is_default).cloned();
+                    let is_changed = source.as_ref().map(|s| &s.name)
+                        != default_source.as_ref().map(|s| &s.name);
+
+                    default_source = source;
+
+                    if let Some(ref source) = default_source {
+                        if is_changed
+                            && push_to_talk_key.is_some()
+                            && !is_push_to_talk_held
+                            && !source.is_muted
+                        {
+                            set_mute(source, true, &pubsub_tx).await;
+                        }
+                    }
+                }
+            }
+            Some((key, value)) = key_rx.recv() => {
+                let source = match default_source {
+                    Some(ref source) => source,
+                    None => continue,
+                };
+
+                if Some(key) == push_to_talk_key {
+                    let is_held = match value {
+                        KEY_PRESSED => true,
+                        KEY_RELEASED => false,
+                        _ => continue,
+                    };
+
+                    if is_held != is_push_to_talk_held {
+                        is_push_to_talk_held = is_held;
+
+                        set_mute(source, !is_held, &pubsub_tx).await;
+                    }
+                } else if Some(key) == toggle_mute_key && value == KEY_PRESSED {
+                    let source = fetch_default_source().await.unwrap_or_else(|| source.clone());
+
+                    set_mute(&source, !source.is_muted, &pubsub_tx).await;
+                }
+            }
+        }
+    }
+}
+
+fn open_devices(config: &Config, keys: &[Key]) -> Vec<Device> {
+    if !config.hotkeys.input_devices.is_empty() {
+        return config
+            .hotkeys
+            .input_devices
+            .iter()
+            .filter_map(|path| match Device::open(path) {
+                Ok(device) => Some(device),
+                Err(error) => {
+                    error!("Could not open input device {}: {}", path.display(), error);
+
+                    None
+                }
+            })
+            .collect();
+    }
+
+    evdev::enumerate()
+        .map(|(_, device)| device)
+        .filter(|device| {
+            device.supported_keys().map_or(false, |supported_keys| {
+                keys.iter().any(|key| supported_keys.contains(*key))
+            })
+        })
+        .collect()
+}
+
+async fn read_keys(device: Device, keys: Vec<Key>, key_tx: UnboundedSender<(Key, i32)>) {
+    let name = device.name().unwrap_or("unknown").to_owned();
+
+    info!("Listening for hotkeys on input device {}", name);
+
+    let mut events = match device.into_event_stream() {
+        Ok(events) => events,
+        Err(error) => {
+            error!("Could not read from input device {}: {}", name, error);
+
+            return;
+        }
+    };
+
+    loop {
+        match events.next_event().await {
+            Ok(event) => {
+                if let InputEventKind::Key(key) = event.kind() {
+                    if keys.contains(&key) && key_tx.send((key, event.value())).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(error) => {
+                error!("Could not read from input device {}: {}", name, error);
+
+                return;
+            }
+        }
+    }
+}
+
+async fn fetch_default_source() -> Option<CardDevice> {
+    match audio::fetch_card_devices(CardDeviceType::Source).await {
+        Ok(sources) => sources.into_iter().find(|source| source.is_default),
+        Err(error) => {
+            error!("Could not fetch the default source: {}", error);
+
+            None
+        }
+    }
+}
+
+async fn set_mute(
+    source: &CardDevice,
+    mute: bool,
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+) {
+    let command = Command::SetMute {
+        _type: CardDeviceType::Source,
+        index: source.index,
+        mute,
+    };
+
+    if let Err(error) = command::execute(command, pubsub_tx.clone()).await {
+        error!(
+            "Could not set the mute state of the default source: {}",
+            error
+        );
+    }
+}
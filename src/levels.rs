@@ -0,0 +1,232 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::{
+    io::AsyncReadExt,
+    sync::{broadcast, mpsc::UnboundedSender, Mutex, Notify},
+    task::JoinHandle,
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{debug, error, instrument};
+
+use crate::{
+    config::Config,
+    device::{audio_client::INTERNAL_APPLICATION_NAME, card_device_type::CardDeviceType, parec},
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+};
+
+static SUBSCRIBERS: AtomicUsize = AtomicUsize::new(0);
+static SUBSCRIBERS_CHANGED: Lazy<Notify> = Lazy::new(Notify::new);
+static LEVELS_TX: Lazy<broadcast::Sender<Arc<Vec<Level>>>> = Lazy::new(|| broadcast::channel(16).0);
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Level {
+    #[serde(rename = "type")]
+    pub _type: CardDeviceType,
+    pub index: u64,
+    pub name: String,
+    #[schemars(range(min = 0, max = 1))]
+    pub peak: f64,
+    #[schemars(range(min = 0, max = 1))]
+    pub rms: f64,
+}
+
+pub struct LevelsSubscription {
+    rx: broadcast::Receiver<Arc<Vec<Level>>>,
+}
+
+impl LevelsSubscription {
+    pub async fn recv(&mut self) -> Option<Arc<Vec<Level>>> {
+        loop {
+            match self.rx.recv().await {
+                Ok(levels) => return Some(levels),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for LevelsSubscription {
+    fn drop(&mut self) {
+        SUBSCRIBERS.fetch_sub(1, Ordering::Relaxed);
+        SUBSCRIBERS_CHANGED.notify_one();
+    }
+}
+
+struct Meter {
+    _type: CardDeviceType,
+    index: u64,
+    name: String,
+    level: Arc<StdMutex<(f64, f64)>>,
+    ended: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Meter {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub fn subscribe() -> LevelsSubscription {
+    let rx = LEVELS_TX.subscribe();
+
+    SUBSCRIBERS.fetch_add(1, Ordering::Relaxed);
+    SUBSCRIBERS_CHANGED.notify_one();
+
+    LevelsSubscription { rx }
+}
+
+#[instrument]
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "levels").await;
+
+    let mut devices: Vec<(CardDeviceType, u64, String)> = Vec::new();
+    let mut meters: HashMap<String, Meter> = HashMap::new();
+
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / config.level_rate));
+
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        let active = SUBSCRIBERS.load(Ordering::Relaxed) > 0;
+
+        if active {
+            update_meters(&devices, &mut meters, config.level_rate);
+        } else if !meters.is_empty() {
+            debug!("Stopping the level meters");
+
+            meters.clear();
+        }
+
+        tokio::select! {
+            Some(message) = rx.recv() => {
+                if let Some(state) = try_downcast_ref!(message, MessageState) {
+                    devices = state
+                        .sources()
+                        .iter()
+                        .map(|source| (CardDeviceType::Source, source.index, source.name.clone()))
+                        .chain(
+                            state
+                                .sinks()
+                                .iter()
+                                .map(|sink| (CardDeviceType::Sink, sink.index, sink.name.clone())),
+                        )
+                        .collect();
+                }
+            }
+            _ = ticker.tick(), if active => {
+                let mut levels: Vec<Level> = meters
+                    .values()
+                    .filter(|meter| !meter.ended.load(Ordering::Relaxed))
+                    .map(|meter| {
+                        let (peak, rms) = *meter.level.lock().unwrap();
+
+                        Level {
+                            _type: meter._type,
+                            index: meter.index,
+                            name: meter.name.clone(),
+                            peak,
+                            rms,
+                        }
+                    })
+                    .collect();
+
+                levels.sort_by(|a, b| a.name.cmp(&b.name));
+
+                LEVELS_TX.send(Arc::new(levels)).ok();
+            }
+            _ = SUBSCRIBERS_CHANGED.notified() => (),
+        }
+    }
+}
+
+fn update_meters(
+    devices: &[(CardDeviceType, u64, String)],
+    meters: &mut HashMap<String, Meter>,
+    level_rate: f64,
+) {
+    meters.retain(|name, meter| {
+        !meter.ended.load(Ordering::Relaxed) && devices.iter().any(|(_, _, n)| n == name)
+    });
+
+    for (_type, index, name) in devices {
+        if meters.contains_key(name) {
+            continue;
+        }
+
+        let monitor_name = match _type {
+            CardDeviceType::Source => name.clone(),
+            CardDeviceType::Sink => format!("{}.monitor", name),
+        };
+
+        let level = Arc::new(StdMutex::new((0.0, 0.0)));
+        let ended = Arc::new(AtomicBool::new(false));
+
+        debug!("Starting the level meter of {} {}", _type, name);
+
+        meters.insert(
+            name.clone(),
+            Meter {
+                _type: *_type,
+                index: *index,
+                name: name.clone(),
+                level: level.clone(),
+                ended: ended.clone(),
+                task: tokio::spawn(async move {
+                    measure(&monitor_name, level, level_rate).await;
+
+                    debug!("The level meter of {} ended", monitor_name);
+
+                    ended.store(true, Ordering::Relaxed);
+                }),
+            },
+        );
+    }
+}
+
+async fn measure(monitor_name: &str, level: Arc<StdMutex<(f64, f64)>>, level_rate: f64) {
+    let mut child = match parec::spawn(
+        monitor_name,
+        1,
+        parec::SAMPLE_RATE,
+        INTERNAL_APPLICATION_NAME,
+    ) {
+        Ok(child) => child,
+        Err(error) => {
+            error!("Could not record from {}: {}", monitor_name, error);
+
+            return;
+        }
+    };
+
+    let mut stdout = child.stdout.take().unwrap();
+
+    let window = ((parec::SAMPLE_RATE as f64 / level_rate) as usize).max(1) * 2;
+    let mut buffer = vec![0u8; window];
+
+    while stdout.read_exact(&mut buffer).await.is_ok() {
+        let samples: Vec<f64> = buffer
+            .chunks_exact(2)
+            .map(|sample| i16::from_le_bytes([sample[0], sample[1]]) as f64 / 32768.0)
+            .collect();
+
+        let peak = samples.iter().fold(0.0f64, |peak, s| peak.max(s.abs()));
+        let rms = (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+
+        *level.lock().unwrap() = (peak, rms);
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, ErrorKind, Read},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex as StdMutex,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::body::{Body, Bytes};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{self, AsyncRead, AsyncReadExt},
+    process::{Child, Command},
+};
+use tracing::{info, instrument};
+
+use crate::{
+    device::{
+        audio, audio_client::LISTENER_APPLICATION_NAME, card_device_type::CardDeviceType, parec,
+    },
+    recorder::encoder,
+};
+
+const CHUNK_SIZE: usize = (encoder::BYTES_PER_SECOND / 20) as usize;
+
+const TICKET_LIFETIME: Duration = Duration::from_secs(60);
+
+static LISTENERS: AtomicUsize = AtomicUsize::new(0);
+static TICKETS: Lazy<StdMutex<HashMap<String, Instant>>> = Lazy::new(Default::default);
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ListenFormat {
+    Wav,
+    Opus,
+}
+
+impl ListenFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ListenFormat::Wav => "audio/wav",
+            ListenFormat::Opus => "audio/ogg",
+        }
+    }
+}
+
+#[derive(Debug, JsonSchema, Serialize)]
+pub struct ListenTicket {
+    /// Passed once as the `ticket` query parameter of `/listen`
+    pub ticket: String,
+    pub expires_in_seconds: u64,
+}
+
+#[derive(Debug)]
+pub struct Listener;
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        LISTENERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub fn acquire(max_listeners: usize) -> Option<Listener> {
+    LISTENERS
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |listeners| {
+            (listeners < max_listeners).then(|| listeners + 1)
+        })
+        .ok()
+        .map(|_| Listener)
+}
+
+pub fn listeners() -> usize {
+    LISTENERS.load(Ordering::Relaxed)
+}
+
+pub fn issue_ticket() -> io::Result<ListenTicket> {
+    let mut bytes = [0; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+
+    let ticket = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    let now = Instant::now();
+    let mut tickets = TICKETS.lock().unwrap();
+
+    tickets.retain(|_, expires_at| *expires_at > now);
+    tickets.insert(ticket.clone(), now + TICKET_LIFETIME);
+
+    Ok(ListenTicket {
+        ticket,
+        expires_in_seconds: TICKET_LIFETIME.as_secs(),
+    })
+}
+
+pub fn redeem_ticket(ticket: &str) -> bool {
+    let now = Instant::now();
+    let mut tickets = TICKETS.lock().unwrap();
+
+    tickets.retain(|_, expires_at| *expires_at > now);
+    tickets.remove(ticket).is_some()
+}
+
+#[instrument]
+pub async fn stream(
+    listener: Listener,
+    _type: CardDeviceType,
+    name: &str,
+    format: ListenFormat,
+) -> io::Result<Body> {
+    let (_, sources, sinks) = audio::fetch_devices().await;
+    let card_devices = if _type == CardDeviceType::Source {
+        &sources
+    } else {
+        &sinks
+    };
+
+    if !card_devices
+        .iter()
+        .any(|card_device| card_device.name == name)
+    {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{} {} does not exist", _type, name),
+        ));
+    }
+
+    let device_name = match _type {
+        CardDeviceType::Source => name.to_owned(),
+        CardDeviceType::Sink => format!("{}.monitor", name),
+    };
+
+    let mut parec = parec::spawn(
+        &device_name,
+        encoder::CHANNELS,
+        encoder::SAMPLE_RATE,
+        LISTENER_APPLICATION_NAME,
+    )?;
+    let mut audio = parec.stdout.take().unwrap();
+
+    let (mut reader, opusenc): (Box<dyn AsyncRead + Send + Unpin>, Option<Child>) = match format {
+        ListenFormat::Wav => (
+            Box::new(Cursor::new(encoder::wav_header(u64::MAX)).chain(audio)),
+            None,
+        ),
+        ListenFormat::Opus => {
+            let mut opusenc = Command::new("opusenc")
+                .arg("--quiet")
+                .arg("--raw")
+                .arg("--raw-bits=16")
+                .arg(format!("--raw-rate={}", encoder::SAMPLE_RATE))
+                .arg(format!("--raw-chan={}", encoder::CHANNELS))
+                .arg("--max-delay=100")
+                .arg("-")
+                .arg("-")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|error| {
+                    io::Error::new(
+                        ErrorKind::Other,
+                        format!("Could not run the encoder: {}", error),
+                    )
+                })?;
+
+            let mut stdin = opusenc.stdin.take().unwrap();
+            let encoded = opusenc.stdout.take().unwrap();
+
+            tokio::spawn(async move { io::copy(&mut audio, &mut stdin).await.ok() });
+
+            (Box::new(encoded), Some(opusenc))
+        }
+    };
+
+    let (mut sender, body) = Body::channel();
+
+    info!(
+        "Streaming {} to a listener ({} listening)",
+        device_name,
+        listeners()
+    );
+
+    tokio::spawn(async move {
+        let _listener = listener;
+        let _parec = parec;
+        let _opusenc = opusenc;
+
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+
+        loop {
+            let read = match reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+
+            if sender
+                .send_data(Bytes::copy_from_slice(&buffer[..read]))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+
+        info!("Stopped streaming {}", device_name);
+    });
+
+    Ok(body)
+}
@@ -1,16 +1,45 @@
 #![feature(async_closure)]
 #![feature(default_free_fn)]
+#![recursion_limit = "1024"]
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
 
-use config::Config;
+use config::{Config, DenyPolicy, HotkeysConfig, NotifierConfig, PrivacyConfig, VolumeLimit};
+use protocol::command::Command;
+use routing_graph::RoutingGraphFormat;
+use tokio::signal::unix::{signal, SignalKind};
+use tracing::info;
 
 mod applet_updater;
+mod cli;
 mod config;
+mod control_socket;
+mod dbus;
 mod device;
+mod device_memory;
 mod device_monitor;
+mod device_test;
+mod equalizer;
+mod hotkeys;
+mod levels;
+mod listen;
+mod metrics;
+mod noise_suppression;
+mod notifier;
+mod privacy;
+mod protocol;
 mod pubsub;
+mod recorder;
+mod routing_graph;
+mod scene;
+mod scheduler;
+mod simulator;
+mod soundboard;
+mod storage;
+mod tray;
 mod util;
+mod virtual_devices;
+mod volume_limits;
 mod watchdog;
 mod web;
 
@@ -23,16 +52,87 @@ async fn main() {
 
     let config = Arc::new(get_config());
 
+    if let Some(ref command) = config.command {
+        cli::run(config.socket_path.as_deref(), command).await;
+
+        return;
+    }
+
+    storage::init(config.data_dir.clone());
+
+    if config.simulate {
+        device::simulated_server::enable();
+    }
+
     let (pubsub_tx, _) = pubsub::start();
+    tokio::spawn(virtual_devices::start(pubsub_tx.clone()));
     tokio::spawn(watchdog::audio::start(pubsub_tx.clone()));
-    tokio::spawn(applet_updater::start(pubsub_tx.clone()));
+    tokio::spawn(applet_updater::start(config.clone(), pubsub_tx.clone()));
     tokio::spawn(device_monitor::start(pubsub_tx.clone()));
-    tokio::spawn(web::server::start(config.clone(), pubsub_tx.clone()))
-        .await
-        .unwrap();
+    tokio::spawn(device_memory::start(pubsub_tx.clone()));
+    tokio::spawn(scheduler::start(pubsub_tx.clone()));
+    tokio::spawn(volume_limits::start(config.clone(), pubsub_tx.clone()));
+    tokio::spawn(privacy::start(config.clone(), pubsub_tx.clone()));
+    tokio::spawn(noise_suppression::start(pubsub_tx.clone()));
+    tokio::spawn(equalizer::start(pubsub_tx.clone()));
+    tokio::spawn(soundboard::start(pubsub_tx.clone()));
+    tokio::spawn(levels::start(config.clone(), pubsub_tx.clone()));
+    tokio::spawn(metrics::start(pubsub_tx.clone()));
+    tokio::spawn(control_socket::start(config.clone(), pubsub_tx.clone()));
+    tokio::spawn(hotkeys::start(config.clone(), pubsub_tx.clone()));
+
+    if config.dbus {
+        tokio::spawn(dbus::audio::start(pubsub_tx.clone()));
+    }
+
+    if config.tray {
+        tokio::spawn(tray::start(pubsub_tx.clone()));
+    }
+
+    if config.simulate {
+        tokio::spawn(simulator::start(pubsub_tx.clone()));
+    }
+
+    let server = tokio::spawn(web::server::start(config.clone(), pubsub_tx.clone()));
+
+    let mut terminate = signal(SignalKind::terminate()).unwrap();
+
+    tokio::select! {
+        result = server => result.unwrap(),
+        _ = tokio::signal::ctrl_c() => (),
+        _ = terminate.recv() => (),
+    }
+
+    info!("Shutting down");
+
+    recorder::stop_all().await;
+
+    device::module::unload_managed_modules().await;
 }
 
 fn get_config() -> Config {
+    let default_socket_path = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .map(|runtime_dir| format!("{}/cctl.sock", runtime_dir));
+    let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
+        .unwrap_or_else(|_| std::env::temp_dir().to_str().unwrap().to_owned());
+    let default_waybar_path = format!("{}/cctl-waybar-{{type}}.json", runtime_dir);
+    let default_i3blocks_path = format!("{}/cctl-i3blocks-{{type}}.txt", runtime_dir);
+    let default_data_dir = std::env::var("XDG_DATA_HOME")
+        .map(|data_home| format!("{}/cctl", data_home))
+        .unwrap_or_else(|_| format!("{}/.local/share/cctl", std::env::var("HOME").unwrap()));
+
+    let mut socket_arg = clap::Arg::with_name("socket")
+        .short("s")
+        .long("socket")
+        .takes_value(true)
+        .value_name("PATH")
+        .help("The path of the local control socket, by default $XDG_RUNTIME_DIR/cctl.sock");
+
+    if let Some(ref default_socket_path) = default_socket_path {
+        socket_arg = socket_arg.default_value(default_socket_path);
+    }
+
     let cli_config = clap::App::new("cctl")
         .arg(
             clap::Arg::with_name("host")
@@ -52,6 +152,395 @@ fn get_config() -> Config {
                 .value_name("PORT")
                 .help("The port to listen to"),
         )
+        .arg(socket_arg)
+        .arg(
+            clap::Arg::with_name("admin-token")
+                .long("admin-token")
+                .takes_value(true)
+                .env("CCTL_ADMIN_TOKEN")
+                .value_name("TOKEN")
+                .help("The bearer token required by the admin endpoints, which are disabled without one"),
+        )
+        .arg(
+            clap::Arg::with_name("data-dir")
+                .long("data-dir")
+                .takes_value(true)
+                .default_value(&default_data_dir)
+                .value_name("PATH")
+                .help("The directory to store scenes, remembered devices, schedules, virtual devices and other state in"),
+        )
+        .arg(
+            clap::Arg::with_name("dbus")
+                .long("dbus")
+                .help("Register the org.cctl.Audio service on the session bus"),
+        )
+        .arg(
+            clap::Arg::with_name("tray")
+                .long("tray")
+                .help("Show a tray icon through the StatusNotifierItem protocol"),
+        )
+        .arg(
+            clap::Arg::with_name("notifier")
+                .long("notifier")
+                .takes_value(true)
+                .multiple(true)
+                .use_delimiter(true)
+                .possible_values(&["xfce", "notifications", "waybar", "i3blocks"])
+                .default_value("xfce,notifications")
+                .value_name("NOTIFIER")
+                .help("The notifiers to update when the default devices change"),
+        )
+        .arg(
+            clap::Arg::with_name("xfce-source-launcher")
+                .long("xfce-source-launcher")
+                .takes_value(true)
+                .default_value("toggle_microphone")
+                .value_name("NAME")
+                .help("The name of the XFCE panel launcher showing the default source"),
+        )
+        .arg(
+            clap::Arg::with_name("xfce-sink-launcher")
+                .long("xfce-sink-launcher")
+                .takes_value(true)
+                .value_name("NAME")
+                .help("The name of the XFCE panel launcher showing the default sink"),
+        )
+        .arg(
+            clap::Arg::with_name("notification-timeout")
+                .long("notification-timeout")
+                .takes_value(true)
+                .default_value("1000")
+                .value_name("MILLISECONDS")
+                .validator(|timeout| match timeout.parse::<i32>() {
+                    Ok(timeout) if timeout >= -1 => Ok(()),
+                    _ => Err(format!(
+                        "The notification timeout {} is not a number of milliseconds or -1",
+                        timeout
+                    )),
+                })
+                .help("The time after which desktop notifications expire"),
+        )
+        .arg(
+            clap::Arg::with_name("waybar-path")
+                .long("waybar-path")
+                .takes_value(true)
+                .default_value(&default_waybar_path)
+                .value_name("PATH")
+                .help("The file to stream waybar statuses to, with {type} replaced by source or sink"),
+        )
+        .arg(
+            clap::Arg::with_name("i3blocks-path")
+                .long("i3blocks-path")
+                .takes_value(true)
+                .default_value(&default_i3blocks_path)
+                .value_name("PATH")
+                .help("The file to write i3blocks statuses to, with {type} replaced by source or sink"),
+        )
+        .arg(
+            clap::Arg::with_name("push-to-talk-key")
+                .long("push-to-talk-key")
+                .takes_value(true)
+                .value_name("KEY")
+                .validator(validate_key)
+                .help("The key, e.g. KEY_F13, which unmutes the default source only while held"),
+        )
+        .arg(
+            clap::Arg::with_name("toggle-mute-key")
+                .long("toggle-mute-key")
+                .takes_value(true)
+                .value_name("KEY")
+                .validator(validate_key)
+                .help("The key, e.g. KEY_MICMUTE, which toggles the mute state of the default source"),
+        )
+        .arg(
+            clap::Arg::with_name("input-device")
+                .long("input-device")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("PATH")
+                .help("The input device to read hotkeys from, instead of every device having them"),
+        )
+        .arg(
+            clap::Arg::with_name("volume-limit")
+                .long("volume-limit")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("TARGET=[MIN-]MAX")
+                .validator(|limit| VolumeLimit::from_str(&limit).map(|_| ()))
+                .help("The volume range, in percent, to keep a device name or a form factor (internal, headphones, webcam or headset) in"),
+        )
+        .arg(
+            clap::Arg::with_name("privacy")
+                .long("privacy")
+                .help("Start in privacy mode, keeping the privacy sources muted and alerting about recordings"),
+        )
+        .arg(
+            clap::Arg::with_name("privacy-source")
+                .long("privacy-source")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAME")
+                .help("The source to keep muted in privacy mode, instead of every source"),
+        )
+        .arg(
+            clap::Arg::with_name("privacy-deny")
+                .long("privacy-deny")
+                .takes_value(true)
+                .possible_values(&["kill", "mute"])
+                .value_name("POLICY")
+                .help("What to do in privacy mode with recordings by applications which are not allowed"),
+        )
+        .arg(
+            clap::Arg::with_name("privacy-allow")
+                .long("privacy-allow")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("APPLICATION")
+                .help("The name of an application allowed to record in privacy mode"),
+        )
+        .arg(
+            clap::Arg::with_name("level-rate")
+                .long("level-rate")
+                .takes_value(true)
+                .default_value("10")
+                .value_name("HZ")
+                .validator(|rate| match rate.parse::<f64>() {
+                    Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(()),
+                    _ => Err(format!("The level rate {} is not a positive number", rate)),
+                })
+                .help("How many times per second to send audio levels to the level websocket"),
+        )
+        .arg(
+            clap::Arg::with_name("max-listeners")
+                .long("max-listeners")
+                .takes_value(true)
+                .default_value("2")
+                .value_name("COUNT")
+                .validator(|count| match count.parse::<usize>() {
+                    Ok(_) => Ok(()),
+                    _ => Err(format!("The listener count {} is not a number", count)),
+                })
+                .help("How many clients can listen to a source or sink at the same time"),
+        )
+        .arg(
+            clap::Arg::with_name("simulate")
+                .long("simulate")
+                .help("Run against an in-memory audio server with scripted events instead of PulseAudio"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("scene")
+                .about("Manages scenes through the control socket of a running cctl")
+                .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    clap::SubCommand::with_name("save")
+                        .about("Saves the current setup as a scene")
+                        .arg(clap::Arg::with_name("name").required(true)),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("restore")
+                        .about("Restores a scene")
+                        .arg(clap::Arg::with_name("name").required(true)),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("delete")
+                        .about("Deletes a scene")
+                        .arg(clap::Arg::with_name("name").required(true)),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("loopback")
+                .about("Manages loopbacks through the control socket of a running cctl")
+                .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    clap::SubCommand::with_name("add")
+                        .about("Plays a source on a sink")
+                        .arg(clap::Arg::with_name("source").required(true))
+                        .arg(clap::Arg::with_name("sink").required(true))
+                        .arg(
+                            clap::Arg::with_name("latency")
+                                .long("latency")
+                                .takes_value(true)
+                                .value_name("MILLISECONDS")
+                                .validator(|latency| match latency.parse::<u32>() {
+                                    Ok(_) => Ok(()),
+                                    _ => Err(format!(
+                                        "The latency {} is not a number of milliseconds",
+                                        latency
+                                    )),
+                                })
+                                .help("The latency of the loopback"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("remove")
+                        .about("Removes a loopback")
+                        .arg(
+                            clap::Arg::with_name("module-index")
+                                .required(true)
+                                .validator(|index| match index.parse::<u64>() {
+                                    Ok(_) => Ok(()),
+                                    _ => Err(format!("The module index {} is not a number", index)),
+                                })
+                                .help("The module index of the loopback"),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("privacy")
+                .about("Turns privacy mode on or off through the control socket of a running cctl")
+                .arg(
+                    clap::Arg::with_name("mode")
+                        .required(true)
+                        .possible_values(&["on", "off"]),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("test")
+                .about("Tests devices through the control socket of a running cctl")
+                .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    clap::SubCommand::with_name("tone")
+                        .about("Plays a test tone on a sink")
+                        .arg(
+                            clap::Arg::with_name("sink")
+                                .required(true)
+                                .help("The index or name of the sink"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("frequency")
+                                .long("frequency")
+                                .takes_value(true)
+                                .value_name("HZ")
+                                .validator(validate_positive_number)
+                                .help("The frequency of the tone (440 by default)"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("duration")
+                                .long("duration")
+                                .takes_value(true)
+                                .value_name("SECONDS")
+                                .validator(validate_positive_number)
+                                .help("The duration of the tone (2 by default)"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("channels")
+                        .about("Names each channel of a sink on that channel")
+                        .arg(
+                            clap::Arg::with_name("sink")
+                                .required(true)
+                                .help("The index or name of the sink"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("microphone")
+                        .about("Records from a source, plays the recording back and reports its levels")
+                        .arg(
+                            clap::Arg::with_name("source")
+                                .required(true)
+                                .help("The index or name of the source"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("sink")
+                                .long("sink")
+                                .takes_value(true)
+                                .value_name("SINK")
+                                .help("The index or name of the sink to play back on, instead of the default sink"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("duration")
+                                .long("duration")
+                                .takes_value(true)
+                                .value_name("SECONDS")
+                                .validator(validate_positive_number)
+                                .help("How long to record (5 by default)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("noise-suppression")
+                .about("Turns noise suppression and echo cancellation for a source on or off through the control socket of a running cctl")
+                .arg(
+                    clap::Arg::with_name("mode")
+                        .required(true)
+                        .possible_values(&["on", "off"]),
+                )
+                .arg(clap::Arg::with_name("source").required(true)),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("equalizer")
+                .about("Equalizes a sink with a preset, or stops equalizing it, through the control socket of a running cctl")
+                .arg(clap::Arg::with_name("sink").required(true))
+                .arg(
+                    clap::Arg::with_name("preset")
+                        .required(true)
+                        .help("The name of the preset, or off"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("graph")
+                .about("Prints the audio routing graph through the control socket of a running cctl")
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["json", "dot"])
+                        .default_value("json")
+                        .help("Prints JSON, or Graphviz DOT to render with dot -Tsvg"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("soundboard")
+                .about("Controls the soundboard through the control socket of a running cctl")
+                .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    clap::SubCommand::with_name("play")
+                        .about("Plays a clip")
+                        .arg(clap::Arg::with_name("name").required(true))
+                        .arg(
+                            clap::Arg::with_name("sink")
+                                .long("sink")
+                                .takes_value(true)
+                                .value_name("SINK")
+                                .help("The name of the sink to play on, instead of the default sink"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("volume")
+                                .long("volume")
+                                .takes_value(true)
+                                .value_name("PERCENT")
+                                .validator(|volume| match volume.parse::<f64>() {
+                                    Ok(volume) if volume >= 0.0 && volume.is_finite() => Ok(()),
+                                    _ => Err(format!("The volume {} is not a percentage", volume)),
+                                })
+                                .help("The volume of the clip (100 by default)"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("microphone")
+                                .long("microphone")
+                                .help("Plays the clip into the soundboard microphone"),
+                        ),
+                )
+                .subcommand(clap::SubCommand::with_name("stop").about("Stops every clip"))
+                .subcommand(
+                    clap::SubCommand::with_name("microphone")
+                        .about("Mixes a source with the soundboard into a virtual microphone, or turns it off")
+                        .arg(
+                            clap::Arg::with_name("mode")
+                                .required(true)
+                                .possible_values(&["on", "off"]),
+                        )
+                        .arg(
+                            clap::Arg::with_name("source")
+                                .required_if("mode", "on")
+                                .help("The name of the source to mix with the soundboard"),
+                        ),
+                ),
+        )
         .get_matches();
 
     let host: [u8; 4] = cli_config
@@ -70,5 +559,232 @@ fn get_config() -> Config {
 
     let server_addr = SocketAddr::from((host, port));
 
-    Config { server_addr }
+    let simulate = cli_config.is_present("simulate");
+    let simulation_dir =
+        std::env::temp_dir().join(format!("cctl-simulated-{}", std::process::id()));
+
+    let socket_path = if simulate && cli_config.occurrences_of("socket") == 0 {
+        Some(simulation_dir.with_extension("sock"))
+    } else {
+        cli_config.value_of("socket").map(PathBuf::from)
+    };
+    let admin_token = cli_config.value_of("admin-token").map(str::to_owned);
+    let data_dir = if simulate && cli_config.occurrences_of("data-dir") == 0 {
+        simulation_dir
+    } else {
+        PathBuf::from(cli_config.value_of("data-dir").unwrap())
+    };
+
+    let dbus = cli_config.is_present("dbus");
+    let tray = cli_config.is_present("tray");
+
+    let notifiers = cli_config
+        .values_of("notifier")
+        .unwrap()
+        .filter(|_| !simulate || cli_config.occurrences_of("notifier") > 0)
+        .map(|notifier| match notifier {
+            "xfce" => NotifierConfig::Xfce {
+                source_launcher: cli_config
+                    .value_of("xfce-source-launcher")
+                    .unwrap()
+                    .to_owned(),
+                sink_launcher: cli_config.value_of("xfce-sink-launcher").map(str::to_owned),
+            },
+            "notifications" => NotifierConfig::Notifications {
+                timeout: cli_config
+                    .value_of("notification-timeout")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+            },
+            "waybar" => NotifierConfig::Waybar {
+                path: cli_config.value_of("waybar-path").unwrap().to_owned(),
+            },
+            "i3blocks" => NotifierConfig::I3blocks {
+                path: cli_config.value_of("i3blocks-path").unwrap().to_owned(),
+            },
+            _ => unreachable!(),
+        })
+        .collect();
+
+    let hotkeys = HotkeysConfig {
+        push_to_talk_key: cli_config
+            .value_of("push-to-talk-key")
+            .map(|key| evdev::Key::from_str(key).unwrap()),
+        toggle_mute_key: cli_config
+            .value_of("toggle-mute-key")
+            .map(|key| evdev::Key::from_str(key).unwrap()),
+        input_devices: cli_config
+            .values_of("input-device")
+            .map(|paths| paths.map(PathBuf::from).collect())
+            .unwrap_or_default(),
+    };
+
+    let volume_limits = cli_config
+        .values_of("volume-limit")
+        .map(|limits| {
+            limits
+                .map(|limit| VolumeLimit::from_str(limit).unwrap())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let privacy = PrivacyConfig {
+        enabled: cli_config.is_present("privacy"),
+        sources: cli_config
+            .values_of("privacy-source")
+            .map(|names| names.map(str::to_owned).collect())
+            .unwrap_or_default(),
+        deny: cli_config
+            .value_of("privacy-deny")
+            .map(|policy| match policy {
+                "kill" => DenyPolicy::Kill,
+                "mute" => DenyPolicy::Mute,
+                _ => unreachable!(),
+            }),
+        allowed_applications: cli_config
+            .values_of("privacy-allow")
+            .map(|names| names.map(str::to_owned).collect())
+            .unwrap_or_default(),
+    };
+
+    let level_rate: f64 = cli_config.value_of("level-rate").unwrap().parse().unwrap();
+    let max_listeners: usize = cli_config
+        .value_of("max-listeners")
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let command = match cli_config.subcommand() {
+        ("scene", Some(scene_config)) => {
+            let (action, action_config) = scene_config.subcommand();
+            let name = action_config.unwrap().value_of("name").unwrap().to_owned();
+
+            match action {
+                "save" => Some(Command::SaveScene { name }),
+                "restore" => Some(Command::RestoreScene { name }),
+                "delete" => Some(Command::DeleteScene { name }),
+                _ => unreachable!(),
+            }
+        }
+        ("loopback", Some(loopback_config)) => match loopback_config.subcommand() {
+            ("add", Some(add_config)) => Some(Command::AddLoopback {
+                source_name: add_config.value_of("source").unwrap().to_owned(),
+                sink_name: add_config.value_of("sink").unwrap().to_owned(),
+                latency_msec: add_config
+                    .value_of("latency")
+                    .map(|latency| latency.parse().unwrap()),
+            }),
+            ("remove", Some(remove_config)) => Some(Command::RemoveLoopback {
+                module_index: remove_config
+                    .value_of("module-index")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+            }),
+            _ => unreachable!(),
+        },
+        ("privacy", Some(privacy_config)) => Some(Command::SetPrivacy {
+            enabled: privacy_config.value_of("mode").unwrap() == "on",
+        }),
+        ("test", Some(test_config)) => match test_config.subcommand() {
+            ("tone", Some(tone_config)) => Some(Command::PlayTestTone {
+                sink: tone_config.value_of("sink").unwrap().parse().unwrap(),
+                frequency: tone_config
+                    .value_of("frequency")
+                    .map(|frequency| frequency.parse().unwrap()),
+                duration_seconds: tone_config
+                    .value_of("duration")
+                    .map(|duration| duration.parse().unwrap()),
+            }),
+            ("channels", Some(channels_config)) => Some(Command::IdentifyChannels {
+                sink: channels_config.value_of("sink").unwrap().parse().unwrap(),
+            }),
+            ("microphone", Some(microphone_config)) => Some(Command::TestMicrophone {
+                source: microphone_config
+                    .value_of("source")
+                    .unwrap()
+                    .parse()
+                    .unwrap(),
+                sink: microphone_config
+                    .value_of("sink")
+                    .map(|sink| sink.parse().unwrap()),
+                duration_seconds: microphone_config
+                    .value_of("duration")
+                    .map(|duration| duration.parse().unwrap()),
+            }),
+            _ => unreachable!(),
+        },
+        ("noise-suppression", Some(noise_suppression_config)) => {
+            Some(Command::SetNoiseSuppression {
+                source_name: noise_suppression_config
+                    .value_of("source")
+                    .unwrap()
+                    .to_owned(),
+                enabled: noise_suppression_config.value_of("mode").unwrap() == "on",
+            })
+        }
+        ("equalizer", Some(equalizer_config)) => Some(Command::SetEqualizer {
+            sink_name: equalizer_config.value_of("sink").unwrap().to_owned(),
+            preset: equalizer_config
+                .value_of("preset")
+                .filter(|preset| *preset != "off")
+                .map(str::to_owned),
+        }),
+        ("graph", Some(graph_config)) => Some(Command::GetRoutingGraph {
+            format: match graph_config.value_of("format").unwrap() {
+                "dot" => RoutingGraphFormat::Dot,
+                _ => RoutingGraphFormat::Json,
+            },
+        }),
+        ("soundboard", Some(soundboard_config)) => match soundboard_config.subcommand() {
+            ("play", Some(play_config)) => Some(Command::PlayClip {
+                name: play_config.value_of("name").unwrap().to_owned(),
+                sink_name: play_config.value_of("sink").map(str::to_owned),
+                volume: play_config
+                    .value_of("volume")
+                    .map(|volume| volume.parse().unwrap()),
+                microphone: play_config.is_present("microphone"),
+            }),
+            ("stop", Some(_)) => Some(Command::StopClips),
+            ("microphone", Some(microphone_config)) => Some(Command::SetSoundboardMicrophone {
+                source_name: match microphone_config.value_of("mode").unwrap() {
+                    "on" => microphone_config.value_of("source").map(str::to_owned),
+                    _ => None,
+                },
+            }),
+            _ => unreachable!(),
+        },
+        _ => None,
+    };
+
+    Config {
+        server_addr,
+        socket_path,
+        admin_token,
+        data_dir,
+        dbus,
+        tray,
+        notifiers,
+        hotkeys,
+        volume_limits,
+        privacy,
+        level_rate,
+        max_listeners,
+        simulate,
+        command,
+    }
+}
+
+fn validate_key(key: String) -> Result<(), String> {
+    evdev::Key::from_str(&key)
+        .map(|_| ())
+        .map_err(|_| format!("Unknown key {}, expected e.g. KEY_F13", key))
+}
+
+fn validate_positive_number(number: String) -> Result<(), String> {
+    match number.parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(()),
+        _ => Err(format!("{} is not a positive number", number)),
+    }
 }
@@ -0,0 +1,351 @@
+use std::{
+    collections::HashMap,
+    default::default,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex, Weak,
+    },
+    time::Duration,
+};
+
+use once_cell::sync::Lazy;
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+
+use crate::{
+    device::{audio_client, card_device::CardDevice, card_device_type::CardDeviceType, pacmd},
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+};
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(default);
+
+const DURATION_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+#[derive(Default)]
+pub struct Metrics {
+    state: StdMutex<MessageState>,
+    commands: StdMutex<HashMap<String, CommandMetrics>>,
+    subscribers: StdMutex<Vec<(&'static str, Weak<AtomicUsize>)>>,
+    websocket_clients: AtomicUsize,
+}
+
+#[derive(Default)]
+struct CommandMetrics {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+    failures: u64,
+}
+
+pub struct WebsocketClient<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for WebsocketClient<'_> {
+    fn drop(&mut self) {
+        self.metrics
+            .websocket_clients
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub fn observe_command(&self, subcommand: &str, duration: Duration, success: bool) {
+        let mut commands = self.commands.lock().unwrap();
+        let command = commands.entry(subcommand.to_owned()).or_default();
+        let seconds = duration.as_secs_f64();
+
+        for (bucket, bound) in command.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+
+        command.count += 1;
+        command.sum += seconds;
+
+        if !success {
+            command.failures += 1;
+        }
+    }
+
+    pub fn register_subscriber(&self, name: &'static str, depth: &Arc<AtomicUsize>) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        subscribers.retain(|(_, depth)| depth.strong_count() > 0);
+        subscribers.push((name, Arc::downgrade(depth)));
+    }
+
+    pub fn websocket_client(&self) -> WebsocketClient<'_> {
+        self.websocket_clients.fetch_add(1, Ordering::Relaxed);
+
+        WebsocketClient { metrics: self }
+    }
+
+    pub async fn render(&self) -> String {
+        let source_clients = audio_client::fetch_client_indexes(CardDeviceType::Source)
+            .await
+            .unwrap_or_default()
+            .len();
+        let sink_clients = audio_client::fetch_client_indexes(CardDeviceType::Sink)
+            .await
+            .unwrap_or_default()
+            .len();
+
+        let mut output = String::new();
+
+        self.render_devices(&mut output);
+
+        write_header(
+            &mut output,
+            "cctl_clients",
+            "gauge",
+            "The number of audio clients.",
+        );
+        writeln!(output, "cctl_clients{{type=\"source\"}} {}", source_clients).unwrap();
+        writeln!(output, "cctl_clients{{type=\"sink\"}} {}", sink_clients).unwrap();
+
+        self.render_commands(&mut output);
+        self.render_subscribers(&mut output);
+
+        write_header(
+            &mut output,
+            "cctl_websocket_clients",
+            "gauge",
+            "The number of connected websocket clients.",
+        );
+        writeln!(
+            output,
+            "cctl_websocket_clients {}",
+            self.websocket_clients.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        output
+    }
+
+    fn render_devices(&self, output: &mut String) {
+        let state = self.state.lock().unwrap().clone();
+        let devices: Vec<(&str, CardDevice)> = state
+            .sources()
+            .iter()
+            .map(|source| ("source", source.clone()))
+            .chain(state.sinks().iter().map(|sink| ("sink", sink.clone())))
+            .collect();
+
+        write_header(output, "cctl_cards", "gauge", "The number of audio cards.");
+        writeln!(output, "cctl_cards {}", state.cards().len()).unwrap();
+
+        write_header(
+            output,
+            "cctl_devices",
+            "gauge",
+            "The number of audio devices.",
+        );
+        writeln!(
+            output,
+            "cctl_devices{{type=\"source\"}} {}",
+            state.sources().len()
+        )
+        .unwrap();
+        writeln!(
+            output,
+            "cctl_devices{{type=\"sink\"}} {}",
+            state.sinks().len()
+        )
+        .unwrap();
+
+        write_header(
+            output,
+            "cctl_device_volume_percent",
+            "gauge",
+            "The volume of an audio device.",
+        );
+
+        for (_type, device) in &devices {
+            writeln!(
+                output,
+                "cctl_device_volume_percent{{{}}} {}",
+                device_labels(_type, device),
+                device.volume
+            )
+            .unwrap();
+        }
+
+        write_header(
+            output,
+            "cctl_device_muted",
+            "gauge",
+            "Whether an audio device is muted.",
+        );
+
+        for (_type, device) in &devices {
+            writeln!(
+                output,
+                "cctl_device_muted{{{}}} {}",
+                device_labels(_type, device),
+                device.is_muted as u8
+            )
+            .unwrap();
+        }
+
+        write_header(
+            output,
+            "cctl_device_default",
+            "gauge",
+            "Whether an audio device is the default one.",
+        );
+
+        for (_type, device) in &devices {
+            writeln!(
+                output,
+                "cctl_device_default{{{}}} {}",
+                device_labels(_type, device),
+                device.is_default as u8
+            )
+            .unwrap();
+        }
+
+        write_header(
+            output,
+            "cctl_device_state",
+            "gauge",
+            "The state of an audio device.",
+        );
+
+        for (_type, device) in &devices {
+            for state in ["running", "idle", "suspended"] {
+                writeln!(
+                    output,
+                    "cctl_device_state{{{},state=\"{}\"}} {}",
+                    device_labels(_type, device),
+                    state,
+                    (device.state.to_string() == state) as u8
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    fn render_commands(&self, output: &mut String) {
+        let commands = self.commands.lock().unwrap();
+
+        write_header(
+            output,
+            "cctl_backend_command_duration_seconds",
+            "histogram",
+            "The duration of pacmd subcommands.",
+        );
+
+        for (subcommand, command) in commands.iter() {
+            for (bucket, bound) in command.buckets.iter().zip(DURATION_BUCKETS) {
+                writeln!(
+                    output,
+                    "cctl_backend_command_duration_seconds_bucket{{subcommand=\"{}\",le=\"{}\"}} {}",
+                    subcommand, bound, bucket
+                )
+                .unwrap();
+            }
+
+            writeln!(
+                output,
+                "cctl_backend_command_duration_seconds_bucket{{subcommand=\"{}\",le=\"+Inf\"}} {}",
+                subcommand, command.count
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "cctl_backend_command_duration_seconds_sum{{subcommand=\"{}\"}} {}",
+                subcommand, command.sum
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "cctl_backend_command_duration_seconds_count{{subcommand=\"{}\"}} {}",
+                subcommand, command.count
+            )
+            .unwrap();
+        }
+
+        write_header(
+            output,
+            "cctl_backend_command_failures_total",
+            "counter",
+            "The number of failed pacmd subcommands.",
+        );
+
+        for (subcommand, command) in commands.iter() {
+            writeln!(
+                output,
+                "cctl_backend_command_failures_total{{subcommand=\"{}\"}} {}",
+                subcommand, command.failures
+            )
+            .unwrap();
+        }
+    }
+
+    fn render_subscribers(&self, output: &mut String) {
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+
+        for (name, depth) in self.subscribers.lock().unwrap().iter() {
+            if let Some(depth) = depth.upgrade() {
+                *depths.entry(*name).or_default() += depth.load(Ordering::Relaxed);
+            }
+        }
+
+        write_header(
+            output,
+            "cctl_pubsub_queue_depth",
+            "gauge",
+            "The number of messages waiting to be received.",
+        );
+
+        for (name, depth) in depths {
+            writeln!(
+                output,
+                "cctl_pubsub_queue_depth{{subscriber=\"{}\"}} {}",
+                name, depth
+            )
+            .unwrap();
+        }
+    }
+}
+
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    pacmd::set_observer(|subcommand, duration, success| {
+        METRICS.observe_command(subcommand, duration, success)
+    });
+
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "metrics").await;
+
+    loop {
+        if let Some(message) = rx.recv().await {
+            if let Some(state) = try_downcast_ref!(message, MessageState) {
+                *METRICS.state.lock().unwrap() = state.clone();
+            }
+        }
+    }
+}
+
+fn write_header(output: &mut String, name: &str, _type: &str, help: &str) {
+    writeln!(output, "# HELP {} {}", name, help).unwrap();
+    writeln!(output, "# TYPE {} {}", name, _type).unwrap();
+}
+
+fn device_labels(_type: &str, device: &CardDevice) -> String {
+    format!(
+        "type=\"{}\",name=\"{}\"",
+        _type,
+        escape_label_value(&device.name)
+    )
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, ErrorKind},
+    sync::Arc,
+};
+
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::{
+        audio,
+        card_device::CardDevice,
+        card_device_type::CardDeviceType,
+        noise_suppression::{self, Chain},
+    },
+    device_monitor,
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+    storage,
+};
+
+const NOISE_SUPPRESSION_FILE_NAME: &str = "noise_suppression.json";
+
+static CHAINS: Lazy<Mutex<HashMap<String, Chain>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct NoiseSuppressionState {
+    /// Whether the RNNoise LADSPA plugin is installed
+    pub rnnoise: bool,
+    /// The names of the sources to process whenever they are present
+    pub sources: Vec<String>,
+    pub chains: Vec<Chain>,
+}
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "noise_suppression").await;
+
+    while let Some(message) = rx.recv().await {
+        if let Some(state) = try_downcast_ref!(message, MessageState) {
+            if update_chains(&state.sources()).await {
+                device_monitor::publish_state(&pubsub_tx).await;
+            }
+        }
+    }
+}
+
+pub async fn state() -> io::Result<NoiseSuppressionState> {
+    let mut chains: Vec<Chain> = CHAINS.lock().await.values().cloned().collect();
+
+    chains.sort_by(|a, b| a.source_name.cmp(&b.source_name));
+
+    Ok(NoiseSuppressionState {
+        rnnoise: noise_suppression::rnnoise_plugin().is_some(),
+        sources: load().await?.into_iter().collect(),
+        chains,
+    })
+}
+
+#[instrument]
+pub async fn set_enabled(source_name: &str, enabled: bool) -> io::Result<()> {
+    let mut chains = CHAINS.lock().await;
+    let (_, sources, _) = audio::fetch_devices().await;
+
+    if enabled {
+        let source = sources
+            .iter()
+            .find(|source| source.name == source_name)
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("Source {} does not exist", source_name),
+                )
+            })?;
+
+        if chains
+            .values()
+            .any(|chain| chain.processed_source_name == source_name)
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("Source {} is already a processed source", source_name),
+            ));
+        }
+
+        if !chains.contains_key(source_name) {
+            let chain = enable(source).await?;
+
+            chains.insert(source_name.to_owned(), chain);
+        }
+
+        storage::update(
+            NOISE_SUPPRESSION_FILE_NAME,
+            |names: &mut BTreeSet<String>| {
+                names.insert(source_name.to_owned());
+            },
+        )
+        .await
+    } else {
+        storage::update(
+            NOISE_SUPPRESSION_FILE_NAME,
+            |names: &mut BTreeSet<String>| {
+                names.remove(source_name);
+            },
+        )
+        .await?;
+
+        if let Some(chain) = chains.remove(source_name) {
+            let processed_source = sources
+                .iter()
+                .find(|source| source.name == chain.processed_source_name);
+
+            noise_suppression::remove_chain(&chain).await;
+
+            info!("Stopped processing source {}", source_name);
+
+            if let (Some(processed_source), Some(source)) = (
+                processed_source,
+                sources.iter().find(|source| source.name == source_name),
+            ) {
+                if processed_source.is_default {
+                    set_default(source).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn update_chains(sources: &[CardDevice]) -> bool {
+    let mut chains = CHAINS.lock().await;
+    let mut changed = false;
+
+    let removed: Vec<String> = chains
+        .keys()
+        .filter(|name| !sources.iter().any(|source| &source.name == *name))
+        .cloned()
+        .collect();
+
+    for name in removed {
+        let chain = chains.remove(&name).unwrap();
+
+        info!("Source {} was removed, tearing down its processing", name);
+
+        noise_suppression::remove_chain(&chain).await;
+
+        changed = true;
+    }
+
+    let names = match load().await {
+        Ok(names) => names,
+        Err(error) => {
+            error!("Could not load the processed sources: {}", error);
+
+            return changed;
+        }
+    };
+
+    for source in sources
+        .iter()
+        .filter(|source| names.contains(&source.name) && !chains.contains_key(&source.name))
+    {
+        match enable(source).await {
+            Ok(chain) => {
+                chains.insert(source.name.clone(), chain);
+
+                changed = true;
+            }
+            Err(error) => error!("Could not process source {}: {}", source.name, error),
+        }
+    }
+
+    changed
+}
+
+async fn enable(source: &CardDevice) -> io::Result<Chain> {
+    let chain = noise_suppression::create_chain(source).await?;
+
+    info!(
+        "Processing source {} as {} (RNNoise = {})",
+        chain.source_name, chain.processed_source_name, chain.rnnoise
+    );
+
+    let (_, sources, _) = audio::fetch_devices().await;
+
+    match sources
+        .iter()
+        .find(|source| source.name == chain.processed_source_name)
+    {
+        Some(processed_source) => {
+            if let Err(error) = set_default(processed_source).await {
+                error!(
+                    "Could not make source {} the default: {}",
+                    processed_source.name, error
+                );
+            }
+        }
+        None => error!("Could not find source {}", chain.processed_source_name),
+    }
+
+    Ok(chain)
+}
+
+async fn set_default(source: &CardDevice) -> io::Result<()> {
+    audio::set_default_card_device(CardDeviceType::Source, source.index).await?;
+    audio::move_audio_clients(CardDeviceType::Source, source.index, &source.name).await
+}
+
+async fn load() -> io::Result<BTreeSet<String>> {
+    storage::load(NOISE_SUPPRESSION_FILE_NAME).await
+}
@@ -0,0 +1,135 @@
+pub mod desktop_entry;
+pub mod i3blocks;
+pub mod notifications;
+pub mod waybar;
+pub mod xfce;
+
+use async_trait::async_trait;
+
+use crate::{
+    config::NotifierConfig,
+    device::{
+        bus::Bus, card_device::CardDevice, card_device_type::CardDeviceType,
+        form_factor::FormFactor,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VolumeLevel {
+    Muted,
+    Low,
+    Medium,
+    High,
+}
+
+impl VolumeLevel {
+    pub fn of(device: &CardDevice) -> Self {
+        if device.is_muted {
+            VolumeLevel::Muted
+        } else if device.volume < 25.0 {
+            VolumeLevel::Low
+        } else if device.volume <= 75.0 {
+            VolumeLevel::Medium
+        } else {
+            VolumeLevel::High
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VolumeLevel::Muted => "muted",
+            VolumeLevel::Low => "low",
+            VolumeLevel::Medium => "medium",
+            VolumeLevel::High => "high",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub _type: CardDeviceType,
+    pub device: CardDevice,
+    pub level: VolumeLevel,
+    pub device_changed: bool,
+}
+
+impl Notification {
+    pub fn new(_type: CardDeviceType, device: CardDevice, device_changed: bool) -> Self {
+        let level = VolumeLevel::of(&device);
+
+        Self {
+            _type,
+            device,
+            level,
+            device_changed,
+        }
+    }
+
+    pub fn icon(&self) -> String {
+        if self._type == CardDeviceType::Source {
+            return format!("microphone-sensitivity-{}-symbolic", self.level.as_str());
+        }
+
+        if self.level == VolumeLevel::Muted {
+            return "audio-volume-muted-symbolic".to_owned();
+        }
+
+        match (&self.device.form_factor, &self.device.bus) {
+            (FormFactor::Headset, _) => "audio-headset-symbolic".to_owned(),
+            (FormFactor::Headphones, _) | (_, Bus::Bluetooth) => {
+                "audio-headphones-symbolic".to_owned()
+            }
+            _ => format!("audio-volume-{}-symbolic", self.level.as_str()),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        if self.device.is_muted {
+            "Muted".to_owned()
+        } else {
+            format!("{}%", self.device.volume)
+        }
+    }
+}
+
+pub struct Alert {
+    pub icon: String,
+    pub summary: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify(&mut self, notification: &Notification);
+
+    async fn alert(&mut self, _alert: &Alert) {}
+}
+
+pub fn from_config(config: &NotifierConfig) -> Box<dyn Notifier> {
+    match config {
+        NotifierConfig::Xfce {
+            source_launcher,
+            sink_launcher,
+        } => Box::new(xfce::XfceNotifier::new(
+            source_launcher.clone(),
+            sink_launcher.clone(),
+        )),
+        NotifierConfig::Notifications { timeout } => {
+            Box::new(notifications::NotificationsNotifier::new(*timeout))
+        }
+        NotifierConfig::Waybar { path } => Box::new(waybar::WaybarNotifier::new(path.clone())),
+        NotifierConfig::I3blocks { path } => {
+            Box::new(i3blocks::I3blocksNotifier::new(path.clone()))
+        }
+    }
+}
+
+pub fn status_path(path: &str, _type: CardDeviceType) -> String {
+    path.replace(
+        "{type}",
+        match _type {
+            CardDeviceType::Source => "source",
+            CardDeviceType::Sink => "sink",
+        },
+    )
+}
@@ -0,0 +1,171 @@
+use std::fmt::Display;
+
+pub struct DesktopEntry {
+    lines: Vec<String>,
+}
+
+impl DesktopEntry {
+    pub fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(str::to_owned).collect(),
+        }
+    }
+
+    pub fn get(&self, group: &str, key: &str) -> Option<&str> {
+        self.find(group, key)
+            .and_then(|index| self.lines[index].split_once('='))
+            .map(|(_, value)| value.trim())
+    }
+
+    pub fn set(&mut self, group: &str, key: &str, value: &str) {
+        let line = format!("{}={}", key, value);
+
+        if let Some(index) = self.find(group, key) {
+            self.lines[index] = line;
+        } else if let Some(index) = self.group_end(group) {
+            self.lines.insert(index, line);
+        } else {
+            self.lines.push(format!("[{}]", group));
+            self.lines.push(line);
+        }
+    }
+
+    fn find(&self, group: &str, key: &str) -> Option<usize> {
+        let mut in_group = false;
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let line = line.trim();
+
+            if let Some(name) = parse_group(line) {
+                in_group = name == group;
+            } else if in_group && !line.starts_with('#') {
+                if let Some((line_key, _)) = line.split_once('=') {
+                    if line_key.trim() == key {
+                        return Some(index);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn group_end(&self, group: &str) -> Option<usize> {
+        let mut end: Option<usize> = None;
+        let mut in_group = false;
+
+        for (index, line) in self.lines.iter().enumerate() {
+            let line = line.trim();
+
+            if let Some(name) = parse_group(line) {
+                in_group = name == group;
+
+                if in_group {
+                    end.replace(index + 1);
+                }
+            } else if in_group && !line.is_empty() {
+                end.replace(index + 1);
+            }
+        }
+
+        end
+    }
+}
+
+impl Display for DesktopEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_group(line: &str) -> Option<&str> {
+    line.strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DesktopEntry;
+
+    const ENTRY: &str = "\
+[Desktop Entry]
+Version=1.0
+# Icon=commented-out
+Exec = pavucontrol --tab=4
+Icon=audio-input-microphone
+Name=Microphone
+
+[Desktop Action Mute]
+Exec=pactl set-source-mute @DEFAULT_SOURCE@ toggle
+Icon=microphone-sensitivity-muted
+";
+
+    #[test]
+    fn reads_keys_of_the_given_group() {
+        let entry = DesktopEntry::parse(ENTRY);
+
+        assert_eq!(
+            entry.get("Desktop Entry", "Exec"),
+            Some("pavucontrol --tab=4")
+        );
+        assert_eq!(
+            entry.get("Desktop Entry", "Icon"),
+            Some("audio-input-microphone")
+        );
+        assert_eq!(
+            entry.get("Desktop Action Mute", "Icon"),
+            Some("microphone-sensitivity-muted")
+        );
+        assert_eq!(entry.get("Desktop Entry", "Comment"), None);
+        assert_eq!(entry.get("Desktop Action Unmute", "Exec"), None);
+    }
+
+    #[test]
+    fn rewrites_only_the_line_of_the_given_group() {
+        let mut entry = DesktopEntry::parse(ENTRY);
+
+        entry.set("Desktop Entry", "Icon", "microphone-disabled-symbolic");
+        entry.set("Desktop Entry", "Exec", "pavucontrol --tab=3");
+
+        assert_eq!(
+            entry.to_string(),
+            ENTRY
+                .replace(
+                    "Icon=audio-input-microphone",
+                    "Icon=microphone-disabled-symbolic"
+                )
+                .replace("Exec = pavucontrol --tab=4", "Exec=pavucontrol --tab=3")
+        );
+    }
+
+    #[test]
+    fn adds_missing_keys_at_the_end_of_their_group() {
+        let mut entry = DesktopEntry::parse(ENTRY);
+
+        entry.set("Desktop Entry", "Comment", "Default source");
+
+        assert_eq!(
+            entry.to_string(),
+            ENTRY.replace(
+                "Name=Microphone\n",
+                "Name=Microphone\nComment=Default source\n"
+            )
+        );
+    }
+
+    #[test]
+    fn adds_missing_groups() {
+        let mut entry = DesktopEntry::parse("[Desktop Entry]\nName=Microphone\n");
+
+        entry.set("Desktop Action Mute", "Exec", "true");
+
+        assert_eq!(
+            entry.to_string(),
+            "[Desktop Entry]\nName=Microphone\n[Desktop Action Mute]\nExec=true\n"
+        );
+    }
+}
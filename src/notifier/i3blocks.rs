@@ -0,0 +1,47 @@
+use async_trait::async_trait;
+use tracing::error;
+
+use crate::device::card_device_type::CardDeviceType;
+
+use super::{status_path, Notification, Notifier, VolumeLevel};
+
+pub struct I3blocksNotifier {
+    path: String,
+}
+
+impl I3blocksNotifier {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl Notifier for I3blocksNotifier {
+    async fn notify(&mut self, notification: &Notification) {
+        let path = status_path(&self.path, notification._type);
+        let temporary_path = format!("{}.tmp", path);
+
+        let label = match notification._type {
+            CardDeviceType::Source => "MIC",
+            CardDeviceType::Sink => "VOL",
+        };
+
+        let mut status = format!(
+            "{} {}\n{}\n",
+            label,
+            notification.summary(),
+            notification.summary()
+        );
+
+        if notification.level == VolumeLevel::Muted {
+            status.push_str("#FF0000\n");
+        }
+
+        let result = std::fs::write(&temporary_path, status)
+            .and_then(|_| std::fs::rename(&temporary_path, &path));
+
+        if let Err(error) = result {
+            error!("Could not write the i3blocks status to {}: {}", path, error);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tracing::error;
+use zbus::{dbus_proxy, zvariant::Value, Connection};
+
+use crate::device::card_device_type::CardDeviceType;
+
+use super::{Alert, Notification, Notifier};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, &Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+pub struct NotificationsNotifier {
+    timeout: i32,
+    connection: Option<Connection>,
+    source_replaces_id: u32,
+    sink_replaces_id: u32,
+    device_changed_replaces_id: u32,
+}
+
+impl NotificationsNotifier {
+    pub fn new(timeout: i32) -> Self {
+        Self {
+            timeout,
+            connection: None,
+            source_replaces_id: 0,
+            sink_replaces_id: 0,
+            device_changed_replaces_id: 0,
+        }
+    }
+
+    async fn proxy(&mut self) -> zbus::Result<NotificationsProxy<'static>> {
+        if self.connection.is_none() {
+            self.connection.replace(Connection::session().await?);
+        }
+
+        NotificationsProxy::new(self.connection.as_ref().unwrap()).await
+    }
+
+    fn replaces_id(&mut self, notification: &Notification) -> &mut u32 {
+        match (notification.device_changed, notification._type) {
+            (true, _) => &mut self.device_changed_replaces_id,
+            (false, CardDeviceType::Source) => &mut self.source_replaces_id,
+            (false, CardDeviceType::Sink) => &mut self.sink_replaces_id,
+        }
+    }
+
+    async fn send(&mut self, notification: &Notification) -> zbus::Result<()> {
+        let (summary, body) = if notification.device_changed {
+            let summary = match notification._type {
+                CardDeviceType::Source => "Default input changed",
+                CardDeviceType::Sink => "Default output changed",
+            };
+
+            (
+                summary.to_owned(),
+                format!(
+                    "{} ({})",
+                    notification.device.description,
+                    notification.summary()
+                ),
+            )
+        } else {
+            (
+                notification.summary(),
+                notification.device.description.clone(),
+            )
+        };
+
+        let value = Value::from(notification.device.volume.round() as i32);
+        let transient = Value::from(true);
+        let mut hints = HashMap::from([("value", &value)]);
+
+        if !notification.device_changed {
+            hints.insert("transient", &transient);
+        }
+
+        let replaces_id = *self.replaces_id(notification);
+
+        let id = self
+            .proxy()
+            .await?
+            .notify(
+                "cctl",
+                replaces_id,
+                &notification.icon(),
+                &summary,
+                &body,
+                &[],
+                hints,
+                self.timeout,
+            )
+            .await?;
+
+        *self.replaces_id(notification) = id;
+
+        Ok(())
+    }
+
+    async fn send_alert(&mut self, alert: &Alert) -> zbus::Result<()> {
+        self.proxy()
+            .await?
+            .notify(
+                "cctl",
+                0,
+                &alert.icon,
+                &alert.summary,
+                &alert.body,
+                &[],
+                HashMap::new(),
+                -1,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for NotificationsNotifier {
+    async fn notify(&mut self, notification: &Notification) {
+        if let Err(error) = self.send(notification).await {
+            self.connection.take();
+
+            error!("Could not send a desktop notification: {}", error);
+        }
+    }
+
+    async fn alert(&mut self, alert: &Alert) {
+        if let Err(error) = self.send_alert(alert).await {
+            self.connection.take();
+
+            error!("Could not send a desktop notification: {}", error);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::error;
+
+use super::{status_path, Notification, Notifier};
+
+pub struct WaybarNotifier {
+    path: String,
+}
+
+impl WaybarNotifier {
+    pub fn new(path: String) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl Notifier for WaybarNotifier {
+    async fn notify(&mut self, notification: &Notification) {
+        let path = status_path(&self.path, notification._type);
+        let temporary_path = format!("{}.tmp", path);
+
+        let status = json!({
+            "text": notification.summary(),
+            "alt": notification.level.as_str(),
+            "class": notification.level.as_str(),
+            "tooltip": notification.device.description,
+            "percentage": notification.device.volume,
+        });
+
+        let result = std::fs::write(&temporary_path, format!("{}\n", status))
+            .and_then(|_| std::fs::rename(&temporary_path, &path));
+
+        if let Err(error) = result {
+            error!("Could not write the waybar status to {}: {}", path, error);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use glob::glob;
+use tracing::{error, log::debug};
+
+use crate::device::card_device_type::CardDeviceType;
+
+use super::{desktop_entry::DesktopEntry, Notification, Notifier};
+
+const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+
+pub struct XfceNotifier {
+    source_launcher: String,
+    sink_launcher: Option<String>,
+}
+
+impl XfceNotifier {
+    pub fn new(source_launcher: String, sink_launcher: Option<String>) -> Self {
+        Self {
+            source_launcher,
+            sink_launcher,
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for XfceNotifier {
+    async fn notify(&mut self, notification: &Notification) {
+        let launcher = match notification._type {
+            CardDeviceType::Source => &self.source_launcher,
+            CardDeviceType::Sink => match self.sink_launcher {
+                Some(ref sink_launcher) => sink_launcher,
+                None => return,
+            },
+        };
+
+        let icon = notification.icon();
+
+        for path in find_launchers(launcher) {
+            debug!("Setting the icon of {} to {}", path.display(), icon);
+
+            if let Err(error) = set_launcher_icon(&path, &icon) {
+                error!(
+                    "Could not set the icon of {} to {}: {}",
+                    path.display(),
+                    icon,
+                    error
+                );
+            }
+        }
+    }
+}
+
+fn find_launchers(launcher: &str) -> Vec<PathBuf> {
+    let launcher_glob: PathBuf = [
+        std::env::var("HOME").unwrap(),
+        ".config".to_owned(),
+        "xfce4".to_owned(),
+        "panel".to_owned(),
+        "**".to_owned(),
+        "*.desktop".to_owned(),
+    ]
+    .iter()
+    .collect();
+
+    glob(launcher_glob.to_str().unwrap())
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|path| {
+            std::fs::read_to_string(path)
+                .map(|text| {
+                    DesktopEntry::parse(&text).get(DESKTOP_ENTRY_GROUP, "Name") == Some(launcher)
+                })
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+fn set_launcher_icon(path: &Path, icon: &str) -> std::io::Result<()> {
+    let mut desktop_entry = DesktopEntry::parse(&std::fs::read_to_string(path)?);
+
+    if desktop_entry.get(DESKTOP_ENTRY_GROUP, "Icon") == Some(icon) {
+        return Ok(());
+    }
+
+    desktop_entry.set(DESKTOP_ENTRY_GROUP, "Icon", icon);
+
+    let temporary_path = path.with_extension("desktop.tmp");
+
+    std::fs::write(&temporary_path, desktop_entry.to_string())?;
+    std::fs::rename(&temporary_path, path)
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex as StdMutex,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{mpsc::UnboundedSender, Mutex},
+    time::interval,
+};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    config::{Config, DenyPolicy},
+    device::{
+        audio,
+        audio_client::{self, AudioClient},
+        card_device::CardDevice,
+        card_device_type::CardDeviceType,
+        module,
+    },
+    notifier::{self, Alert, Notifier},
+    pubsub::{
+        self, message::Message, message_recording::MessageRecording, message_state::MessageState,
+        message_topic::MessageTopic, try_downcast_ref::try_downcast_ref,
+    },
+    storage,
+};
+
+const RECORDINGS_FILE_NAME: &str = "recordings.json";
+const MAX_RECORDINGS: usize = 1000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ACTIVE: Lazy<StdMutex<HashMap<u64, ActiveRecording>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct ActiveRecording {
+    pub client_index: u64,
+    pub application_name: String,
+    pub process_id: Option<u32>,
+    pub source_name: String,
+    pub started_at: String,
+    pub denied: bool,
+    #[serde(skip)]
+    started: Instant,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Recording {
+    pub application_name: String,
+    pub process_id: Option<u32>,
+    pub source_name: String,
+    pub started_at: String,
+    pub duration_seconds: u64,
+    pub denied: bool,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct PrivacyState {
+    pub enabled: bool,
+    pub active: Vec<ActiveRecording>,
+}
+
+#[instrument]
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    ENABLED.store(config.privacy.enabled, Ordering::Relaxed);
+
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "privacy").await;
+
+    let mut notifiers: Vec<Box<dyn Notifier>> =
+        config.notifiers.iter().map(notifier::from_config).collect();
+
+    let mut poll = interval(Duration::from_secs(2));
+
+    loop {
+        tokio::select! {
+            _ = poll.tick() => {
+                if is_enabled() {
+                    match audio::fetch_card_devices(CardDeviceType::Source).await {
+                        Ok(sources) => mute_sources(&config, &sources).await,
+                        Err(error) => error!("Could not fetch the sources: {}", error),
+                    }
+                }
+
+                match audio_client::fetch_clients(CardDeviceType::Source).await {
+                    Ok(clients) => {
+                        update_recordings(&config, &pubsub_tx, &mut notifiers, clients).await
+                    }
+                    Err(error) => error!("Could not fetch the recording clients: {}", error),
+                }
+            }
+            Some(message) = rx.recv() => {
+                if let Some(state) = try_downcast_ref!(message, MessageState) {
+                    if is_enabled() {
+                        mute_sources(&config, &state.sources()).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    info!(
+        "Turning privacy mode {}",
+        if enabled { "on" } else { "off" }
+    );
+
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn state() -> PrivacyState {
+    let mut active: Vec<ActiveRecording> = ACTIVE.lock().unwrap().values().cloned().collect();
+
+    active.sort_by_key(|recording| recording.client_index);
+
+    PrivacyState {
+        enabled: is_enabled(),
+        active,
+    }
+}
+
+pub async fn recordings() -> io::Result<Vec<Recording>> {
+    storage::load(RECORDINGS_FILE_NAME).await
+}
+
+async fn mute_sources(config: &Config, sources: &[CardDevice]) {
+    for source in sources.iter().filter(|source| {
+        !source.is_muted
+            && (config.privacy.sources.is_empty() || config.privacy.sources.contains(&source.name))
+    }) {
+        warn!("Muting source {} in privacy mode", source.name);
+
+        if let Err(error) = audio::toggle_mute(CardDeviceType::Source, source.index, true).await {
+            error!("Could not mute source {}: {}", source.name, error);
+        }
+    }
+}
+
+async fn update_recordings(
+    config: &Config,
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+    notifiers: &mut [Box<dyn Notifier>],
+    clients: Vec<AudioClient>,
+) {
+    let clients: Vec<AudioClient> = clients
+        .into_iter()
+        .filter(|client| {
+            !client.is_monitor()
+                && !client.is_internal()
+                && !client.module_index.map_or(false, module::is_managed)
+        })
+        .collect();
+
+    let stopped: Vec<ActiveRecording> = {
+        let mut active = ACTIVE.lock().unwrap();
+        let stopped_indexes: Vec<u64> = active
+            .keys()
+            .filter(|index| !clients.iter().any(|client| client.index == **index))
+            .cloned()
+            .collect();
+
+        stopped_indexes
+            .iter()
+            .filter_map(|index| active.remove(index))
+            .collect()
+    };
+
+    for recording in stopped {
+        let duration_seconds = recording.started.elapsed().as_secs();
+
+        info!(
+            "{} stopped recording from {} after {}s",
+            recording.application_name, recording.source_name, duration_seconds
+        );
+
+        pubsub::publish_recording(
+            pubsub_tx,
+            MessageRecording {
+                client_index: recording.client_index,
+                application_name: recording.application_name.clone(),
+                process_id: recording.process_id,
+                source_name: recording.source_name.clone(),
+                is_recording: false,
+                denied: recording.denied,
+                duration_seconds: Some(duration_seconds),
+            },
+        )
+        .await;
+
+        let result = storage::update(RECORDINGS_FILE_NAME, |recordings: &mut Vec<Recording>| {
+            recordings.push(Recording {
+                application_name: recording.application_name,
+                process_id: recording.process_id,
+                source_name: recording.source_name,
+                started_at: recording.started_at,
+                duration_seconds,
+                denied: recording.denied,
+            });
+
+            let excess = recordings.len().saturating_sub(MAX_RECORDINGS);
+            recordings.drain(..excess);
+        })
+        .await;
+
+        if let Err(error) = result {
+            error!("Could not log a recording: {}", error);
+        }
+    }
+
+    for client in clients {
+        if ACTIVE.lock().unwrap().contains_key(&client.index) {
+            continue;
+        }
+
+        let application_name = client
+            .application_name
+            .clone()
+            .or_else(|| client.client_name.clone())
+            .unwrap_or_else(|| "Unknown application".to_owned());
+
+        info!(
+            "{} started recording from {}",
+            application_name, client.card_device_name
+        );
+
+        let deny = if is_enabled()
+            && !config
+                .privacy
+                .allowed_applications
+                .contains(&application_name)
+        {
+            config.privacy.deny
+        } else {
+            None
+        };
+
+        let denied = match deny {
+            Some(policy) => match deny_client(policy, &client).await {
+                Ok(()) => {
+                    warn!("Denied {} from recording ({:?})", application_name, policy);
+
+                    true
+                }
+                Err(error) => {
+                    error!(
+                        "Could not deny {} from recording: {}",
+                        application_name, error
+                    );
+
+                    false
+                }
+            },
+            None => false,
+        };
+
+        if is_enabled() {
+            let alert = Alert {
+                icon: "microphone-sensitivity-high-symbolic".to_owned(),
+                summary: if denied {
+                    format!("{} was denied recording", application_name)
+                } else {
+                    format!("{} is recording", application_name)
+                },
+                body: client.card_device_name.clone(),
+            };
+
+            for notifier in notifiers.iter_mut() {
+                notifier.alert(&alert).await;
+            }
+        }
+
+        pubsub::publish_recording(
+            pubsub_tx,
+            MessageRecording {
+                client_index: client.index,
+                application_name: application_name.clone(),
+                process_id: client.process_id,
+                source_name: client.card_device_name.clone(),
+                is_recording: true,
+                denied,
+                duration_seconds: None,
+            },
+        )
+        .await;
+
+        ACTIVE.lock().unwrap().insert(
+            client.index,
+            ActiveRecording {
+                client_index: client.index,
+                application_name,
+                process_id: client.process_id,
+                source_name: client.card_device_name,
+                started_at: Local::now().to_rfc3339(),
+                denied,
+                started: Instant::now(),
+            },
+        );
+    }
+}
+
+async fn deny_client(policy: DenyPolicy, client: &AudioClient) -> io::Result<()> {
+    match policy {
+        DenyPolicy::Kill => audio_client::kill_client(client.index, CardDeviceType::Source).await,
+        DenyPolicy::Mute => {
+            audio_client::set_client_mute(client.index, CardDeviceType::Source, true).await
+        }
+    }
+}
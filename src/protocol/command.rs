@@ -0,0 +1,390 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io,
+    sync::{mpsc::UnboundedSender, Mutex},
+};
+use tracing::{instrument, log::debug};
+
+use crate::{
+    device::{
+        audio, audio_client, card_device_type::CardDeviceType, card_profile::CardProfile,
+        equalizer::Band, loopback, virtual_device::VirtualDevice,
+    },
+    device_memory, device_monitor,
+    device_test::{self, CardDeviceSelector},
+    equalizer::{self, Preset},
+    noise_suppression, privacy,
+    pubsub::message::Message,
+    routing_graph::{self, RoutingGraphFormat},
+    scene, soundboard, virtual_devices,
+};
+
+use super::event::Event;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Command {
+    GetState,
+    GetRoutingGraph {
+        #[serde(default)]
+        format: RoutingGraphFormat,
+    },
+    SetVolume {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        index: u64,
+        volume: f64,
+    },
+    SetMute {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        index: u64,
+        mute: bool,
+    },
+    SetDefault {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        index: u64,
+        name: String,
+    },
+    SetProfile {
+        index: u64,
+        profile: CardProfile,
+    },
+    MoveClient {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        index: u64,
+        name: String,
+    },
+    SaveScene {
+        name: String,
+    },
+    RestoreScene {
+        name: String,
+    },
+    DeleteScene {
+        name: String,
+    },
+    SetDeviceRestore {
+        key: String,
+        restore: bool,
+    },
+    SetPrivacy {
+        enabled: bool,
+    },
+    AddLoopback {
+        source_name: String,
+        sink_name: String,
+        latency_msec: Option<u32>,
+    },
+    RemoveLoopback {
+        module_index: u64,
+    },
+    CreateVirtualDevice {
+        device: VirtualDevice,
+        #[serde(default)]
+        persist: bool,
+    },
+    RemoveVirtualDevice {
+        name: String,
+    },
+    SetNoiseSuppression {
+        source_name: String,
+        enabled: bool,
+    },
+    SetEqualizer {
+        sink_name: String,
+        preset: Option<String>,
+    },
+    SaveEqualizerPreset {
+        name: String,
+        bands: Vec<Band>,
+    },
+    PlayTestTone {
+        sink: CardDeviceSelector,
+        frequency: Option<f64>,
+        duration_seconds: Option<f64>,
+    },
+    IdentifyChannels {
+        sink: CardDeviceSelector,
+    },
+    TestMicrophone {
+        source: CardDeviceSelector,
+        sink: Option<CardDeviceSelector>,
+        duration_seconds: Option<f64>,
+    },
+    PlayClip {
+        name: String,
+        sink_name: Option<String>,
+        volume: Option<f64>,
+        #[serde(default)]
+        microphone: bool,
+    },
+    StopClips,
+    SetSoundboardMicrophone {
+        source_name: Option<String>,
+    },
+}
+
+impl Command {
+    pub fn requires_admin(&self) -> bool {
+        matches!(
+            self,
+            Command::SetPrivacy { .. }
+                | Command::AddLoopback { .. }
+                | Command::RemoveLoopback { .. }
+                | Command::CreateVirtualDevice { .. }
+                | Command::RemoveVirtualDevice { .. }
+                | Command::TestMicrophone { .. }
+                | Command::PlayClip {
+                    microphone: true,
+                    ..
+                }
+                | Command::SetSoundboardMicrophone { .. }
+        )
+    }
+}
+
+#[instrument]
+pub async fn execute(
+    command: Command,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> io::Result<Event> {
+    let mut event = Event::Done;
+
+    match command {
+        Command::GetState => (),
+        Command::GetRoutingGraph { format } => {
+            debug!("Fetching the routing graph as {:?}", format);
+
+            let graph = routing_graph::fetch().await?;
+
+            event = match format {
+                RoutingGraphFormat::Json => Event::RoutingGraph(graph),
+                RoutingGraphFormat::Dot => Event::RoutingGraphDot {
+                    dot: graph.to_dot(),
+                },
+            };
+        }
+        Command::SetVolume {
+            _type,
+            index,
+            volume,
+        } => {
+            debug!(
+                "Setting the volume of {} device index {} to {}",
+                _type, index, volume
+            );
+
+            audio::set_volume(_type, index, volume).await?;
+        }
+        Command::SetMute { _type, index, mute } => {
+            debug!(
+                "Setting the mute state of {} device index {} to {}",
+                _type, index, mute
+            );
+
+            audio::toggle_mute(_type, index, mute).await?;
+        }
+        Command::SetDefault { _type, index, name } => {
+            debug!(
+                "Setting the default {} device to index {} (name = {})",
+                _type, index, name
+            );
+
+            audio::set_default_card_device(_type, index).await?;
+
+            debug!(
+                "Moving audio clients to {} device index {} (name = {})",
+                _type, index, name
+            );
+
+            audio::move_audio_clients(_type, index, &name).await?;
+        }
+        Command::SetProfile { index, profile } => {
+            debug!(
+                "Setting the default bluetooth card index {} profile to {}",
+                index, profile
+            );
+
+            audio::set_card_profile(index, profile).await?;
+        }
+        Command::MoveClient { _type, index, name } => {
+            debug!(
+                "Moving audio client index {} to {} device {}",
+                index, _type, name
+            );
+
+            audio_client::set_client_card_device(index, _type, &name).await?;
+        }
+        Command::SaveScene { name } => {
+            debug!("Saving the current setup as scene {}", name);
+
+            scene::capture(&name).await?;
+        }
+        Command::RestoreScene { name } => {
+            debug!("Restoring scene {}", name);
+
+            scene::restore(&name).await?;
+        }
+        Command::DeleteScene { name } => {
+            debug!("Deleting scene {}", name);
+
+            scene::delete(&name).await?;
+        }
+        Command::SetDeviceRestore { key, restore } => {
+            debug!("Setting whether to restore device {} to {}", key, restore);
+
+            device_memory::set_restore(&key, restore).await?;
+        }
+        Command::SetPrivacy { enabled } => privacy::set_enabled(enabled),
+        Command::AddLoopback {
+            source_name,
+            sink_name,
+            latency_msec,
+        } => {
+            debug!("Looping source {} back to sink {}", source_name, sink_name);
+
+            loopback::add_loopback(
+                &source_name,
+                &sink_name,
+                latency_msec.unwrap_or(loopback::DEFAULT_LATENCY_MSEC),
+            )
+            .await?;
+        }
+        Command::RemoveLoopback { module_index } => {
+            debug!("Removing loopback module index {}", module_index);
+
+            loopback::remove_loopback(module_index).await?;
+        }
+        Command::CreateVirtualDevice { device, persist } => {
+            debug!(
+                "Creating virtual device {:?} (persist = {})",
+                device, persist
+            );
+
+            virtual_devices::create(device, persist).await?;
+        }
+        Command::RemoveVirtualDevice { name } => {
+            debug!("Removing virtual device {}", name);
+
+            virtual_devices::remove(&name).await?;
+        }
+        Command::SetNoiseSuppression {
+            source_name,
+            enabled,
+        } => {
+            debug!(
+                "Setting whether to process source {} to {}",
+                source_name, enabled
+            );
+
+            noise_suppression::set_enabled(&source_name, enabled).await?;
+        }
+        Command::SetEqualizer { sink_name, preset } => {
+            debug!(
+                "Setting the equalizer preset of sink {} to {:?}",
+                sink_name, preset
+            );
+
+            equalizer::set_preset(&sink_name, preset.as_deref()).await?;
+        }
+        Command::SaveEqualizerPreset { name, bands } => {
+            debug!("Saving equalizer preset {}", name);
+
+            equalizer::save_preset(Preset { name, bands }).await?;
+        }
+        Command::PlayTestTone {
+            sink,
+            frequency,
+            duration_seconds,
+        } => {
+            debug!("Playing a test tone on sink {}", sink);
+
+            device_test::play_tone(&sink, frequency, duration_seconds).await?;
+        }
+        Command::IdentifyChannels { sink } => {
+            debug!("Identifying the channels of sink {}", sink);
+
+            device_test::identify_channels(&sink).await?;
+        }
+        Command::TestMicrophone {
+            source,
+            sink,
+            duration_seconds,
+        } => {
+            debug!("Testing source {}", source);
+
+            let result =
+                device_test::test_microphone(&source, sink.as_ref(), duration_seconds).await?;
+
+            debug!("Tested source {}: {:?}", source, result);
+
+            event = Event::MicrophoneTest(result);
+        }
+        Command::PlayClip {
+            name,
+            sink_name,
+            volume,
+            microphone,
+        } => {
+            debug!("Playing clip {} (microphone = {})", name, microphone);
+
+            soundboard::play(
+                &name,
+                sink_name.as_deref(),
+                volume.unwrap_or(100.0),
+                microphone,
+            )
+            .await?;
+        }
+        Command::StopClips => soundboard::stop_all(),
+        Command::SetSoundboardMicrophone { source_name } => match source_name {
+            Some(source_name) => {
+                debug!("Mixing source {} with the soundboard", source_name);
+
+                soundboard::enable_microphone(&source_name).await?;
+            }
+            None => {
+                debug!("Turning the soundboard microphone off");
+
+                soundboard::disable_microphone().await?;
+            }
+        },
+    }
+
+    device_monitor::publish_state(&pubsub_tx).await;
+
+    Ok(event)
+}
+
+pub async fn execute_json(
+    text: &str,
+    admin: bool,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> Event {
+    let command = match serde_json::from_str::<Command>(text) {
+        Ok(command) => command,
+        Err(error) => {
+            return Event::Error {
+                message: format!("Could not parse command: {}", error),
+            }
+        }
+    };
+
+    if command.requires_admin() && !admin {
+        return Event::Error {
+            message: "Could not execute command: it requires the admin token".to_owned(),
+        };
+    }
+
+    match execute(command, pubsub_tx).await {
+        Ok(event) => event,
+        Err(error) => Event::Error {
+            message: format!("Could not execute command: {}", error),
+        },
+    }
+}
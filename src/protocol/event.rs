@@ -0,0 +1,41 @@
+use serde::Serialize;
+
+use crate::{
+    device_test::MicrophoneTestResult,
+    levels::Level,
+    pubsub::{
+        message::Message, message_recording::MessageRecording, message_state::MessageState,
+        try_downcast_ref::try_downcast_ref,
+    },
+    routing_graph::RoutingGraph,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    State(MessageState),
+    Recording(MessageRecording),
+    Levels { levels: Vec<Level> },
+    MicrophoneTest(MicrophoneTestResult),
+    RoutingGraph(RoutingGraph),
+    RoutingGraphDot { dot: String },
+    Done,
+    Error { message: String },
+}
+
+impl Event {
+    pub fn from_message(message: &Message) -> Option<Self> {
+        try_downcast_ref!(message, MessageState)
+            .cloned()
+            .map(Event::State)
+            .or_else(|| {
+                try_downcast_ref!(message, MessageRecording)
+                    .cloned()
+                    .map(Event::Recording)
+            })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
@@ -1,23 +1,36 @@
 pub mod message;
+pub mod message_recording;
 pub mod message_register;
 pub mod message_state;
 pub mod message_topic;
+pub mod subscriber;
 pub mod try_downcast_ref;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
 use tokio::sync::{
     mpsc::{self, UnboundedReceiver, UnboundedSender},
     Mutex,
 };
 
+use crate::metrics::METRICS;
+use crate::pubsub::message_recording::MessageRecording;
 use crate::pubsub::message_state::MessageState;
 use crate::pubsub::message_topic::MessageTopic;
 
 use self::{
-    message::Message, message_register::MessageRegister, try_downcast_ref::try_downcast_ref,
+    message::Message, message_register::MessageRegister, subscriber::Subscriber,
+    try_downcast_ref::try_downcast_ref,
 };
 
+type Registration = (Arc<UnboundedSender<Message>>, Arc<AtomicUsize>);
+
 pub fn start() -> (
     Arc<Mutex<UnboundedSender<Message>>>,
     tokio::task::JoinHandle<()>,
@@ -29,9 +42,56 @@ pub fn start() -> (
     (Arc::new(Mutex::new(tx)), task)
 }
 
+pub async fn subscribe(
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+    topic: MessageTopic,
+    name: &'static str,
+) -> Subscriber {
+    subscribe_topics(pubsub_tx, &[topic], name).await
+}
+
+pub async fn subscribe_topics(
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+    topics: &[MessageTopic],
+    name: &'static str,
+) -> Subscriber {
+    let (tx, rx) = mpsc::unbounded_channel::<Message>();
+    let tx = Arc::new(tx);
+    let depth = Arc::new(AtomicUsize::new(0));
+
+    METRICS.register_subscriber(name, &depth);
+
+    let pubsub_tx = pubsub_tx.lock().await;
+
+    for topic in topics {
+        pubsub_tx
+            .send(Arc::new(MessageRegister::new(
+                *topic,
+                tx.clone(),
+                depth.clone(),
+            )))
+            .unwrap();
+    }
+
+    Subscriber::new(rx, depth)
+}
+
+pub async fn publish_recording(
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+    message_recording: MessageRecording,
+) {
+    pubsub_tx
+        .lock()
+        .await
+        .send(Arc::new((
+            MessageTopic::Recording,
+            Arc::new(message_recording),
+        )))
+        .unwrap();
+}
+
 async fn start_loop(mut rx: UnboundedReceiver<Message>) {
-    let mut registrations: HashMap<MessageTopic, Vec<Arc<UnboundedSender<Message>>>> =
-        HashMap::new();
+    let mut registrations: HashMap<MessageTopic, Vec<Registration>> = HashMap::new();
 
     loop {
         let message = rx.recv().await;
@@ -43,8 +103,12 @@ async fn start_loop(mut rx: UnboundedReceiver<Message>) {
                 if let Some((topic, message)) =
                     try_downcast_ref!(message, (MessageTopic, Arc<MessageState>)).cloned()
                 {
-                    broadcast(topic, message, &registrations);
+                    broadcast(topic, message, &mut registrations);
                 }
+            } else if let Some((topic, message)) =
+                try_downcast_ref!(message, (MessageTopic, Arc<MessageRecording>)).cloned()
+            {
+                broadcast(topic, message, &mut registrations);
             }
         }
     }
@@ -52,22 +116,24 @@ async fn start_loop(mut rx: UnboundedReceiver<Message>) {
 
 fn handle_register(
     message: &MessageRegister,
-    registrations: &mut HashMap<MessageTopic, Vec<Arc<UnboundedSender<Message>>>>,
+    registrations: &mut HashMap<MessageTopic, Vec<Registration>>,
 ) {
     registrations
         .entry(message.topic())
         .or_insert_with(Vec::new)
-        .push(message.tx());
+        .push((message.tx(), message.depth()));
 }
 
 fn broadcast(
     topic: MessageTopic,
     message: Message,
-    registrations: &HashMap<MessageTopic, Vec<Arc<UnboundedSender<Message>>>>,
+    registrations: &mut HashMap<MessageTopic, Vec<Registration>>,
 ) {
-    if let Some(registrations) = registrations.get(&topic) {
-        for tx in registrations {
-            tx.send(message.clone()).unwrap();
-        }
+    if let Some(registrations) = registrations.get_mut(&topic) {
+        registrations.retain(|(tx, depth)| {
+            depth.fetch_add(1, Ordering::Relaxed);
+
+            tx.send(message.clone()).is_ok()
+        });
     }
 }
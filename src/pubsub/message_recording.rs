@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use super::{message::MessagePayload, message_topic::MessageTopic};
+
+/// Sent when an application starts or stops recording from a source
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct MessageRecording {
+    pub client_index: u64,
+    pub application_name: String,
+    pub process_id: Option<u32>,
+    pub source_name: String,
+    pub is_recording: bool,
+    pub denied: bool,
+    /// Set once the recording stopped
+    pub duration_seconds: Option<u64>,
+}
+
+impl MessagePayload for MessageRecording {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl MessagePayload for (MessageTopic, Arc<MessageRecording>) {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
@@ -1,4 +1,7 @@
-use std::{any::Any, sync::Arc};
+use std::{
+    any::Any,
+    sync::{atomic::AtomicUsize, Arc},
+};
 
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -11,12 +14,18 @@ use super::{
 pub struct MessageRegister {
     topic: MessageTopic,
     tx: Arc<UnboundedSender<Message>>,
+    depth: Arc<AtomicUsize>,
 }
 
 impl MessageRegister {
-    pub fn new(topic: MessageTopic, tx: Arc<UnboundedSender<Message>>) -> Self {
-        Self { topic, tx }
+    pub fn new(
+        topic: MessageTopic,
+        tx: Arc<UnboundedSender<Message>>,
+        depth: Arc<AtomicUsize>,
+    ) -> Self {
+        Self { topic, tx, depth }
     }
+
     pub fn topic(&self) -> MessageTopic {
         self.topic
     }
@@ -24,6 +33,10 @@ impl MessageRegister {
     pub fn tx(&self) -> Arc<UnboundedSender<Message>> {
         self.tx.clone()
     }
+
+    pub fn depth(&self) -> Arc<AtomicUsize> {
+        self.depth.clone()
+    }
 }
 
 impl MessagePayload for MessageRegister {
@@ -1,17 +1,24 @@
 use std::{default::default, sync::Arc, time::SystemTime};
 
+use schemars::JsonSchema;
 use serde::{ser::SerializeStruct, Serialize};
 
-use crate::device::{card::Card, card_device::CardDevice};
+use crate::{
+    device::{card::Card, card_device::CardDevice, loopback::Loopback},
+    recorder::Recording,
+};
 
 use super::{message::MessagePayload, message_topic::MessageTopic};
 
 #[allow(dead_code)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, JsonSchema)]
 pub struct MessageState {
     cards: Arc<Vec<Card>>,
     sources: Arc<Vec<CardDevice>>,
     sinks: Arc<Vec<CardDevice>>,
+    loopbacks: Arc<Vec<Loopback>>,
+    recordings: Arc<Vec<Recording>>,
+    /// Milliseconds since the UNIX epoch
     timestamp: u128,
 }
 
@@ -20,11 +27,15 @@ impl MessageState {
         cards: Arc<Vec<Card>>,
         sources: Arc<Vec<CardDevice>>,
         sinks: Arc<Vec<CardDevice>>,
+        loopbacks: Arc<Vec<Loopback>>,
+        recordings: Arc<Vec<Recording>>,
     ) -> Self {
         Self {
             cards,
             sources,
             sinks,
+            loopbacks,
+            recordings,
             timestamp: SystemTime::now()
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .unwrap()
@@ -32,6 +43,10 @@ impl MessageState {
         }
     }
 
+    pub fn cards(&self) -> Arc<Vec<Card>> {
+        self.cards.clone()
+    }
+
     pub fn sources(&self) -> Arc<Vec<CardDevice>> {
         self.sources.clone()
     }
@@ -39,6 +54,18 @@ impl MessageState {
     pub fn sinks(&self) -> Arc<Vec<CardDevice>> {
         self.sinks.clone()
     }
+
+    pub fn loopbacks(&self) -> Arc<Vec<Loopback>> {
+        self.loopbacks.clone()
+    }
+
+    pub fn recordings(&self) -> Arc<Vec<Recording>> {
+        self.recordings.clone()
+    }
+
+    pub fn timestamp(&self) -> u128 {
+        self.timestamp
+    }
 }
 
 impl Default for MessageState {
@@ -47,6 +74,8 @@ impl Default for MessageState {
             cards: default(),
             sources: default(),
             sinks: default(),
+            loopbacks: default(),
+            recordings: default(),
             timestamp: default(),
         }
     }
@@ -58,11 +87,15 @@ impl Serialize for MessageState {
         S: serde::Serializer,
     {
         let mut s = serializer
-            .serialize_struct(std::any::type_name::<MessageState>(), 3)
+            .serialize_struct(std::any::type_name::<MessageState>(), 6)
             .unwrap();
         s.serialize_field("cards", self.cards.as_ref()).unwrap();
         s.serialize_field("sources", self.sources.as_ref()).unwrap();
         s.serialize_field("sinks", self.sinks.as_ref()).unwrap();
+        s.serialize_field("loopbacks", self.loopbacks.as_ref())
+            .unwrap();
+        s.serialize_field("recordings", self.recordings.as_ref())
+            .unwrap();
         s.serialize_field("timestamp", &self.timestamp).unwrap();
         s.end()
     }
@@ -1,4 +1,5 @@
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum MessageTopic {
     AudioState,
+    Recording,
 }
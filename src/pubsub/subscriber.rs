@@ -0,0 +1,29 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use super::message::Message;
+
+pub struct Subscriber {
+    rx: UnboundedReceiver<Message>,
+    depth: Arc<AtomicUsize>,
+}
+
+impl Subscriber {
+    pub fn new(rx: UnboundedReceiver<Message>, depth: Arc<AtomicUsize>) -> Self {
+        Self { rx, depth }
+    }
+
+    pub async fn recv(&mut self) -> Option<Message> {
+        let message = self.rx.recv().await;
+
+        if message.is_some() {
+            self.depth.fetch_sub(1, Ordering::Relaxed);
+        }
+
+        message
+    }
+}
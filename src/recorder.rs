@@ -0,0 +1,401 @@
+pub mod encoder;
+
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex as StdMutex},
+    time::Duration,
+};
+
+use axum::body::{Body, Bytes};
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::AsyncReadExt,
+    process::{Child, ChildStdout},
+    sync::{mpsc::UnboundedSender, Mutex, Notify},
+    time::sleep,
+};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::{
+        audio, audio_client::RECORDER_APPLICATION_NAME, card_device_type::CardDeviceType, parec,
+    },
+    device_monitor,
+    pubsub::message::Message,
+    storage,
+};
+
+use self::encoder::{Encoder, RecordingFormat};
+
+const RECORDINGS_DIR_NAME: &str = "recordings";
+const CHUNK_SIZE: usize = (encoder::BYTES_PER_SECOND / 10) as usize;
+
+static ACTIVE: Lazy<StdMutex<HashMap<String, ActiveRecording>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+struct ActiveRecording {
+    recording: Recording,
+    stop: Arc<Notify>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema)]
+pub struct RecordingOptions {
+    #[serde(rename = "type")]
+    pub _type: CardDeviceType,
+    /// The name of the source, or of the sink whose monitor to record
+    pub name: String,
+    pub format: RecordingFormat,
+    pub max_duration_seconds: Option<u64>,
+    pub max_size_bytes: Option<u64>,
+}
+
+/// A running recording
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+#[schemars(rename = "RecorderRecording")]
+pub struct Recording {
+    pub file_name: String,
+    #[serde(rename = "type")]
+    pub _type: CardDeviceType,
+    pub card_device_name: String,
+    pub format: RecordingFormat,
+    pub started_at: String,
+    pub duration_seconds: u64,
+    pub size_bytes: u64,
+    pub max_duration_seconds: Option<u64>,
+    pub max_size_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct RecordingFile {
+    pub file_name: String,
+    pub format: RecordingFormat,
+    pub size_bytes: u64,
+    pub modified_at: String,
+    pub is_recording: bool,
+}
+
+pub fn active() -> Vec<Recording> {
+    let mut recordings: Vec<Recording> = ACTIVE
+        .lock()
+        .unwrap()
+        .values()
+        .map(|active| active.recording.clone())
+        .collect();
+
+    recordings.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+    recordings
+}
+
+#[instrument]
+pub async fn start(
+    options: RecordingOptions,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> io::Result<Recording> {
+    let (_, sources, sinks) = audio::fetch_devices().await;
+    let card_devices = if options._type == CardDeviceType::Source {
+        &sources
+    } else {
+        &sinks
+    };
+
+    if !card_devices
+        .iter()
+        .any(|card_device| card_device.name == options.name)
+    {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("{} {} does not exist", options._type, options.name),
+        ));
+    }
+
+    let device_name = match options._type {
+        CardDeviceType::Source => options.name.clone(),
+        CardDeviceType::Sink => format!("{}.monitor", options.name),
+    };
+
+    let dir = storage::path(RECORDINGS_DIR_NAME);
+
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let now = Local::now();
+    let file_name = format!(
+        "{}.{}",
+        now.format("%Y%m%d-%H%M%S-%3f"),
+        options.format.extension()
+    );
+    let path = dir.join(&file_name);
+
+    let mut child = parec::spawn(
+        &device_name,
+        encoder::CHANNELS,
+        encoder::SAMPLE_RATE,
+        RECORDER_APPLICATION_NAME,
+    )?;
+    let stdout = child.stdout.take().unwrap();
+    let encoder = Encoder::create(options.format, &path).await?;
+
+    let recording = Recording {
+        file_name: file_name.clone(),
+        _type: options._type,
+        card_device_name: options.name,
+        format: options.format,
+        started_at: now.to_rfc3339(),
+        duration_seconds: 0,
+        size_bytes: 0,
+        max_duration_seconds: options.max_duration_seconds,
+        max_size_bytes: options.max_size_bytes,
+    };
+    let stop = Arc::new(Notify::new());
+
+    ACTIVE.lock().unwrap().insert(
+        file_name.clone(),
+        ActiveRecording {
+            recording: recording.clone(),
+            stop: stop.clone(),
+        },
+    );
+
+    info!("Recording {} to {}", device_name, file_name);
+
+    tokio::spawn(record(
+        file_name,
+        path,
+        child,
+        stdout,
+        encoder,
+        stop,
+        pubsub_tx.clone(),
+    ));
+
+    device_monitor::publish_state(&pubsub_tx).await;
+
+    Ok(recording)
+}
+
+pub fn stop(file_name: &str) -> io::Result<()> {
+    ACTIVE
+        .lock()
+        .unwrap()
+        .get(file_name)
+        .map(|active| active.stop.notify_one())
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::NotFound,
+                format!("Recording {} is not running", file_name),
+            )
+        })
+}
+
+pub async fn stop_all() {
+    for active in ACTIVE.lock().unwrap().values() {
+        active.stop.notify_one();
+    }
+
+    for _ in 0..50 {
+        if ACTIVE.lock().unwrap().is_empty() {
+            return;
+        }
+
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    error!("Could not finish every recording in time");
+}
+
+pub async fn list() -> io::Result<Vec<RecordingFile>> {
+    let mut entries = match tokio::fs::read_dir(storage::path(RECORDINGS_DIR_NAME)).await {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+
+    let mut files = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+
+        let format = match format_of(&file_name) {
+            Some(format) => format,
+            None => continue,
+        };
+
+        let metadata = entry.metadata().await?;
+
+        files.push(RecordingFile {
+            is_recording: ACTIVE.lock().unwrap().contains_key(&file_name),
+            file_name,
+            format,
+            size_bytes: metadata.len(),
+            modified_at: DateTime::<Local>::from(metadata.modified()?).to_rfc3339(),
+        });
+    }
+
+    files.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+    Ok(files)
+}
+
+pub async fn read(file_name: &str) -> io::Result<(RecordingFormat, u64, Body)> {
+    let (path, format) = finished_file(file_name)?;
+
+    let mut file = tokio::fs::File::open(path).await?;
+    let size_bytes = file.metadata().await?.len();
+
+    let (mut sender, body) = Body::channel();
+
+    tokio::spawn(async move {
+        let mut buffer = vec![0u8; CHUNK_SIZE];
+
+        loop {
+            let read = match file.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+
+            if sender
+                .send_data(Bytes::copy_from_slice(&buffer[..read]))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    Ok((format, size_bytes, body))
+}
+
+pub async fn delete(file_name: &str) -> io::Result<()> {
+    let (path, _) = finished_file(file_name)?;
+
+    tokio::fs::remove_file(path).await?;
+
+    info!("Deleted recording {}", file_name);
+
+    Ok(())
+}
+
+async fn record(
+    file_name: String,
+    path: PathBuf,
+    mut child: Child,
+    mut stdout: ChildStdout,
+    mut encoder: Encoder,
+    stop: Arc<Notify>,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) {
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut recorded_bytes = 0u64;
+
+    loop {
+        let read = tokio::select! {
+            read = stdout.read(&mut buffer) => read,
+            _ = stop.notified() => break,
+        };
+
+        let read = match read {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(error) => {
+                error!(
+                    "Could not read the audio of recording {}: {}",
+                    file_name, error
+                );
+
+                break;
+            }
+        };
+
+        if let Err(error) = encoder.write(&buffer[..read]).await {
+            error!("Could not write recording {}: {}", file_name, error);
+
+            break;
+        }
+
+        recorded_bytes += read as u64;
+
+        let duration_seconds = recorded_bytes / encoder::BYTES_PER_SECOND;
+        let size_bytes = file_size(&path).await;
+
+        let limit_reached = match ACTIVE.lock().unwrap().get_mut(&file_name) {
+            Some(active) => {
+                active.recording.duration_seconds = duration_seconds;
+                active.recording.size_bytes = size_bytes;
+
+                active
+                    .recording
+                    .max_duration_seconds
+                    .map_or(false, |max| duration_seconds >= max)
+                    || active
+                        .recording
+                        .max_size_bytes
+                        .map_or(false, |max| size_bytes >= max)
+            }
+            None => true,
+        };
+
+        if limit_reached {
+            info!("Recording {} reached its limit", file_name);
+
+            break;
+        }
+    }
+
+    child.kill().await.ok();
+
+    if let Err(error) = encoder.finish().await {
+        error!("Could not finish recording {}: {}", file_name, error);
+    }
+
+    ACTIVE.lock().unwrap().remove(&file_name);
+
+    info!(
+        "Stopped recording {} ({} bytes)",
+        file_name,
+        file_size(&path).await
+    );
+
+    device_monitor::publish_state(&pubsub_tx).await;
+}
+
+fn finished_file(file_name: &str) -> io::Result<(PathBuf, RecordingFormat)> {
+    let format = format_of(file_name).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::NotFound,
+            format!("Recording {} does not exist", file_name),
+        )
+    })?;
+
+    if ACTIVE.lock().unwrap().contains_key(file_name) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("Recording {} is still running", file_name),
+        ));
+    }
+
+    Ok((storage::path(RECORDINGS_DIR_NAME).join(file_name), format))
+}
+
+fn format_of(file_name: &str) -> Option<RecordingFormat> {
+    if file_name.starts_with('.') || file_name.contains('/') {
+        return None;
+    }
+
+    Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(RecordingFormat::from_extension)
+}
+
+async fn file_size(path: &Path) -> u64 {
+    tokio::fs::metadata(path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or_default()
+}
@@ -0,0 +1,176 @@
+use std::{path::Path, process::Stdio};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::File,
+    io::{self, AsyncSeekExt, AsyncWriteExt, ErrorKind, SeekFrom},
+    process::{Child, ChildStdin, Command},
+};
+
+pub const SAMPLE_RATE: u32 = 48000;
+pub const CHANNELS: u8 = 2;
+pub const BYTES_PER_SECOND: u64 = SAMPLE_RATE as u64 * CHANNELS as u64 * 2;
+
+const WAV_HEADER_SIZE: u64 = 44;
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingFormat {
+    Wav,
+    Flac,
+    Opus,
+}
+
+impl RecordingFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "wav",
+            RecordingFormat::Flac => "flac",
+            RecordingFormat::Opus => "opus",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav => "audio/wav",
+            RecordingFormat::Flac => "audio/flac",
+            RecordingFormat::Opus => "audio/ogg",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        [
+            RecordingFormat::Wav,
+            RecordingFormat::Flac,
+            RecordingFormat::Opus,
+        ]
+        .into_iter()
+        .find(|format| format.extension() == extension)
+    }
+}
+
+pub enum Encoder {
+    Wav { file: File, data_size: u64 },
+    Process { child: Child, stdin: ChildStdin },
+}
+
+impl Encoder {
+    pub async fn create(format: RecordingFormat, path: &Path) -> io::Result<Self> {
+        match format {
+            RecordingFormat::Wav => {
+                let mut file = File::create(path).await?;
+
+                file.write_all(&wav_header(0)).await?;
+
+                Ok(Encoder::Wav { file, data_size: 0 })
+            }
+            RecordingFormat::Flac => spawn(
+                Command::new("flac")
+                    .arg("--silent")
+                    .arg("--force")
+                    .arg("--force-raw-format")
+                    .arg("--endian=little")
+                    .arg("--sign=signed")
+                    .arg("--bps=16")
+                    .arg(format!("--channels={}", CHANNELS))
+                    .arg(format!("--sample-rate={}", SAMPLE_RATE))
+                    .arg("--output-name")
+                    .arg(path)
+                    .arg("-"),
+            ),
+            RecordingFormat::Opus => spawn(
+                Command::new("opusenc")
+                    .arg("--quiet")
+                    .arg("--raw")
+                    .arg("--raw-bits=16")
+                    .arg(format!("--raw-rate={}", SAMPLE_RATE))
+                    .arg(format!("--raw-chan={}", CHANNELS))
+                    .arg("-")
+                    .arg(path),
+            ),
+        }
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Encoder::Wav { file, data_size } => {
+                file.write_all(data).await?;
+
+                *data_size += data.len() as u64;
+
+                Ok(())
+            }
+            Encoder::Process { stdin, .. } => stdin.write_all(data).await,
+        }
+    }
+
+    pub async fn finish(self) -> io::Result<()> {
+        match self {
+            Encoder::Wav {
+                mut file,
+                data_size,
+            } => {
+                file.seek(SeekFrom::Start(0)).await?;
+                file.write_all(&wav_header(data_size)).await?;
+                file.flush().await
+            }
+            Encoder::Process { mut child, stdin } => {
+                drop(stdin);
+
+                let status = child.wait().await?;
+
+                if !status.success() {
+                    return Err(io::Error::new(
+                        ErrorKind::Other,
+                        format!("The encoder exited with {}", status),
+                    ));
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+fn spawn(command: &mut Command) -> io::Result<Encoder> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| {
+            io::Error::new(
+                ErrorKind::Other,
+                format!("Could not run the encoder: {}", error),
+            )
+        })?;
+
+    let stdin = child.stdin.take().unwrap();
+
+    Ok(Encoder::Process { child, stdin })
+}
+
+pub fn wav_header(data_size: u64) -> Vec<u8> {
+    let data_size = data_size.min(u32::MAX as u64 - WAV_HEADER_SIZE) as u32;
+    let block_align = CHANNELS as u16 * 2;
+
+    let mut header = Vec::with_capacity(WAV_HEADER_SIZE as usize);
+
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(data_size + WAV_HEADER_SIZE as u32 - 8).to_le_bytes());
+    header.extend_from_slice(b"WAVE");
+    header.extend_from_slice(b"fmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&(CHANNELS as u16).to_le_bytes());
+    header.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    header.extend_from_slice(&(BYTES_PER_SECOND as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&16u16.to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_size.to_le_bytes());
+
+    header
+}
@@ -0,0 +1,347 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::io;
+use tracing::instrument;
+
+use crate::device::{
+    audio,
+    audio_client::{self, AudioClient},
+    card_device::CardDevice,
+    card_device_type::CardDeviceType,
+    module::{self, Module},
+};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingGraphFormat {
+    Json,
+    Dot,
+}
+
+impl Default for RoutingGraphFormat {
+    fn default() -> Self {
+        RoutingGraphFormat::Json
+    }
+}
+
+#[derive(Clone, Copy, Debug, JsonSchema, PartialEq, Serialize)]
+#[schemars(rename = "RoutingGraphNodeKind")]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Application,
+    Sink,
+    Source,
+    Monitor,
+    Module,
+}
+
+#[derive(Clone, Copy, Debug, JsonSchema, PartialEq, Serialize)]
+#[schemars(rename = "RoutingGraphEdgeKind")]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    Stream,
+    Monitor,
+    Module,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+#[schemars(rename = "RoutingGraphNode")]
+pub struct Node {
+    /// The kind and name of the node, such as `sink:NAME` or `module:INDEX`
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    pub index: Option<u64>,
+    pub card: Option<String>,
+    pub process_ids: Vec<u32>,
+    pub volume: Option<f64>,
+    pub is_muted: Option<bool>,
+    pub is_default: bool,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+#[schemars(rename = "RoutingGraphEdge")]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+    /// The index of the sink input or source output of a stream
+    pub index: Option<u64>,
+    pub volume: Option<f64>,
+    pub is_muted: Option<bool>,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct RoutingGraph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+impl RoutingGraph {
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph audio {\n    rankdir=LR;\n    node [fontname=\"sans-serif\"];\n");
+
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Application => "shape=ellipse",
+                NodeKind::Sink => "shape=box",
+                NodeKind::Source => "shape=box, style=rounded",
+                NodeKind::Monitor => "shape=box, style=dashed",
+                NodeKind::Module => "shape=component",
+            };
+            let mut label = node.label.clone();
+
+            if let Some(volume) = node.volume {
+                write!(label, "\n{}%", volume).unwrap();
+            }
+
+            if node.is_muted == Some(true) {
+                label.push_str(" (muted)");
+            }
+
+            writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\", {}{}];",
+                escape(&node.id),
+                escape(&label),
+                shape,
+                if node.is_default { ", penwidth=2" } else { "" }
+            )
+            .unwrap();
+        }
+
+        for edge in &self.edges {
+            let mut label = edge.volume.map(|volume| format!("{}%", volume));
+
+            if edge.is_muted == Some(true) {
+                label = Some(format!("{} (muted)", label.unwrap_or_default()));
+            }
+
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [{}{}];",
+                escape(&edge.from),
+                escape(&edge.to),
+                if edge.kind == EdgeKind::Stream {
+                    "style=solid"
+                } else {
+                    "style=dotted"
+                },
+                label
+                    .map(|label| format!(", label=\"{}\"", escape(&label)))
+                    .unwrap_or_default()
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+#[instrument]
+pub async fn fetch() -> io::Result<RoutingGraph> {
+    let (cards, sources, sinks) = audio::fetch_devices().await;
+    let sink_inputs = audio_client::fetch_clients(CardDeviceType::Sink).await?;
+    let source_outputs = audio_client::fetch_clients(CardDeviceType::Source).await?;
+    let modules = module::fetch_modules().await?;
+
+    let mut nodes: BTreeMap<String, Node> = BTreeMap::new();
+    let mut edges = Vec::new();
+
+    let card_name = |card_device: &CardDevice| {
+        cards
+            .iter()
+            .find(|card| !card_device.is_virtual && card.index == card_device.card_index)
+            .map(|card| card.name.clone())
+    };
+
+    for (kind, card_device) in sinks
+        .iter()
+        .map(|sink| (NodeKind::Sink, sink))
+        .chain(sources.iter().map(|source| (NodeKind::Source, source)))
+    {
+        let id = device_id(kind, &card_device.name);
+
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                kind,
+                label: card_device.description.clone(),
+                index: Some(card_device.index),
+                card: card_name(card_device),
+                process_ids: Vec::new(),
+                volume: Some(card_device.volume),
+                is_muted: Some(card_device.is_muted),
+                is_default: card_device.is_default,
+            },
+        );
+
+        if kind == NodeKind::Sink {
+            let monitor_name = format!("{}.monitor", card_device.name);
+            let monitor_id = device_id(NodeKind::Monitor, &monitor_name);
+
+            nodes.insert(
+                monitor_id.clone(),
+                Node {
+                    id: monitor_id.clone(),
+                    kind: NodeKind::Monitor,
+                    label: format!("Monitor of {}", card_device.description),
+                    index: None,
+                    card: None,
+                    process_ids: Vec::new(),
+                    volume: None,
+                    is_muted: None,
+                    is_default: false,
+                },
+            );
+
+            edges.push(Edge {
+                from: id.clone(),
+                to: monitor_id,
+                kind: EdgeKind::Monitor,
+                index: None,
+                volume: None,
+                is_muted: None,
+            });
+        }
+
+        if let Some(module) = card_device
+            .module_index
+            .filter(|_| card_device.is_virtual)
+            .and_then(|index| modules.iter().find(|module| module.index == index))
+        {
+            let module_id = insert_module(&mut nodes, module);
+
+            let (from, to) = if kind == NodeKind::Sink {
+                (id, module_id)
+            } else {
+                (module_id, id)
+            };
+
+            edges.push(Edge {
+                from,
+                to,
+                kind: EdgeKind::Module,
+                index: None,
+                volume: None,
+                is_muted: None,
+            });
+        }
+    }
+
+    for (_type, client) in sink_inputs
+        .iter()
+        .map(|client| (CardDeviceType::Sink, client))
+        .chain(
+            source_outputs
+                .iter()
+                .map(|client| (CardDeviceType::Source, client)),
+        )
+    {
+        let owner_id = match client
+            .module_index
+            .filter(|_| client.client_name.is_none())
+            .and_then(|index| modules.iter().find(|module| module.index == index))
+        {
+            Some(module) => insert_module(&mut nodes, module),
+            None => insert_application(&mut nodes, client),
+        };
+
+        let card_device_id = if _type == CardDeviceType::Source && client.is_monitor() {
+            device_id(NodeKind::Monitor, &client.card_device_name)
+        } else if _type == CardDeviceType::Source {
+            device_id(NodeKind::Source, &client.card_device_name)
+        } else {
+            device_id(NodeKind::Sink, &client.card_device_name)
+        };
+
+        let (from, to) = if _type == CardDeviceType::Sink {
+            (owner_id, card_device_id)
+        } else {
+            (card_device_id, owner_id)
+        };
+
+        edges.push(Edge {
+            from,
+            to,
+            kind: EdgeKind::Stream,
+            index: Some(client.index),
+            volume: Some(client.volume),
+            is_muted: Some(client.is_muted),
+        });
+    }
+
+    Ok(RoutingGraph {
+        nodes: nodes.into_values().collect(),
+        edges,
+    })
+}
+
+fn insert_module(nodes: &mut BTreeMap<String, Node>, module: &Module) -> String {
+    let id = format!("module:{}", module.index);
+
+    nodes.entry(id.clone()).or_insert_with(|| Node {
+        id: id.clone(),
+        kind: NodeKind::Module,
+        label: module.name.clone(),
+        index: Some(module.index),
+        card: None,
+        process_ids: Vec::new(),
+        volume: None,
+        is_muted: None,
+        is_default: false,
+    });
+
+    id
+}
+
+fn insert_application(nodes: &mut BTreeMap<String, Node>, client: &AudioClient) -> String {
+    let name = client
+        .application_name
+        .as_deref()
+        .or(client.client_name.as_deref())
+        .unwrap_or("Unknown");
+    let id = format!("application:{}", name);
+
+    let node = nodes.entry(id.clone()).or_insert_with(|| Node {
+        id: id.clone(),
+        kind: NodeKind::Application,
+        label: name.to_owned(),
+        index: None,
+        card: None,
+        process_ids: Vec::new(),
+        volume: None,
+        is_muted: None,
+        is_default: false,
+    });
+
+    if let Some(process_id) = client.process_id {
+        if !node.process_ids.contains(&process_id) {
+            node.process_ids.push(process_id);
+        }
+    }
+
+    id
+}
+
+fn device_id(kind: NodeKind, name: &str) -> String {
+    match kind {
+        NodeKind::Sink => format!("sink:{}", name),
+        NodeKind::Source => format!("source:{}", name),
+        NodeKind::Monitor => format!("monitor:{}", name),
+        _ => unreachable!(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
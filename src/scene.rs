@@ -0,0 +1,296 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+
+use crate::{
+    device::{
+        audio, audio_client, bus::Bus, card_device::CardDevice, card_device_type::CardDeviceType,
+        card_profile::CardProfile,
+    },
+    storage,
+};
+
+const SCENES_FILE_NAME: &str = "scenes.json";
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Scene {
+    #[serde(default)]
+    pub name: String,
+    pub default_source: Option<String>,
+    pub default_sink: Option<String>,
+    #[serde(default)]
+    pub sources: Vec<SceneDevice>,
+    #[serde(default)]
+    pub sinks: Vec<SceneDevice>,
+    #[serde(default)]
+    pub profiles: Vec<SceneProfile>,
+    #[serde(default)]
+    pub routes: Vec<SceneRoute>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct SceneDevice {
+    pub name: String,
+    #[schemars(range(min = 0, max = 100))]
+    pub volume: f64,
+    pub is_muted: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct SceneProfile {
+    pub card_name: String,
+    pub profile: CardProfile,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct SceneRoute {
+    #[serde(rename = "type")]
+    pub _type: CardDeviceType,
+    pub application_name: String,
+    pub card_device_name: String,
+}
+
+pub async fn list() -> io::Result<Vec<Scene>> {
+    let scenes: BTreeMap<String, Scene> = storage::load(SCENES_FILE_NAME).await?;
+
+    Ok(scenes.into_values().collect())
+}
+
+pub async fn get(name: &str) -> io::Result<Scene> {
+    let mut scenes: BTreeMap<String, Scene> = storage::load(SCENES_FILE_NAME).await?;
+
+    scenes.remove(name).ok_or_else(|| not_found(name))
+}
+
+pub async fn save(scene: Scene) -> io::Result<()> {
+    storage::update(SCENES_FILE_NAME, |scenes: &mut BTreeMap<String, Scene>| {
+        scenes.insert(scene.name.clone(), scene);
+    })
+    .await
+}
+
+pub async fn delete(name: &str) -> io::Result<()> {
+    storage::update(SCENES_FILE_NAME, |scenes: &mut BTreeMap<String, Scene>| {
+        scenes.remove(name)
+    })
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| not_found(name))
+}
+
+#[instrument]
+pub async fn capture(name: &str) -> io::Result<Scene> {
+    let (cards, sources, sinks) = audio::fetch_devices().await;
+
+    let mut routes: Vec<SceneRoute> = Vec::new();
+
+    for _type in [CardDeviceType::Source, CardDeviceType::Sink] {
+        for client in audio_client::fetch_clients(_type).await? {
+            if client.is_cctl() {
+                continue;
+            }
+
+            if let Some(application_name) = client.application_name {
+                if routes
+                    .iter()
+                    .any(|r| r._type == _type && r.application_name == application_name)
+                {
+                    continue;
+                }
+
+                routes.push(SceneRoute {
+                    _type,
+                    application_name,
+                    card_device_name: client.card_device_name,
+                });
+            }
+        }
+    }
+
+    let scene = Scene {
+        name: name.to_owned(),
+        default_source: sources
+            .iter()
+            .find(|s| s.is_default)
+            .map(|s| s.name.clone()),
+        default_sink: sinks.iter().find(|s| s.is_default).map(|s| s.name.clone()),
+        sources: sources.iter().map(to_scene_device).collect(),
+        sinks: sinks.iter().map(to_scene_device).collect(),
+        profiles: cards
+            .iter()
+            .filter(|card| card.bus == Bus::Bluetooth)
+            .map(|card| SceneProfile {
+                card_name: card.name.clone(),
+                profile: card.active_profile.clone(),
+            })
+            .collect(),
+        routes,
+    };
+
+    save(scene.clone()).await?;
+
+    info!("Saved scene {}", name);
+
+    Ok(scene)
+}
+
+#[instrument]
+pub async fn restore(name: &str) -> io::Result<()> {
+    let scene = get(name).await?;
+
+    let (cards, _, _) = audio::fetch_devices().await;
+
+    for scene_profile in &scene.profiles {
+        if let Some(card) = cards.iter().find(|c| c.name == scene_profile.card_name) {
+            if card.active_profile.as_parsed() != scene_profile.profile.as_parsed() {
+                audio::set_card_profile(card.index, scene_profile.profile.clone()).await?;
+            }
+        }
+    }
+
+    let (_, sources, sinks) = audio::fetch_devices().await;
+
+    for (_type, card_devices, scene_devices, default) in [
+        (
+            CardDeviceType::Source,
+            &sources,
+            &scene.sources,
+            &scene.default_source,
+        ),
+        (
+            CardDeviceType::Sink,
+            &sinks,
+            &scene.sinks,
+            &scene.default_sink,
+        ),
+    ] {
+        for scene_device in scene_devices {
+            if let Some(card_device) = card_devices.iter().find(|c| c.name == scene_device.name) {
+                audio::set_volume(_type, card_device.index, scene_device.volume).await?;
+                audio::toggle_mute(_type, card_device.index, scene_device.is_muted).await?;
+            }
+        }
+
+        if let Some(default) = default {
+            if let Some(card_device) = card_devices.iter().find(|c| &c.name == default) {
+                audio::set_default_card_device(_type, card_device.index).await?;
+            }
+        }
+    }
+
+    for _type in [CardDeviceType::Source, CardDeviceType::Sink] {
+        let card_devices = if _type == CardDeviceType::Source {
+            &sources
+        } else {
+            &sinks
+        };
+
+        for client in audio_client::fetch_clients(_type).await? {
+            let route = scene.routes.iter().find(|r| {
+                r._type == _type && Some(&r.application_name) == client.application_name.as_ref()
+            });
+
+            if let Some(route) = route {
+                if client.card_device_name != route.card_device_name
+                    && card_devices
+                        .iter()
+                        .any(|c| c.name == route.card_device_name)
+                {
+                    audio_client::set_client_card_device(
+                        client.index,
+                        _type,
+                        &route.card_device_name,
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+
+    info!("Restored scene {}", name);
+
+    Ok(())
+}
+
+fn to_scene_device(card_device: &CardDevice) -> SceneDevice {
+    SceneDevice {
+        name: card_device.name.clone(),
+        volume: card_device.volume,
+        is_muted: card_device.is_muted,
+    }
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("Scene {} does not exist", name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device::{
+        audio,
+        audio_client::{self, AudioClient},
+        card_device::CardDevice,
+        card_device_type::CardDeviceType,
+        module, simulated_server,
+    };
+
+    const SINK_NAME: &str = "alsa_output.pci-0000_00_1f.3.analog-stereo";
+
+    #[tokio::test]
+    async fn restores_volumes_mute_states_and_routes() {
+        let _lock = simulated_server::reset().await;
+
+        super::capture("simulated").await.unwrap();
+
+        module::load_module("module-null-sink", "sink_name=scene_test")
+            .await
+            .unwrap();
+        audio_client::set_client_card_device(
+            firefox().await.index,
+            CardDeviceType::Sink,
+            "scene_test",
+        )
+        .await
+        .unwrap();
+        simulated_server::set_volume(CardDeviceType::Sink, SINK_NAME, 30.0);
+        audio::toggle_mute(CardDeviceType::Sink, sink().await.index, true)
+            .await
+            .unwrap();
+
+        super::restore("simulated").await.unwrap();
+
+        let sink = sink().await;
+
+        assert_eq!(sink.volume.round(), 100.0);
+        assert!(!sink.is_muted);
+        assert_eq!(firefox().await.card_device_name, SINK_NAME);
+
+        super::delete("simulated").await.unwrap();
+    }
+
+    async fn sink() -> CardDevice {
+        let (_, _, sinks) = audio::fetch_devices().await;
+
+        sinks
+            .into_iter()
+            .find(|sink| sink.name == SINK_NAME)
+            .unwrap()
+    }
+
+    async fn firefox() -> AudioClient {
+        audio_client::fetch_clients(CardDeviceType::Sink)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|client| client.application_name.as_deref() == Some("Firefox"))
+            .unwrap()
+    }
+}
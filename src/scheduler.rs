@@ -0,0 +1,261 @@
+pub mod cron;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{Local, NaiveDateTime, TimeZone, Timelike};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{mpsc::UnboundedSender, Mutex},
+    time::sleep,
+};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::{audio, card_device_type::CardDeviceType},
+    device_monitor,
+    pubsub::message::Message,
+    scene, storage,
+};
+
+use self::cron::Cron;
+
+const SCHEDULES_FILE_NAME: &str = "schedules.json";
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Schedule {
+    #[serde(default)]
+    pub name: String,
+    /// minute hour day month weekday, in local time, each field being `*`, a value, a range or a
+    /// comma separated list of them, optionally with a `/step`, such as `0 9 * * mon-fri`
+    pub cron: String,
+    pub action: ScheduledAction,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// `set_mute` mutes or unmutes every device of `type`, `cap_volume` lowers the volume of every
+/// device of `type` above `volume`, `set_default` makes the device of `type` called `name` the
+/// default and `restore_scene` restores the scene called `name`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ScheduledAction {
+    SetMute {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        mute: bool,
+    },
+    CapVolume {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        #[schemars(range(min = 0, max = 100))]
+        volume: f64,
+    },
+    SetDefault {
+        #[serde(rename = "type")]
+        _type: CardDeviceType,
+        name: String,
+    },
+    RestoreScene {
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct ScheduleState {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    /// Null when the schedule is disabled
+    pub next_run: Option<String>,
+}
+
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut last_evaluated_minute: Option<NaiveDateTime> = None;
+
+    loop {
+        let now = Local::now();
+        let elapsed = now.second() as u64 * 1000 + now.timestamp_subsec_millis() as u64 % 1000;
+
+        sleep(Duration::from_millis(60_000 - elapsed)).await;
+
+        let now = Local::now().naive_local();
+        let now = match now.with_second(0).and_then(|now| now.with_nanosecond(0)) {
+            Some(now) => now,
+            None => continue,
+        };
+
+        let minutes: Vec<NaiveDateTime> = match last_evaluated_minute {
+            Some(last) if now <= last => continue,
+            Some(last) => (1..)
+                .map(|offset| last + chrono::Duration::minutes(offset))
+                .take_while(|minute| *minute <= now)
+                .collect(),
+            None => vec![now],
+        };
+
+        last_evaluated_minute.replace(now);
+
+        let schedules = match load().await {
+            Ok(schedules) => schedules,
+            Err(error) => {
+                error!("Could not load the schedules: {}", error);
+
+                continue;
+            }
+        };
+
+        let mut changed = false;
+
+        for schedule in schedules.values().filter(|schedule| schedule.enabled) {
+            match schedule.cron.parse::<Cron>() {
+                Ok(cron) if minutes.iter().any(|minute| cron.matches(minute)) => {
+                    info!("Running schedule {}", schedule.name);
+
+                    if let Err(error) = run(&schedule.action).await {
+                        error!("Could not run schedule {}: {}", schedule.name, error);
+                    }
+
+                    changed = true;
+                }
+                Ok(_) => (),
+                Err(error) => error!("Invalid schedule {}: {}", schedule.name, error),
+            }
+        }
+
+        if changed {
+            device_monitor::publish_state(&pubsub_tx).await;
+        }
+    }
+}
+
+pub async fn list() -> io::Result<Vec<ScheduleState>> {
+    let now = Local::now().naive_local();
+
+    Ok(load()
+        .await?
+        .into_values()
+        .map(|schedule| ScheduleState {
+            next_run: next_run(&schedule, &now),
+            schedule,
+        })
+        .collect())
+}
+
+pub async fn get(name: &str) -> io::Result<ScheduleState> {
+    let now = Local::now().naive_local();
+
+    load()
+        .await?
+        .remove(name)
+        .map(|schedule| ScheduleState {
+            next_run: next_run(&schedule, &now),
+            schedule,
+        })
+        .ok_or_else(|| not_found(name))
+}
+
+pub async fn save(schedule: Schedule) -> io::Result<()> {
+    schedule
+        .cron
+        .parse::<Cron>()
+        .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+
+    storage::update(
+        SCHEDULES_FILE_NAME,
+        |schedules: &mut BTreeMap<String, Schedule>| {
+            schedules.insert(schedule.name.clone(), schedule);
+        },
+    )
+    .await
+}
+
+pub async fn delete(name: &str) -> io::Result<()> {
+    storage::update(
+        SCHEDULES_FILE_NAME,
+        |schedules: &mut BTreeMap<String, Schedule>| schedules.remove(name),
+    )
+    .await?
+    .map(|_| ())
+    .ok_or_else(|| not_found(name))
+}
+
+#[instrument]
+async fn run(action: &ScheduledAction) -> io::Result<()> {
+    let (_, sources, sinks) = audio::fetch_devices().await;
+
+    match action {
+        ScheduledAction::SetMute { _type, mute } => {
+            let card_devices = if *_type == CardDeviceType::Source {
+                &sources
+            } else {
+                &sinks
+            };
+
+            for card_device in card_devices.iter().filter(|c| c.is_muted != *mute) {
+                audio::toggle_mute(*_type, card_device.index, *mute).await?;
+            }
+        }
+        ScheduledAction::CapVolume { _type, volume } => {
+            let card_devices = if *_type == CardDeviceType::Source {
+                &sources
+            } else {
+                &sinks
+            };
+
+            for card_device in card_devices.iter().filter(|c| c.volume > *volume) {
+                audio::set_volume(*_type, card_device.index, *volume).await?;
+            }
+        }
+        ScheduledAction::SetDefault { _type, name } => {
+            let card_devices = if *_type == CardDeviceType::Source {
+                &sources
+            } else {
+                &sinks
+            };
+
+            match card_devices.iter().find(|c| &c.name == name) {
+                Some(card_device) => {
+                    audio::set_default_card_device(*_type, card_device.index).await?;
+                    audio::move_audio_clients(*_type, card_device.index, name).await?;
+                }
+                None => error!("Could not find {} {}", _type, name),
+            }
+        }
+        ScheduledAction::RestoreScene { name } => scene::restore(name).await?,
+    }
+
+    Ok(())
+}
+
+async fn load() -> io::Result<BTreeMap<String, Schedule>> {
+    storage::load(SCHEDULES_FILE_NAME).await
+}
+
+fn next_run(schedule: &Schedule, now: &NaiveDateTime) -> Option<String> {
+    if !schedule.enabled {
+        return None;
+    }
+
+    let next_run = schedule.cron.parse::<Cron>().ok()?.next_after(now)?;
+
+    Local
+        .from_local_datetime(&next_run)
+        .earliest()
+        .map(|next_run| next_run.to_rfc3339())
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::NotFound,
+        format!("Schedule {} does not exist", name),
+    )
+}
@@ -0,0 +1,243 @@
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Clone, Debug)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        self.matches_day(time)
+            && is_set(self.hours, time.hour())
+            && is_set(self.minutes, time.minute())
+    }
+
+    pub fn next_after(&self, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(366 * 4);
+
+        while time < limit {
+            if !self.matches_day(&time) {
+                time = time.date().and_hms_opt(0, 0, 0)? + Duration::days(1);
+            } else if !is_set(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !is_set(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let day = is_set(self.days, time.day());
+        let weekday = is_set(self.weekdays, time.weekday().num_days_from_sunday());
+
+        if !is_set(self.months, time.month()) {
+            return false;
+        }
+
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return Err(format!(
+                "Expected 5 fields (minute hour day month weekday) in {:?}",
+                expression
+            ));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7, &WEEKDAYS)?;
+
+        if is_set(weekdays, 7) {
+            weekdays |= 1;
+        }
+
+        Ok(Cron {
+            minutes: parse_field(fields[0], 0, 59, &[])?,
+            hours: parse_field(fields[1], 0, 23, &[])?,
+            days: parse_field(fields[2], 1, 31, &[])?,
+            months: parse_field(fields[3], 1, 12, &MONTHS)?,
+            weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid step in {:?}", part))?,
+            ),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = parse_value(start, min, names)?;
+            let end = parse_value(end, min, names)?;
+
+            // Sunday is both 0 and 7, so that ranges such as mon-sun end on it
+            (
+                start,
+                if max == 7 && end == 0 && start > 0 {
+                    7
+                } else {
+                    end
+                },
+            )
+        } else {
+            let value = parse_value(range, min, names)?;
+
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!("{:?} is out of range {}-{}", part, min, max));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn parse_value(value: &str, min: u32, names: &[&str]) -> Result<u32, String> {
+    names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(value))
+        .map(|position| position as u32 + min)
+        .or_else(|| value.parse().ok())
+        .ok_or_else(|| format!("Invalid value {:?}", value))
+}
+
+fn is_set(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{is_set, parse_field, Cron, WEEKDAYS};
+
+    fn time(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2024-01-01 is a Monday
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn values(bits: u64, max: u32) -> Vec<u32> {
+        (0..=max).filter(|value| is_set(bits, *value)).collect()
+    }
+
+    #[test]
+    fn parses_ranges_steps_and_lists() {
+        assert_eq!(
+            values(parse_field("1-3", 0, 59, &[]).unwrap(), 59),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            values(parse_field("*/15", 0, 59, &[]).unwrap(), 59),
+            [0, 15, 30, 45]
+        );
+        assert_eq!(
+            values(parse_field("10-20/5,50", 0, 59, &[]).unwrap(), 59),
+            [10, 15, 20, 50]
+        );
+        assert_eq!(
+            values(parse_field("20/20", 0, 59, &[]).unwrap(), 59),
+            [20, 40]
+        );
+        assert!(parse_field("5-1", 0, 59, &[]).is_err());
+        assert!(parse_field("60", 0, 59, &[]).is_err());
+        assert!(parse_field("*/0", 0, 59, &[]).is_err());
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(
+            values(parse_field("mon-fri", 0, 7, &WEEKDAYS).unwrap(), 7),
+            [1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            values(parse_field("mon-sun", 0, 7, &WEEKDAYS).unwrap(), 7),
+            [1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(
+            values(parse_field("SAT,sun", 0, 7, &WEEKDAYS).unwrap(), 7),
+            [0, 6]
+        );
+        assert!("0 9 * jan-dec mon-sun"
+            .parse::<Cron>()
+            .unwrap()
+            .matches(&time(7, 9, 0)));
+        assert!("0 9 * * 7".parse::<Cron>().unwrap().matches(&time(7, 9, 0)));
+        assert!("0 9 * * caturday".parse::<Cron>().is_err());
+    }
+
+    #[test]
+    fn matches_either_the_day_or_the_weekday() {
+        let cron: Cron = "0 9 17 * mon".parse().unwrap();
+
+        assert!(cron.matches(&time(1, 9, 0)));
+        assert!(cron.matches(&time(17, 9, 0)));
+        assert!(!cron.matches(&time(2, 9, 0)));
+
+        let cron: Cron = "0 9 17 * *".parse().unwrap();
+
+        assert!(!cron.matches(&time(1, 9, 0)));
+        assert!(cron.matches(&time(17, 9, 0)));
+    }
+
+    #[test]
+    fn finds_the_next_run() {
+        let cron: Cron = "30 9 * * mon-fri".parse().unwrap();
+
+        assert_eq!(cron.next_after(&time(1, 9, 0)), Some(time(1, 9, 30)));
+        assert_eq!(cron.next_after(&time(1, 9, 30)), Some(time(2, 9, 30)));
+        assert_eq!(cron.next_after(&time(5, 10, 0)), Some(time(8, 9, 30)));
+
+        let cron: Cron = "0 0 30 2 *".parse().unwrap();
+
+        assert_eq!(cron.next_after(&time(1, 0, 0)), None);
+    }
+}
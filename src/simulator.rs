@@ -0,0 +1,101 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{mpsc::UnboundedSender, Mutex},
+    time::sleep,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    device::{card_device_type::CardDeviceType, simulated_server},
+    device_monitor,
+    pubsub::message::Message,
+};
+
+const STEP_INTERVAL: Duration = Duration::from_secs(10);
+const HEADSET_ADDRESS: &str = "38:18:4C:12:34:56";
+const HEADSET_DESCRIPTION: &str = "WH-1000XM4";
+const RECORDING_APPLICATION_NAME: &str = "Zoom";
+const RECORDING_PROCESS_ID: u32 = 3100;
+
+enum Step {
+    ConnectHeadset,
+    SetVolume(f64),
+    StartRecording,
+    StopRecording,
+    DisconnectHeadset,
+}
+
+const SCRIPT: [Step; 6] = [
+    Step::ConnectHeadset,
+    Step::SetVolume(35.0),
+    Step::StartRecording,
+    Step::SetVolume(60.0),
+    Step::StopRecording,
+    Step::DisconnectHeadset,
+];
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut card_name = None;
+    let mut recording = None;
+
+    loop {
+        for step in SCRIPT.iter() {
+            sleep(STEP_INTERVAL).await;
+
+            match step {
+                Step::ConnectHeadset => {
+                    info!(
+                        "Simulating the connection of headset {}",
+                        HEADSET_DESCRIPTION
+                    );
+
+                    card_name.replace(simulated_server::connect_bluetooth_headset(
+                        HEADSET_ADDRESS,
+                        HEADSET_DESCRIPTION,
+                    ));
+                }
+                Step::SetVolume(volume) => {
+                    info!(
+                        "Simulating a volume change of the default sink to {}%",
+                        volume
+                    );
+
+                    simulated_server::set_volume(CardDeviceType::Sink, "@DEFAULT_SINK@", *volume);
+                }
+                Step::StartRecording => {
+                    info!("Simulating {} recording", RECORDING_APPLICATION_NAME);
+
+                    recording = simulated_server::start_stream(
+                        CardDeviceType::Source,
+                        RECORDING_APPLICATION_NAME,
+                        RECORDING_PROCESS_ID,
+                    );
+                }
+                Step::StopRecording => {
+                    info!(
+                        "Simulating {} stopping to record",
+                        RECORDING_APPLICATION_NAME
+                    );
+
+                    if let Some(index) = recording.take() {
+                        simulated_server::stop_stream(CardDeviceType::Source, index);
+                    }
+                }
+                Step::DisconnectHeadset => {
+                    info!(
+                        "Simulating the disconnection of headset {}",
+                        HEADSET_DESCRIPTION
+                    );
+
+                    if let Some(card_name) = card_name.take() {
+                        simulated_server::disconnect_card(&card_name);
+                    }
+                }
+            }
+
+            device_monitor::publish_state(&pubsub_tx).await;
+        }
+    }
+}
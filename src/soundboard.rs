@@ -0,0 +1,379 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use chrono::Local;
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::{
+        audio,
+        card_device::CardDevice,
+        loopback, module, pacat,
+        virtual_device::{self, VirtualDevice},
+    },
+    device_monitor,
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+    storage,
+};
+
+const CLIPS_FILE_NAME: &str = "soundboard.json";
+const CLIPS_DIR_NAME: &str = "soundboard";
+const SINK_NAME: &str = "cctl_soundboard";
+const MICROPHONE_NAME: &str = "cctl_soundboard_microphone";
+const CHANNEL_MAP: &str = "front-left,front-right";
+const MAX_VOLUME: f64 = 150.0;
+pub const MAX_CLIP_SIZE: u64 = 10 * 1024 * 1024;
+
+static PLAYING: AtomicUsize = AtomicUsize::new(0);
+static STOP_TX: Lazy<broadcast::Sender<()>> = Lazy::new(|| broadcast::channel(1).0);
+static MICROPHONE: Lazy<Mutex<Option<SoundboardMicrophone>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Clip {
+    pub name: String,
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub uploaded_at: String,
+}
+
+impl Clip {
+    pub fn content_type(&self) -> &'static str {
+        match self.file_name.rsplit('.').next() {
+            Some("wav") => "audio/wav",
+            Some("flac") => "audio/flac",
+            _ => "audio/ogg",
+        }
+    }
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct SoundboardMicrophone {
+    pub source_name: String,
+    pub microphone_name: String,
+    pub module_indexes: Vec<u64>,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct SoundboardState {
+    /// The number of clips playing
+    pub playing: usize,
+    pub microphone: Option<SoundboardMicrophone>,
+}
+
+pub async fn state() -> SoundboardState {
+    SoundboardState {
+        playing: PLAYING.load(Ordering::Relaxed),
+        microphone: MICROPHONE.lock().await.clone(),
+    }
+}
+
+pub async fn list() -> io::Result<Vec<Clip>> {
+    Ok(load().await?.into_values().collect())
+}
+
+pub async fn get(name: &str) -> io::Result<Clip> {
+    load().await?.remove(name).ok_or_else(|| not_found(name))
+}
+
+pub async fn read(name: &str) -> io::Result<(Clip, Vec<u8>)> {
+    let clip = get(name).await?;
+    let contents = tokio::fs::read(clip_path(&clip)).await?;
+
+    Ok((clip, contents))
+}
+
+#[instrument(skip(contents))]
+pub async fn save(name: &str, contents: &[u8]) -> io::Result<Clip> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "The clip name {} may only contain letters, digits, dashes and underscores",
+                name
+            ),
+        ));
+    }
+
+    let extension = match contents.get(0..4) {
+        Some(b"RIFF") => "wav",
+        Some(b"fLaC") => "flac",
+        Some(b"OggS") => "ogg",
+        _ => {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Clips must be WAV, FLAC or Ogg files",
+            ))
+        }
+    };
+
+    let dir = storage::path(CLIPS_DIR_NAME);
+
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let clip = Clip {
+        name: name.to_owned(),
+        file_name: format!("{}.{}", name, extension),
+        size_bytes: contents.len() as u64,
+        uploaded_at: Local::now().to_rfc3339(),
+    };
+
+    tokio::fs::write(dir.join(&clip.file_name), contents).await?;
+
+    let replaced = storage::update(CLIPS_FILE_NAME, |clips: &mut BTreeMap<String, Clip>| {
+        clips.insert(name.to_owned(), clip.clone())
+    })
+    .await?;
+
+    if let Some(replaced) = replaced.filter(|replaced| replaced.file_name != clip.file_name) {
+        tokio::fs::remove_file(clip_path(&replaced)).await.ok();
+    }
+
+    info!("Saved clip {} ({} bytes)", name, clip.size_bytes);
+
+    Ok(clip)
+}
+
+pub async fn delete(name: &str) -> io::Result<()> {
+    let clip = storage::update(CLIPS_FILE_NAME, |clips: &mut BTreeMap<String, Clip>| {
+        clips.remove(name)
+    })
+    .await?
+    .ok_or_else(|| not_found(name))?;
+
+    match tokio::fs::remove_file(clip_path(&clip)).await {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+#[instrument]
+pub async fn play(
+    name: &str,
+    sink_name: Option<&str>,
+    volume: f64,
+    microphone: bool,
+) -> io::Result<()> {
+    let clip = get(name).await?;
+    let path = clip_path(&clip);
+
+    if !(0.0..=MAX_VOLUME).contains(&volume) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("The volume {} is not between 0 and {}", volume, MAX_VOLUME),
+        ));
+    }
+
+    if let Some(sink_name) = sink_name {
+        let (_, _, sinks) = audio::fetch_devices().await;
+
+        if !sinks.iter().any(|sink| sink.name == sink_name) {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Sink {} does not exist", sink_name),
+            ));
+        }
+    }
+
+    if microphone && MICROPHONE.lock().await.is_none() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "The soundboard microphone is off",
+        ));
+    }
+
+    let mut sink_names = Vec::new();
+
+    if sink_name.is_some() || !microphone {
+        sink_names.push(sink_name);
+    }
+
+    if microphone {
+        sink_names.push(Some(SINK_NAME));
+    }
+
+    for sink_name in sink_names {
+        let mut child = pacat::spawn_file(sink_name, &path, volume)?;
+        let mut stop_rx = STOP_TX.subscribe();
+
+        info!(
+            "Playing clip {} on {}",
+            name,
+            sink_name.unwrap_or("the default sink")
+        );
+
+        PLAYING.fetch_add(1, Ordering::Relaxed);
+
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = child.wait() => (),
+                _ = stop_rx.recv() => (),
+            }
+
+            PLAYING.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+
+    Ok(())
+}
+
+pub fn stop_all() {
+    info!("Stopping every clip");
+
+    STOP_TX.send(()).ok();
+}
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "soundboard").await;
+
+    while let Some(message) = rx.recv().await {
+        if let Some(state) = try_downcast_ref!(message, MessageState) {
+            if remove_orphaned_microphone(&state.sources()).await {
+                device_monitor::publish_state(&pubsub_tx).await;
+            }
+        }
+    }
+}
+
+#[instrument]
+pub async fn enable_microphone(source_name: &str) -> io::Result<SoundboardMicrophone> {
+    let mut microphone = MICROPHONE.lock().await;
+
+    if let Some(ref microphone) = *microphone {
+        return Err(io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "The soundboard microphone already mixes source {}",
+                microphone.source_name
+            ),
+        ));
+    }
+
+    let (_, sources, _) = audio::fetch_devices().await;
+
+    if !sources.iter().any(|source| source.name == source_name) {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("Source {} does not exist", source_name),
+        ));
+    }
+
+    let mut module_indexes = Vec::new();
+
+    let result = async {
+        module_indexes.push(
+            virtual_device::create(&VirtualDevice::NullSink {
+                name: SINK_NAME.to_owned(),
+                description: Some("Soundboard".to_owned()),
+            })
+            .await?,
+        );
+        module_indexes.push(
+            loopback::add_loopback(source_name, SINK_NAME, loopback::DEFAULT_LATENCY_MSEC).await?,
+        );
+        module_indexes.push(
+            virtual_device::create(&VirtualDevice::RemapSource {
+                name: MICROPHONE_NAME.to_owned(),
+                description: Some("Microphone with soundboard".to_owned()),
+                master: format!("{}.monitor", SINK_NAME),
+                master_channel_map: CHANNEL_MAP.to_owned(),
+                channel_map: CHANNEL_MAP.to_owned(),
+            })
+            .await?,
+        );
+
+        Ok::<(), io::Error>(())
+    }
+    .await;
+
+    if let Err(error) = result {
+        error!("Could not set up the soundboard microphone: {}", error);
+
+        module::unload_loaded_modules(&module_indexes).await;
+
+        return Err(error);
+    }
+
+    info!(
+        "Mixing source {} with the soundboard as {}",
+        source_name, MICROPHONE_NAME
+    );
+
+    let enabled = SoundboardMicrophone {
+        source_name: source_name.to_owned(),
+        microphone_name: MICROPHONE_NAME.to_owned(),
+        module_indexes,
+    };
+
+    microphone.replace(enabled.clone());
+
+    Ok(enabled)
+}
+
+pub async fn disable_microphone() -> io::Result<()> {
+    let microphone =
+        MICROPHONE.lock().await.take().ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, "The soundboard microphone is off")
+        })?;
+
+    module::unload_loaded_modules(&microphone.module_indexes).await;
+
+    info!("Stopped mixing source {}", microphone.source_name);
+
+    Ok(())
+}
+
+async fn remove_orphaned_microphone(sources: &[CardDevice]) -> bool {
+    let mut microphone = MICROPHONE.lock().await;
+
+    let removed = microphone.as_ref().map_or(false, |enabled| {
+        !sources
+            .iter()
+            .any(|source| source.name == enabled.source_name)
+    });
+
+    if !removed {
+        return false;
+    }
+
+    let microphone = microphone.take().unwrap();
+
+    info!(
+        "Source {} was removed, tearing down the soundboard microphone",
+        microphone.source_name
+    );
+
+    module::unload_loaded_modules(&microphone.module_indexes).await;
+
+    true
+}
+
+async fn load() -> io::Result<BTreeMap<String, Clip>> {
+    storage::load(CLIPS_FILE_NAME).await
+}
+
+fn clip_path(clip: &Clip) -> PathBuf {
+    storage::path(CLIPS_DIR_NAME).join(&clip.file_name)
+}
+
+fn not_found(name: &str) -> io::Error {
+    io::Error::new(ErrorKind::NotFound, format!("Clip {} does not exist", name))
+}
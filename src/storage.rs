@@ -0,0 +1,62 @@
+use std::{io, path::PathBuf};
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::Mutex;
+
+static DATA_DIR: OnceCell<PathBuf> = OnceCell::new();
+static LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+pub fn init(data_dir: PathBuf) {
+    std::fs::create_dir_all(&data_dir).unwrap();
+
+    DATA_DIR.set(data_dir).unwrap();
+}
+
+#[cfg(test)]
+pub fn init_temporary() {
+    DATA_DIR.get_or_init(|| {
+        let data_dir = std::env::temp_dir().join(format!("cctl-test-{}", std::process::id()));
+
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        data_dir
+    });
+}
+
+pub fn path(file_name: &str) -> PathBuf {
+    DATA_DIR.get().unwrap().join(file_name)
+}
+
+pub async fn load<T: DeserializeOwned + Default>(file_name: &str) -> io::Result<T> {
+    let _lock = LOCK.lock().await;
+
+    read(file_name)
+}
+
+pub async fn update<T, F, R>(file_name: &str, f: F) -> io::Result<R>
+where
+    T: DeserializeOwned + Serialize + Default,
+    F: FnOnce(&mut T) -> R,
+{
+    let _lock = LOCK.lock().await;
+
+    let mut value: T = read(file_name)?;
+    let result = f(&mut value);
+
+    let path = path(file_name);
+    let temporary_path = path.with_extension("tmp");
+
+    std::fs::write(&temporary_path, serde_json::to_vec_pretty(&value)?)?;
+    std::fs::rename(&temporary_path, &path)?;
+
+    Ok(result)
+}
+
+fn read<T: DeserializeOwned + Default>(file_name: &str) -> io::Result<T> {
+    match std::fs::read(path(file_name)) {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(error) => Err(error),
+    }
+}
@@ -0,0 +1,251 @@
+pub mod menu;
+pub mod status_notifier_item;
+
+use std::sync::Arc;
+
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tokio_stream::StreamExt;
+use tracing::{error, info, instrument, warn};
+use zbus::{dbus_proxy, fdo::DBusProxy, Connection, ConnectionBuilder};
+
+use crate::pubsub::{
+    self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+    try_downcast_ref::try_downcast_ref,
+};
+
+use self::{
+    menu::{Menu, MENU_PATH},
+    status_notifier_item::{StatusNotifierItem, ITEM_PATH},
+};
+
+const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+#[dbus_proxy(
+    interface = "org.kde.StatusNotifierWatcher",
+    default_service = "org.kde.StatusNotifierWatcher",
+    default_path = "/StatusNotifierWatcher"
+)]
+trait StatusNotifierWatcher {
+    fn register_status_notifier_item(&self, service: &str) -> zbus::Result<()>;
+}
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "tray").await;
+
+    let service_name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+
+    let connection = match ConnectionBuilder::session() {
+        Ok(builder) => connect(builder, &service_name, &pubsub_tx).await,
+        Err(error) => Err(error),
+    };
+
+    let connection = match connection {
+        Ok(connection) => connection,
+        Err(error) => {
+            error!("Could not serve the tray on the session bus: {}", error);
+
+            return;
+        }
+    };
+
+    tokio::spawn(register(connection.clone(), service_name));
+
+    let item = connection
+        .object_server()
+        .interface::<_, StatusNotifierItem>(ITEM_PATH)
+        .await
+        .unwrap();
+    let menu = connection
+        .object_server()
+        .interface::<_, Menu>(MENU_PATH)
+        .await
+        .unwrap();
+
+    loop {
+        if let Some(message) = rx.recv().await {
+            if let Some(state) = try_downcast_ref!(message, MessageState) {
+                item.get_mut().await.update(state.clone());
+
+                if let Err(error) = StatusNotifierItem::emit_changes(item.signal_context()).await {
+                    error!("Could not update the tray icon: {}", error);
+                }
+
+                let revision = menu.get_mut().await.update(state.clone());
+
+                if let Err(error) = Menu::layout_updated(menu.signal_context(), revision, 0).await {
+                    error!("Could not update the tray menu: {}", error);
+                }
+            }
+        }
+    }
+}
+
+async fn connect(
+    builder: ConnectionBuilder<'_>,
+    service_name: &str,
+    pubsub_tx: &Arc<Mutex<UnboundedSender<Message>>>,
+) -> zbus::Result<Connection> {
+    builder
+        .name(service_name)?
+        .serve_at(ITEM_PATH, StatusNotifierItem::new(pubsub_tx.clone()))?
+        .serve_at(MENU_PATH, Menu::new(pubsub_tx.clone()))?
+        .build()
+        .await
+}
+
+async fn register(connection: Connection, service_name: String) {
+    let owner_changes = match DBusProxy::new(&connection).await {
+        Ok(proxy) => proxy.receive_name_owner_changed().await,
+        Err(error) => Err(error),
+    };
+
+    let mut owner_changes = match owner_changes {
+        Ok(owner_changes) => owner_changes,
+        Err(error) => {
+            error!("Could not watch for {}: {}", WATCHER_NAME, error);
+
+            return;
+        }
+    };
+
+    if let Err(error) = register_with_watcher(&connection, &service_name).await {
+        warn!(
+            "Could not register the tray yet, waiting for {}: {}",
+            WATCHER_NAME, error
+        );
+    }
+
+    while let Some(signal) = owner_changes.next().await {
+        let appeared = signal.args().map_or(false, |args| {
+            args.name().as_str() == WATCHER_NAME && args.new_owner().is_some()
+        });
+
+        if !appeared {
+            continue;
+        }
+
+        if let Err(error) = register_with_watcher(&connection, &service_name).await {
+            error!(
+                "Could not register the tray with {}: {}",
+                WATCHER_NAME, error
+            );
+        }
+    }
+}
+
+async fn register_with_watcher(connection: &Connection, service_name: &str) -> zbus::Result<()> {
+    StatusNotifierWatcherProxy::new(connection)
+        .await?
+        .register_status_notifier_item(service_name)
+        .await?;
+
+    info!(
+        "Registered the tray as {} with {}",
+        service_name, WATCHER_NAME
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        process::Stdio,
+        sync::{Arc, Mutex as StdMutex},
+        time::Duration,
+    };
+
+    use tokio::{
+        io::{AsyncBufReadExt, BufReader},
+        process::Command,
+        time::sleep,
+    };
+    use zbus::{dbus_interface, ConnectionBuilder};
+
+    use crate::pubsub;
+
+    use super::WATCHER_NAME;
+
+    const SERVICE_NAME: &str = "org.kde.StatusNotifierItem-test-1";
+
+    struct WatcherStub {
+        items: Arc<StdMutex<Vec<String>>>,
+    }
+
+    #[dbus_interface(name = "org.kde.StatusNotifierWatcher")]
+    impl WatcherStub {
+        fn register_status_notifier_item(&self, service: &str) {
+            self.items.lock().unwrap().push(service.to_owned());
+        }
+    }
+
+    #[tokio::test]
+    async fn registers_once_the_watcher_appears() {
+        let mut daemon = match Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+        {
+            Ok(daemon) => daemon,
+            Err(error) => {
+                eprintln!("Skipping the tray test without dbus-daemon: {}", error);
+
+                return;
+            }
+        };
+
+        let mut address = String::new();
+
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .await
+            .unwrap();
+
+        let address = address.trim();
+        let (pubsub_tx, _) = pubsub::start();
+        let connection = super::connect(
+            ConnectionBuilder::address(address).unwrap(),
+            SERVICE_NAME,
+            &pubsub_tx,
+        )
+        .await
+        .unwrap();
+
+        tokio::spawn(super::register(connection, SERVICE_NAME.to_owned()));
+
+        sleep(Duration::from_millis(200)).await;
+
+        let items = Arc::new(StdMutex::new(Vec::new()));
+        let _watcher = ConnectionBuilder::address(address)
+            .unwrap()
+            .name(WATCHER_NAME)
+            .unwrap()
+            .serve_at(
+                "/StatusNotifierWatcher",
+                WatcherStub {
+                    items: items.clone(),
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+
+        for _ in 0..100 {
+            if items
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|item| item == SERVICE_NAME)
+            {
+                return;
+            }
+
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        panic!("The tray never registered with the watcher");
+    }
+}
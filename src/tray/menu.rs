@@ -0,0 +1,302 @@
+use std::{collections::HashMap, default::default, sync::Arc};
+
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{debug, error};
+use zbus::{
+    dbus_interface,
+    zvariant::{OwnedValue, Value},
+    SignalContext,
+};
+
+use crate::{
+    device::{bus::Bus, card_device::CardDevice, card_device_type::CardDeviceType},
+    protocol::command::{self, Command},
+    pubsub::{message::Message, message_state::MessageState},
+};
+
+pub const MENU_PATH: &str = "/MenuBar";
+
+type Layout = (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>);
+
+enum MenuItemKind {
+    Standard,
+    Separator,
+    Checkmark(bool),
+    Radio(bool),
+}
+
+struct MenuItem {
+    id: i32,
+    label: String,
+    kind: MenuItemKind,
+    children: Vec<MenuItem>,
+}
+
+impl MenuItem {
+    fn properties(&self) -> HashMap<String, OwnedValue> {
+        let mut properties: HashMap<String, OwnedValue> = HashMap::new();
+
+        match self.kind {
+            MenuItemKind::Separator => {
+                properties.insert("type".to_owned(), Value::from("separator").into());
+            }
+            MenuItemKind::Checkmark(checked) | MenuItemKind::Radio(checked) => {
+                let toggle_type = if let MenuItemKind::Radio(_) = self.kind {
+                    "radio"
+                } else {
+                    "checkmark"
+                };
+
+                properties.insert("label".to_owned(), Value::from(self.label.as_str()).into());
+                properties.insert("toggle-type".to_owned(), Value::from(toggle_type).into());
+                properties.insert(
+                    "toggle-state".to_owned(),
+                    Value::from(checked as i32).into(),
+                );
+            }
+            MenuItemKind::Standard => {
+                properties.insert("label".to_owned(), Value::from(self.label.as_str()).into());
+            }
+        }
+
+        if !self.children.is_empty() {
+            properties.insert("children-display".to_owned(), Value::from("submenu").into());
+        }
+
+        properties
+    }
+
+    fn layout(&self, depth: i32) -> Layout {
+        let children = if depth == 0 {
+            Vec::new()
+        } else {
+            self.children
+                .iter()
+                .map(|child| Value::from(child.layout(depth - 1)).into())
+                .collect()
+        };
+
+        (self.id, self.properties(), children)
+    }
+
+    fn find(&self, id: i32) -> Option<&MenuItem> {
+        if self.id == id {
+            return Some(self);
+        }
+
+        self.children.iter().find_map(|child| child.find(id))
+    }
+}
+
+pub struct Menu {
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+    revision: u32,
+    root: MenuItem,
+    actions: HashMap<i32, Command>,
+}
+
+impl Menu {
+    pub fn new(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) -> Self {
+        let mut menu = Self {
+            pubsub_tx,
+            revision: 0,
+            root: MenuItem {
+                id: 0,
+                label: String::new(),
+                kind: MenuItemKind::Standard,
+                children: Vec::new(),
+            },
+            actions: HashMap::new(),
+        };
+
+        menu.update(default());
+
+        menu
+    }
+
+    pub fn update(&mut self, state: MessageState) -> u32 {
+        let mut builder = MenuBuilder::default();
+
+        let mut children = Vec::new();
+
+        for (_type, label, card_devices) in [
+            (CardDeviceType::Source, "Mute input", state.sources()),
+            (CardDeviceType::Sink, "Mute output", state.sinks()),
+        ] {
+            if let Some(card_device) = card_devices.iter().find(|c| c.is_default) {
+                children.push(builder.item(
+                    label,
+                    MenuItemKind::Checkmark(card_device.is_muted),
+                    Some(Command::SetMute {
+                        _type,
+                        index: card_device.index,
+                        mute: !card_device.is_muted,
+                    }),
+                ));
+            }
+        }
+
+        children.push(builder.item("", MenuItemKind::Separator, None));
+
+        for (_type, label, card_devices) in [
+            (CardDeviceType::Sink, "Output", state.sinks()),
+            (CardDeviceType::Source, "Input", state.sources()),
+        ] {
+            let devices = card_devices
+                .iter()
+                .map(|card_device| builder.device_item(_type, card_device))
+                .collect();
+
+            children.push(builder.submenu(label, devices));
+        }
+
+        for card in state
+            .cards()
+            .iter()
+            .filter(|card| card.bus == Bus::Bluetooth)
+        {
+            let profiles = card
+                .profiles
+                .iter()
+                .map(|profile| {
+                    builder.item(
+                        &profile.to_string(),
+                        MenuItemKind::Radio(profile.as_parsed() == card.active_profile.as_parsed()),
+                        Some(Command::SetProfile {
+                            index: card.index,
+                            profile: profile.clone(),
+                        }),
+                    )
+                })
+                .collect();
+
+            children.push(builder.submenu(&card.description, profiles));
+        }
+
+        self.root.children = children;
+        self.actions = builder.actions;
+        self.revision += 1;
+
+        self.revision
+    }
+
+    async fn execute(&self, command: Command) {
+        match command::execute(command, self.pubsub_tx.clone()).await {
+            Ok(event) => debug!("Executed a tray menu command: {}", event.to_json()),
+            Err(error) => error!("Could not execute a tray menu command: {}", error),
+        }
+    }
+}
+
+#[dbus_interface(name = "com.canonical.dbusmenu")]
+impl Menu {
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        _property_names: Vec<String>,
+    ) -> (u32, Layout) {
+        let layout = self
+            .root
+            .find(parent_id)
+            .unwrap_or(&self.root)
+            .layout(recursion_depth);
+
+        (self.revision, layout)
+    }
+
+    fn get_group_properties(
+        &self,
+        ids: Vec<i32>,
+        _property_names: Vec<String>,
+    ) -> Vec<(i32, HashMap<String, OwnedValue>)> {
+        ids.into_iter()
+            .filter_map(|id| self.root.find(id))
+            .map(|item| (item.id, item.properties()))
+            .collect()
+    }
+
+    async fn event(&self, id: i32, event_id: &str, _data: Value<'_>, _timestamp: u32) {
+        if event_id != "clicked" {
+            return;
+        }
+
+        if let Some(command) = self.actions.get(&id).cloned() {
+            self.execute(command).await;
+        }
+    }
+
+    fn about_to_show(&self, _id: i32) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn version(&self) -> u32 {
+        3
+    }
+
+    #[dbus_interface(property)]
+    fn text_direction(&self) -> &str {
+        "ltr"
+    }
+
+    #[dbus_interface(property)]
+    fn status(&self) -> &str {
+        "normal"
+    }
+
+    #[dbus_interface(property)]
+    fn icon_theme_path(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[dbus_interface(signal)]
+    pub async fn layout_updated(
+        ctxt: &SignalContext<'_>,
+        revision: u32,
+        parent: i32,
+    ) -> zbus::Result<()>;
+}
+
+#[derive(Default)]
+struct MenuBuilder {
+    next_id: i32,
+    actions: HashMap<i32, Command>,
+}
+
+impl MenuBuilder {
+    fn item(&mut self, label: &str, kind: MenuItemKind, command: Option<Command>) -> MenuItem {
+        self.next_id += 1;
+
+        if let Some(command) = command {
+            self.actions.insert(self.next_id, command);
+        }
+
+        MenuItem {
+            id: self.next_id,
+            label: label.to_owned(),
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    fn submenu(&mut self, label: &str, children: Vec<MenuItem>) -> MenuItem {
+        let mut item = self.item(label, MenuItemKind::Standard, None);
+
+        item.children = children;
+
+        item
+    }
+
+    fn device_item(&mut self, _type: CardDeviceType, card_device: &CardDevice) -> MenuItem {
+        self.item(
+            &card_device.description,
+            MenuItemKind::Radio(card_device.is_default),
+            Some(Command::SetDefault {
+                _type,
+                index: card_device.index,
+                name: card_device.name.clone(),
+            }),
+        )
+    }
+}
@@ -0,0 +1,170 @@
+use std::{default::default, sync::Arc};
+
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{debug, error};
+use zbus::{dbus_interface, zvariant::OwnedObjectPath, SignalContext};
+
+use crate::{
+    device::{card_device::CardDevice, card_device_type::CardDeviceType},
+    notifier::Notification,
+    protocol::command::{self, Command},
+    pubsub::{message::Message, message_state::MessageState},
+};
+
+use super::menu::MENU_PATH;
+
+pub const ITEM_PATH: &str = "/StatusNotifierItem";
+
+const SCROLL_VOLUME_STEP: f64 = 5.0;
+
+type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+pub struct StatusNotifierItem {
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+    state: MessageState,
+}
+
+impl StatusNotifierItem {
+    pub fn new(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) -> Self {
+        Self {
+            pubsub_tx,
+            state: default(),
+        }
+    }
+
+    pub fn update(&mut self, state: MessageState) {
+        self.state = state;
+    }
+
+    pub async fn emit_changes(ctxt: &SignalContext<'_>) -> zbus::Result<()> {
+        Self::new_icon(ctxt).await?;
+        Self::new_tool_tip(ctxt).await
+    }
+
+    fn default_source(&self) -> Option<CardDevice> {
+        self.state.sources().iter().find(|s| s.is_default).cloned()
+    }
+
+    fn default_sink(&self) -> Option<CardDevice> {
+        self.state.sinks().iter().find(|s| s.is_default).cloned()
+    }
+
+    async fn execute(&self, command: Command) {
+        match command::execute(command, self.pubsub_tx.clone()).await {
+            Ok(event) => debug!("Executed a tray command: {}", event.to_json()),
+            Err(error) => error!("Could not execute a tray command: {}", error),
+        }
+    }
+}
+
+#[dbus_interface(name = "org.kde.StatusNotifierItem")]
+impl StatusNotifierItem {
+    async fn activate(&self, _x: i32, _y: i32) {
+        if let Some(source) = self.default_source() {
+            self.execute(Command::SetMute {
+                _type: CardDeviceType::Source,
+                index: source.index,
+                mute: !source.is_muted,
+            })
+            .await;
+        }
+    }
+
+    async fn secondary_activate(&self, _x: i32, _y: i32) {
+        if let Some(sink) = self.default_sink() {
+            self.execute(Command::SetMute {
+                _type: CardDeviceType::Sink,
+                index: sink.index,
+                mute: !sink.is_muted,
+            })
+            .await;
+        }
+    }
+
+    async fn scroll(&self, delta: i32, _orientation: &str) {
+        if let Some(sink) = self.default_sink() {
+            let volume = (sink.volume + SCROLL_VOLUME_STEP * delta.signum() as f64).max(0.0);
+
+            self.execute(Command::SetVolume {
+                _type: CardDeviceType::Sink,
+                index: sink.index,
+                volume,
+            })
+            .await;
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn category(&self) -> &str {
+        "Hardware"
+    }
+
+    #[dbus_interface(property)]
+    fn id(&self) -> &str {
+        "cctl"
+    }
+
+    #[dbus_interface(property)]
+    fn title(&self) -> &str {
+        "cctl"
+    }
+
+    #[dbus_interface(property)]
+    fn status(&self) -> &str {
+        "Active"
+    }
+
+    #[dbus_interface(property)]
+    fn item_is_menu(&self) -> bool {
+        false
+    }
+
+    #[dbus_interface(property)]
+    fn menu(&self) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(MENU_PATH).unwrap()
+    }
+
+    #[dbus_interface(property)]
+    fn icon_name(&self) -> String {
+        match (self.default_source(), self.default_sink()) {
+            (Some(source), _) if source.is_muted => {
+                Notification::new(CardDeviceType::Source, source, false).icon()
+            }
+            (_, Some(sink)) => Notification::new(CardDeviceType::Sink, sink, false).icon(),
+            _ => "audio-card-symbolic".to_owned(),
+        }
+    }
+
+    #[dbus_interface(property)]
+    fn tool_tip(&self) -> ToolTip {
+        let describe = |label: &str, _type: CardDeviceType, device: Option<CardDevice>| {
+            device
+                .map(|device| {
+                    format!(
+                        "{}: {} ({})",
+                        label,
+                        device.description,
+                        Notification::new(_type, device.clone(), false).summary()
+                    )
+                })
+                .unwrap_or_else(|| format!("{}: none", label))
+        };
+
+        (
+            self.icon_name(),
+            Vec::new(),
+            "cctl".to_owned(),
+            [
+                describe("Output", CardDeviceType::Sink, self.default_sink()),
+                describe("Input", CardDeviceType::Source, self.default_source()),
+            ]
+            .join("\n"),
+        )
+    }
+
+    #[dbus_interface(signal)]
+    async fn new_icon(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn new_tool_tip(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+}
@@ -0,0 +1,115 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, ErrorKind},
+    sync::{Arc, Mutex as StdMutex},
+};
+
+use once_cell::sync::Lazy;
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
+use tracing::{error, info, instrument};
+
+use crate::{
+    device::virtual_device::{self, VirtualDevice},
+    device_monitor,
+    pubsub::message::Message,
+    storage,
+};
+
+const VIRTUAL_DEVICES_FILE_NAME: &str = "virtual_devices.json";
+
+static CREATED: Lazy<StdMutex<HashSet<String>>> = Lazy::new(|| StdMutex::new(HashSet::new()));
+
+#[instrument]
+pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    let virtual_devices = match list_persisted().await {
+        Ok(virtual_devices) => virtual_devices,
+        Err(error) => {
+            error!("Could not load the persisted virtual devices: {}", error);
+
+            return;
+        }
+    };
+
+    if virtual_devices.is_empty() {
+        return;
+    }
+
+    for virtual_device in virtual_devices {
+        match virtual_device::create(&virtual_device).await {
+            Ok(_) => {
+                CREATED
+                    .lock()
+                    .unwrap()
+                    .insert(virtual_device.name().to_owned());
+
+                info!("Created persisted virtual device {}", virtual_device.name());
+            }
+            Err(error) => error!(
+                "Could not create persisted virtual device {}: {}",
+                virtual_device.name(),
+                error
+            ),
+        }
+    }
+
+    device_monitor::publish_state(&pubsub_tx).await;
+}
+
+pub async fn list_persisted() -> io::Result<Vec<VirtualDevice>> {
+    let virtual_devices: BTreeMap<String, VirtualDevice> =
+        storage::load(VIRTUAL_DEVICES_FILE_NAME).await?;
+
+    Ok(virtual_devices.into_values().collect())
+}
+
+pub async fn create(virtual_device: VirtualDevice, persist: bool) -> io::Result<()> {
+    virtual_device::create(&virtual_device).await?;
+
+    CREATED
+        .lock()
+        .unwrap()
+        .insert(virtual_device.name().to_owned());
+
+    if persist {
+        storage::update(
+            VIRTUAL_DEVICES_FILE_NAME,
+            |virtual_devices: &mut BTreeMap<String, VirtualDevice>| {
+                virtual_devices.insert(virtual_device.name().to_owned(), virtual_device);
+            },
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn remove(name: &str) -> io::Result<()> {
+    let persisted = storage::update(
+        VIRTUAL_DEVICES_FILE_NAME,
+        |virtual_devices: &mut BTreeMap<String, VirtualDevice>| virtual_devices.remove(name),
+    )
+    .await?
+    .is_some();
+
+    if !CREATED.lock().unwrap().contains(name) {
+        return if persisted {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("Virtual device {} does not exist", name),
+            ))
+        };
+    }
+
+    let result = match virtual_device::remove(name).await {
+        Err(error) if error.kind() == ErrorKind::NotFound && persisted => Ok(()),
+        result => result,
+    };
+
+    if result.is_ok() {
+        CREATED.lock().unwrap().remove(name);
+    }
+
+    result
+}
@@ -0,0 +1,208 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::{mpsc::UnboundedSender, Mutex},
+    time::{interval, MissedTickBehavior},
+};
+use tracing::{error, instrument, warn};
+
+use crate::{
+    config::{Config, VolumeLimit, VolumeLimitTarget},
+    device::{audio, card_device::CardDevice, card_device_type::CardDeviceType},
+    device_monitor,
+    notifier::{self, Alert, Notifier},
+    pubsub::{
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
+    },
+};
+
+#[instrument]
+pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
+    if config.volume_limits.is_empty() {
+        return;
+    }
+
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "volume_limits").await;
+
+    let mut notifiers: Vec<Box<dyn Notifier>> =
+        config.notifiers.iter().map(notifier::from_config).collect();
+
+    let mut poll = interval(Duration::from_secs(1));
+
+    poll.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        let corrected = tokio::select! {
+            _ = poll.tick() => {
+                let sources = audio::fetch_card_devices(CardDeviceType::Source).await;
+                let sinks = audio::fetch_card_devices(CardDeviceType::Sink).await;
+
+                match (sources, sinks) {
+                    (Ok(sources), Ok(sinks)) => {
+                        correct_volumes(&config, &mut notifiers, &sources, &sinks).await
+                    }
+                    (Err(error), _) | (_, Err(error)) => {
+                        error!("Could not fetch the device volumes: {}", error);
+
+                        false
+                    }
+                }
+            }
+            Some(message) = rx.recv() => match try_downcast_ref!(message, MessageState) {
+                Some(state) => {
+                    correct_volumes(&config, &mut notifiers, &state.sources(), &state.sinks())
+                        .await
+                }
+                None => false,
+            },
+        };
+
+        if corrected {
+            device_monitor::publish_state(&pubsub_tx).await;
+        }
+    }
+}
+
+async fn correct_volumes(
+    config: &Config,
+    notifiers: &mut [Box<dyn Notifier>],
+    sources: &[CardDevice],
+    sinks: &[CardDevice],
+) -> bool {
+    let mut corrected = false;
+
+    for (_type, card_devices) in [
+        (CardDeviceType::Source, sources),
+        (CardDeviceType::Sink, sinks),
+    ] {
+        for card_device in card_devices.iter() {
+            let volume = match limit_volume(&config.volume_limits, card_device) {
+                Some(volume) => volume,
+                None => continue,
+            };
+
+            warn!(
+                "Correcting the volume of {} {} from {}% to {}%",
+                _type, card_device.name, card_device.volume, volume
+            );
+
+            if let Err(error) = audio::set_volume(_type, card_device.index, volume).await {
+                error!(
+                    "Could not correct the volume of {}: {}",
+                    card_device.name, error
+                );
+
+                continue;
+            }
+
+            corrected = true;
+
+            let alert = Alert {
+                icon: "dialog-warning-symbolic".to_owned(),
+                summary: format!("Volume limited to {}%", volume),
+                body: format!(
+                    "{} was set to {}%",
+                    card_device.description, card_device.volume
+                ),
+            };
+
+            for notifier in notifiers.iter_mut() {
+                notifier.alert(&alert).await;
+            }
+        }
+    }
+
+    corrected
+}
+
+fn limit_volume(volume_limits: &[VolumeLimit], card_device: &CardDevice) -> Option<f64> {
+    let limit = volume_limits
+        .iter()
+        .find(|limit| match limit.target {
+            VolumeLimitTarget::Device(ref name) => name == &card_device.name,
+            _ => false,
+        })
+        .or_else(|| {
+            volume_limits.iter().find(|limit| match limit.target {
+                VolumeLimitTarget::FormFactor(ref form_factor) => {
+                    form_factor == &card_device.form_factor
+                }
+                _ => false,
+            })
+        })?;
+
+    if card_device.volume > limit.max {
+        Some(limit.max)
+    } else {
+        limit.min.filter(|min| card_device.volume < *min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+
+    use crate::{
+        config::{Config, HotkeysConfig, PrivacyConfig},
+        device::{
+            audio, card_device::CardDevice, card_device_type::CardDeviceType, simulated_server,
+        },
+        pubsub,
+    };
+
+    const SINK_NAME: &str = "alsa_output.pci-0000_00_1f.3.analog-stereo";
+    const SOURCE_NAME: &str = "alsa_input.pci-0000_00_1f.3.analog-stereo";
+    const WEBCAM_NAME: &str = "alsa_input.usb-046d_HD_Pro_Webcam_C920-02.analog-stereo";
+
+    #[tokio::test]
+    async fn keeps_volumes_within_the_limits() {
+        let _lock = simulated_server::reset().await;
+        let (pubsub_tx, _) = pubsub::start();
+        let config = Config {
+            server_addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            socket_path: None,
+            admin_token: None,
+            data_dir: PathBuf::new(),
+            dbus: false,
+            tray: false,
+            notifiers: Vec::new(),
+            hotkeys: HotkeysConfig {
+                push_to_talk_key: None,
+                toggle_mute_key: None,
+                input_devices: Vec::new(),
+            },
+            volume_limits: vec!["internal=20-50".parse().unwrap()],
+            privacy: PrivacyConfig {
+                enabled: false,
+                sources: Vec::new(),
+                deny: None,
+                allowed_applications: Vec::new(),
+            },
+            level_rate: 10.0,
+            max_listeners: 2,
+            simulate: true,
+            command: None,
+        };
+
+        tokio::spawn(super::start(Arc::new(config), pubsub_tx.clone()));
+
+        simulated_server::set_volume(CardDeviceType::Source, SOURCE_NAME, 5.0);
+
+        simulated_server::wait_until(|| async {
+            let (_, sources, sinks) = audio::fetch_devices().await;
+
+            volume(&sinks, SINK_NAME) == Some(50.0)
+                && volume(&sources, SOURCE_NAME) == Some(20.0)
+                && volume(&sources, WEBCAM_NAME) == Some(100.0)
+        })
+        .await;
+    }
+
+    fn volume(card_devices: &[CardDevice], name: &str) -> Option<f64> {
+        card_devices
+            .iter()
+            .find(|card_device| card_device.name == name)
+            .map(|card_device| card_device.volume.round())
+    }
+}
@@ -1,29 +1,17 @@
 use std::sync::Arc;
 
-use tokio::sync::{
-    mpsc::{self, UnboundedSender},
-    Mutex,
-};
+use tokio::sync::{mpsc::UnboundedSender, Mutex};
 
 use crate::{
     device::{audio, card_device::CardDevice, card_device_type::CardDeviceType},
     pubsub::{
-        message::Message, message_register::MessageRegister, message_state::MessageState,
-        message_topic::MessageTopic, try_downcast_ref::try_downcast_ref,
+        self, message::Message, message_state::MessageState, message_topic::MessageTopic,
+        try_downcast_ref::try_downcast_ref,
     },
 };
 
 pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
-    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
-
-    pubsub_tx
-        .lock()
-        .await
-        .send(Arc::new(MessageRegister::new(
-            MessageTopic::AudioState,
-            Arc::new(tx),
-        )))
-        .unwrap();
+    let mut rx = pubsub::subscribe(&pubsub_tx, MessageTopic::AudioState, "watchdog").await;
 
     let mut default_source: Option<CardDevice> = None;
     let mut default_sink: Option<CardDevice> = None;
@@ -62,3 +50,51 @@ pub async fn start(pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        device::{audio, audio_client, card_device_type::CardDeviceType, module, simulated_server},
+        device_monitor, pubsub,
+    };
+
+    #[tokio::test]
+    async fn moves_clients_to_the_default_sink() {
+        let _lock = simulated_server::reset().await;
+        let (pubsub_tx, _) = pubsub::start();
+
+        tokio::spawn(super::start(pubsub_tx.clone()));
+
+        module::load_module("module-null-sink", "sink_name=watchdog_test")
+            .await
+            .unwrap();
+
+        let (_, _, sinks) = audio::fetch_devices().await;
+        let sink = sinks
+            .iter()
+            .find(|sink| sink.name == "watchdog_test")
+            .unwrap();
+
+        audio::set_default_card_device(CardDeviceType::Sink, sink.index)
+            .await
+            .unwrap();
+
+        simulated_server::wait_until(|| {
+            let pubsub_tx = pubsub_tx.clone();
+
+            async move {
+                device_monitor::publish_state(&pubsub_tx).await;
+
+                audio_client::fetch_clients(CardDeviceType::Sink)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .any(|client| {
+                        client.application_name.as_deref() == Some("Firefox")
+                            && client.card_device_name == "watchdog_test"
+                    })
+            }
+        })
+        .await;
+    }
+}
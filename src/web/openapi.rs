@@ -0,0 +1,837 @@
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    JsonSchema,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    device::{card_device_type::CardDeviceType, module::Module, virtual_device::VirtualDevice},
+    device_memory::RememberedDevice,
+    device_test::MicrophoneTestResult,
+    equalizer::{EqualizerState, Preset},
+    levels::Level,
+    listen::ListenTicket,
+    noise_suppression::NoiseSuppressionState,
+    privacy::{self, PrivacyState},
+    pubsub::{message_recording::MessageRecording, message_state::MessageState},
+    recorder::{self, RecordingFile, RecordingOptions},
+    routing_graph::RoutingGraph,
+    scene::Scene,
+    scheduler::{Schedule, ScheduleState},
+    soundboard::{Clip, SoundboardState},
+};
+
+use super::server::{
+    ChannelsRequest, DefaultRequest, DeviceRequest, EqualizerRequest, MicrophoneTestRequest,
+    ModuleRequest, MuteRequest, NoiseSuppressionRequest, PlayClipRequest, PresetRequest,
+    PrivacyRequest, ProfileRequest, SceneRequest, SoundboardMicrophoneRequest, ToneRequest,
+    VirtualDeviceRequest, VolumeRequest,
+};
+
+pub const API_VERSION: &str = "1.16.0";
+
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    let paths = json!({
+        "/audio": {
+            "get": {
+                "summary": "Fetch the current audio state",
+                "responses": {
+                    "200": {
+                        "description": "The current audio state",
+                        "content": json_content::<MessageState>(&mut gen)
+                    }
+                }
+            }
+        },
+        "/audio/volume": command_path::<VolumeRequest>(&mut gen, "Set the volume of a source or sink"),
+        "/audio/mute": command_path::<MuteRequest>(&mut gen, "Mute or unmute a source or sink"),
+        "/audio/default": command_path::<DefaultRequest>(&mut gen, "Set the default source or sink and move the audio clients to it"),
+        "/audio/profile": command_path::<ProfileRequest>(&mut gen, "Set the profile of a bluetooth card"),
+        "/audio/ws": {
+            "get": {
+                "summary": "Open a websocket",
+                "description": "Every text message sent is a JSON object with a `command` field, one of \
+                    `get_state`, `get_routing_graph`, `set_volume`, `set_mute`, `set_default`, `set_profile`, \
+                    `move_client`, `save_scene`, `restore_scene`, `delete_scene`, `set_device_restore`, \
+                    `set_privacy`, `add_loopback`, `remove_loopback`, `create_virtual_device`, \
+                    `remove_virtual_device`, `set_noise_suppression`, `play_test_tone`, `identify_channels`, \
+                    `test_microphone`, `play_clip`, `stop_clips`, `set_soundboard_microphone`, `set_equalizer` \
+                    and `save_equalizer_preset`, along with the fields of the respective request. Every text \
+                    message received is a JSON object with an `event` field, either `state`, along with the \
+                    fields of `MessageState`, `recording`, along with the fields of `MessageRecording`, \
+                    `microphone_test`, along with the fields of `MicrophoneTestResult`, `routing_graph`, along \
+                    with the fields of `RoutingGraph`, `routing_graph_dot`, along with a `dot` string, `done`, \
+                    once a command was executed, or `error`, along with a `message`.",
+                "responses": {
+                    "101": { "description": "Switching to the websocket protocol" }
+                }
+            }
+        },
+        "/audio/levels/ws": {
+            "get": {
+                "summary": "Open a websocket streaming audio levels",
+                "description": "While at least one such websocket is open, the level of every source, and of \
+                    the monitor of every sink, is measured and sent `--level-rate` times per second as a JSON \
+                    object with an `event` field of `levels` and a `levels` array of `Level`. Messages sent \
+                    to this websocket are ignored.",
+                "responses": {
+                    "101": { "description": "Switching to the websocket protocol" }
+                }
+            }
+        },
+        "/listen": {
+            "get": {
+                "summary": "Listen to a source or to the monitor of a sink",
+                "description": "Streams the audio for as long as the response is read, for instance by an \
+                    `<audio>` element. Since such elements cannot send an `Authorization` header, a ticket \
+                    from `POST /listen/tickets` can be passed as the `ticket` query parameter instead. At most \
+                    `--max-listeners` clients listen at the same time.",
+                "security": [{ "admin": [] }],
+                "parameters": [
+                    { "name": "type", "in": "query", "required": true, "schema": schema_ref::<CardDeviceType>(&mut gen) },
+                    { "name": "name", "in": "query", "required": true, "schema": { "type": "string" } },
+                    {
+                        "name": "format",
+                        "in": "query",
+                        "required": false,
+                        "description": "WAV by default, Opus in an Ogg container needs `opusenc`",
+                        "schema": { "type": "string", "enum": ["wav", "opus"] }
+                    },
+                    { "name": "ticket", "in": "query", "required": false, "schema": { "type": "string" } }
+                ],
+                "responses": {
+                    "200": {
+                        "description": "The audio stream",
+                        "content": {
+                            "audio/wav": { "schema": { "type": "string", "format": "binary" } },
+                            "audio/ogg": { "schema": { "type": "string", "format": "binary" } }
+                        }
+                    },
+                    "404": { "description": "The source or sink does not exist" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "503": { "description": "Too many clients are listening" }
+                }
+            }
+        },
+        "/listen/tickets": {
+            "post": {
+                "summary": "Issue a ticket to listen once within a minute without the `Authorization` header",
+                "security": [{ "admin": [] }],
+                "responses": {
+                    "200": { "description": "The ticket", "content": json_content::<ListenTicket>(&mut gen) },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/routing-graph": {
+            "get": {
+                "summary": "Fetch the routing graph of applications, sinks, sources, monitors and modules",
+                "description": "Edges of kind `stream` are the streams of applications and modules, from a \
+                    source or monitor to the recording application or module, and from the playing \
+                    application or module to a sink. Edges of kind `monitor` link a sink to its monitor, and \
+                    edges of kind `module` link a virtual sink to the module consuming it, or a module to the \
+                    virtual source it provides.",
+                "parameters": [
+                    {
+                        "name": "format",
+                        "in": "query",
+                        "required": false,
+                        "schema": { "type": "string", "enum": ["json", "dot"], "default": "json" }
+                    }
+                ],
+                "responses": {
+                    "200": {
+                        "description": "The routing graph",
+                        "content": {
+                            "application/json": { "schema": schema_ref::<RoutingGraph>(&mut gen) },
+                            "text/vnd.graphviz": { "schema": { "type": "string" } }
+                        }
+                    }
+                }
+            }
+        },
+        "/modules": {
+            "get": {
+                "summary": "List the loaded modules along with their arguments",
+                "responses": {
+                    "200": {
+                        "description": "The loaded modules",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<Module>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            },
+            "post": {
+                "summary": "Load a module",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<ModuleRequest>(&mut gen) },
+                "responses": {
+                    "200": {
+                        "description": "The index of the loaded module",
+                        "content": {
+                            "application/json": { "schema": { "type": "integer", "format": "int64" } }
+                        }
+                    },
+                    "400": { "description": "The module could not be loaded" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/modules/{index}": {
+            "delete": {
+                "summary": "Unload a module",
+                "security": [{ "admin": [] }],
+                "parameters": [{
+                    "name": "index",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int64" }
+                }],
+                "responses": {
+                    "200": { "description": "The module was unloaded" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The module could not be unloaded" }
+                }
+            }
+        },
+        "/virtual-devices": {
+            "get": {
+                "summary": "List the virtual devices which are created whenever cctl starts",
+                "responses": {
+                    "200": {
+                        "description": "The persisted virtual devices",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<VirtualDevice>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            },
+            "post": {
+                "summary": "Create a virtual device",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<VirtualDeviceRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The device was created and the new state was published" },
+                    "400": { "description": "The device could not be created" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "409": { "description": "A virtual device of the same name exists" }
+                }
+            }
+        },
+        "/virtual-devices/{name}": {
+            "delete": {
+                "summary": "Remove a virtual device and stop persisting it",
+                "security": [{ "admin": [] }],
+                "parameters": [{ "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }],
+                "responses": {
+                    "200": { "description": "The device was removed and the new state was published" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The virtual device does not exist" }
+                }
+            }
+        },
+        "/loopbacks": admin_command_path::<LoopbackRequest>(&mut gen, "Play a source on a sink through module-loopback"),
+        "/loopbacks/{module_index}": {
+            "delete": {
+                "summary": "Remove a loopback",
+                "security": [{ "admin": [] }],
+                "parameters": [{
+                    "name": "module_index",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int64" }
+                }],
+                "responses": {
+                    "200": { "description": "The loopback was removed and the new state was published" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The module is not a loopback" }
+                }
+            }
+        },
+        "/recordings": {
+            "get": {
+                "summary": "List the recording files, running ones included",
+                "security": [{ "admin": [] }],
+                "responses": {
+                    "200": {
+                        "description": "The recording files",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<RecordingFile>(&mut gen) }
+                            }
+                        }
+                    },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            },
+            "post": {
+                "summary": "Start recording a source or the monitor of a sink",
+                "description": "WAV files are written by cctl, FLAC and Opus files by the `flac` and `opusenc` \
+                    encoders. The recording stops when it reaches its maximum duration or size.",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<RecordingOptions>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The started recording", "content": json_content::<recorder::Recording>(&mut gen) },
+                    "404": { "description": "The source or sink does not exist" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/recordings/{file_name}": {
+            "get": {
+                "summary": "Download a finished recording",
+                "security": [{ "admin": [] }],
+                "parameters": [
+                    { "name": "file_name", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "responses": {
+                    "200": {
+                        "description": "The recording",
+                        "content": {
+                            "audio/wav": { "schema": { "type": "string", "format": "binary" } },
+                            "audio/flac": { "schema": { "type": "string", "format": "binary" } },
+                            "audio/ogg": { "schema": { "type": "string", "format": "binary" } }
+                        }
+                    },
+                    "400": { "description": "The recording is still running" },
+                    "404": { "description": "The recording does not exist" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            },
+            "delete": {
+                "summary": "Delete a finished recording",
+                "security": [{ "admin": [] }],
+                "parameters": [
+                    { "name": "file_name", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "responses": {
+                    "200": { "description": "The recording was deleted" },
+                    "400": { "description": "The recording is still running" },
+                    "404": { "description": "The recording does not exist" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/recordings/{file_name}/stop": {
+            "post": {
+                "summary": "Stop a running recording",
+                "security": [{ "admin": [] }],
+                "parameters": [
+                    { "name": "file_name", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "responses": {
+                    "200": { "description": "The recording is stopping" },
+                    "404": { "description": "The recording is not running" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/tests/tone": command_path::<ToneRequest>(&mut gen, "Play a test tone on a sink"),
+        "/tests/channels": command_path::<ChannelsRequest>(&mut gen, "Name each channel of a sink on that channel, with espeak or else with beeps"),
+        "/tests/microphone": {
+            "post": {
+                "summary": "Record from a source, play the recording back on a sink and report its levels",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<MicrophoneTestRequest>(&mut gen) },
+                "responses": {
+                    "200": {
+                        "description": "The levels of the recording",
+                        "content": json_content::<MicrophoneTestResult>(&mut gen)
+                    },
+                    "400": { "description": "The duration is not between 0 and 30 seconds" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The source or sink does not exist" }
+                }
+            }
+        },
+        "/soundboard": {
+            "get": {
+                "summary": "Fetch the state of the soundboard",
+                "responses": {
+                    "200": { "description": "The soundboard state", "content": json_content::<SoundboardState>(&mut gen) }
+                }
+            }
+        },
+        "/soundboard/clips": {
+            "get": {
+                "summary": "List the uploaded clips",
+                "responses": {
+                    "200": {
+                        "description": "The clips",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<Clip>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/soundboard/clips/{name}": {
+            "get": {
+                "summary": "Download a clip",
+                "parameters": [scene_name_parameter()],
+                "responses": {
+                    "200": {
+                        "description": "The clip",
+                        "content": {
+                            "audio/wav": { "schema": { "type": "string", "format": "binary" } },
+                            "audio/flac": { "schema": { "type": "string", "format": "binary" } },
+                            "audio/ogg": { "schema": { "type": "string", "format": "binary" } }
+                        }
+                    },
+                    "404": { "description": "The clip does not exist" }
+                }
+            },
+            "put": {
+                "summary": "Upload a clip, replacing any clip of the same name",
+                "description": "The body is a WAV, FLAC or Ogg file of at most 10 MiB. Clip names may only \
+                    contain letters, digits, dashes and underscores.",
+                "security": [{ "admin": [] }],
+                "parameters": [scene_name_parameter()],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/octet-stream": { "schema": { "type": "string", "format": "binary" } }
+                    }
+                },
+                "responses": {
+                    "200": { "description": "The uploaded clip", "content": json_content::<Clip>(&mut gen) },
+                    "400": { "description": "The name is invalid or the file is not WAV, FLAC or Ogg" },
+                    "413": { "description": "The file is larger than 10 MiB" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            },
+            "delete": {
+                "summary": "Delete a clip",
+                "security": [{ "admin": [] }],
+                "parameters": [scene_name_parameter()],
+                "responses": {
+                    "200": { "description": "The clip was deleted" },
+                    "404": { "description": "The clip does not exist" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/soundboard/clips/{name}/play": {
+            "post": {
+                "summary": "Play a clip on a sink, into the soundboard microphone, or both",
+                "description": "Without a sink and without `microphone`, the clip plays on the default sink. \
+                    Playing into the soundboard microphone requires the admin token.",
+                "security": [{}, { "admin": [] }],
+                "parameters": [scene_name_parameter()],
+                "requestBody": { "required": true, "content": json_content::<PlayClipRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The clip started playing" },
+                    "400": { "description": "The volume is out of range or the soundboard microphone is off" },
+                    "401": { "description": "The admin token is missing or invalid and `microphone` is set" },
+                    "403": { "description": "cctl was started without --admin-token and `microphone` is set" },
+                    "404": { "description": "The clip or sink does not exist" }
+                }
+            }
+        },
+        "/soundboard/stop": {
+            "post": {
+                "summary": "Stop every playing clip",
+                "responses": {
+                    "200": { "description": "The clips were stopped" }
+                }
+            }
+        },
+        "/soundboard/microphone": {
+            "put": {
+                "summary": "Mix a source with the soundboard into a virtual microphone",
+                "description": "The source is looped back into a `cctl_soundboard` null sink, whose monitor \
+                    is remapped into the `cctl_soundboard_microphone` source for calls to record from.",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<SoundboardMicrophoneRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The soundboard microphone was created" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The source does not exist" },
+                    "409": { "description": "The soundboard microphone is already on" }
+                }
+            },
+            "delete": {
+                "summary": "Remove the soundboard microphone",
+                "security": [{ "admin": [] }],
+                "responses": {
+                    "200": { "description": "The soundboard microphone was removed" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" },
+                    "404": { "description": "The soundboard microphone is off" }
+                }
+            }
+        },
+        "/scenes": {
+            "get": {
+                "summary": "List the saved scenes",
+                "responses": {
+                    "200": {
+                        "description": "The saved scenes",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<Scene>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            },
+            "post": {
+                "summary": "Save the current setup as a scene, replacing any scene of the same name",
+                "requestBody": { "required": true, "content": json_content::<SceneRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The saved scene", "content": json_content::<Scene>(&mut gen) }
+                }
+            }
+        },
+        "/scenes/{name}": {
+            "parameters": [scene_name_parameter()],
+            "get": {
+                "summary": "Fetch a scene",
+                "responses": {
+                    "200": { "description": "The scene", "content": json_content::<Scene>(&mut gen) },
+                    "404": { "description": "The scene does not exist" }
+                }
+            },
+            "put": {
+                "summary": "Create or edit a scene",
+                "requestBody": { "required": true, "content": json_content::<Scene>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The scene was saved" }
+                }
+            },
+            "delete": {
+                "summary": "Delete a scene",
+                "responses": {
+                    "200": { "description": "The scene was deleted" },
+                    "404": { "description": "The scene does not exist" }
+                }
+            }
+        },
+        "/scenes/{name}/restore": {
+            "parameters": [scene_name_parameter()],
+            "post": {
+                "summary": "Restore a scene",
+                "description": "Sets the bluetooth profiles first, then the volumes, mute states and defaults, \
+                    and finally moves the applications of the scene to their devices. Devices which are \
+                    missing are skipped. Applications stay on their scene's devices until another scene is \
+                    restored.",
+                "responses": {
+                    "200": { "description": "The scene was restored and the new state was published" },
+                    "404": { "description": "The scene does not exist" }
+                }
+            }
+        },
+        "/schedules": {
+            "get": {
+                "summary": "List the schedules along with their next run",
+                "responses": {
+                    "200": {
+                        "description": "The schedules",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<ScheduleState>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/schedules/{name}": {
+            "parameters": [{ "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }],
+            "get": {
+                "summary": "Fetch a schedule along with its next run",
+                "responses": {
+                    "200": { "description": "The schedule", "content": json_content::<ScheduleState>(&mut gen) },
+                    "404": { "description": "The schedule does not exist" }
+                }
+            },
+            "put": {
+                "summary": "Create or edit a schedule",
+                "requestBody": { "required": true, "content": json_content::<Schedule>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The schedule was saved" },
+                    "400": { "description": "The cron expression is invalid" }
+                }
+            },
+            "delete": {
+                "summary": "Delete a schedule",
+                "responses": {
+                    "200": { "description": "The schedule was deleted" },
+                    "404": { "description": "The schedule does not exist" }
+                }
+            }
+        },
+        "/privacy": {
+            "get": {
+                "summary": "Fetch whether privacy mode is on and which applications are recording",
+                "responses": {
+                    "200": { "description": "The privacy state", "content": json_content::<PrivacyState>(&mut gen) }
+                }
+            },
+            "put": {
+                "summary": "Turn privacy mode on or off",
+                "description": "In privacy mode the privacy sources are kept muted, every recording raises an \
+                    alert and, with a deny policy, recordings by applications which are not allowed are \
+                    killed or muted.",
+                "security": [{ "admin": [] }],
+                "requestBody": { "required": true, "content": json_content::<PrivacyRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "Privacy mode was turned on or off" },
+                    "401": { "description": "The admin token is missing or invalid" },
+                    "403": { "description": "cctl was started without --admin-token" }
+                }
+            }
+        },
+        "/noise-suppression": {
+            "get": {
+                "summary": "Fetch which sources are processed for noise suppression and echo cancellation",
+                "responses": {
+                    "200": {
+                        "description": "The noise suppression state",
+                        "content": json_content::<NoiseSuppressionState>(&mut gen)
+                    }
+                }
+            }
+        },
+        "/noise-suppression/{source_name}": {
+            "put": {
+                "summary": "Turn noise suppression and echo cancellation on or off for a source",
+                "description": "Turning it on sets up a processed source, fed by the source through \
+                    module-echo-cancel and the RNNoise LADSPA plugin when it is installed, and makes it the \
+                    default source. The choice is remembered, and the processing is torn down while the \
+                    source is gone and set up again when it comes back.",
+                "parameters": [
+                    { "name": "source_name", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "requestBody": { "required": true, "content": json_content::<NoiseSuppressionRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "Noise suppression was turned on or off" },
+                    "400": { "description": "The source is itself a processed source" },
+                    "404": { "description": "The source does not exist" }
+                }
+            }
+        },
+        "/equalizer": {
+            "get": {
+                "summary": "Fetch the equalizer presets, which sink uses which preset and the equalized sinks",
+                "responses": {
+                    "200": { "description": "The equalizer state", "content": json_content::<EqualizerState>(&mut gen) }
+                }
+            }
+        },
+        "/equalizer/presets": {
+            "get": {
+                "summary": "List the equalizer presets",
+                "responses": {
+                    "200": {
+                        "description": "The presets",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<Preset>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/equalizer/presets/{name}": {
+            "get": {
+                "summary": "Fetch an equalizer preset",
+                "parameters": [scene_name_parameter()],
+                "responses": {
+                    "200": { "description": "The preset", "content": json_content::<Preset>(&mut gen) },
+                    "404": { "description": "The preset does not exist" }
+                }
+            },
+            "put": {
+                "summary": "Create or replace an equalizer preset",
+                "description": "If the bands changed, the chains of the sinks equalized with the preset are torn down \
+                    and set up again: the default sink switches back to the sink and then to the new equalized \
+                    sink, and their sound drops out for a moment.",
+                "parameters": [scene_name_parameter()],
+                "requestBody": { "required": true, "content": json_content::<PresetRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The preset was saved" },
+                    "400": { "description": "A band is out of range, or there are no or too many bands" }
+                }
+            },
+            "delete": {
+                "summary": "Delete an equalizer preset",
+                "parameters": [scene_name_parameter()],
+                "responses": {
+                    "200": { "description": "The preset was deleted" },
+                    "400": { "description": "A sink uses the preset" },
+                    "404": { "description": "The preset does not exist" }
+                }
+            }
+        },
+        "/equalizer/devices/{sink_name}": {
+            "put": {
+                "summary": "Set or clear the equalizer preset of a sink",
+                "description": "Whenever the sink is the default sink, it is equalized by a chain of \
+                    module-ladspa-sink filters, one per band, ending in a `SINK.equalized` sink that is made \
+                    the default sink.",
+                "parameters": [
+                    { "name": "sink_name", "in": "path", "required": true, "schema": { "type": "string" } }
+                ],
+                "requestBody": { "required": true, "content": json_content::<EqualizerRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The preset of the sink was set or cleared" },
+                    "400": { "description": "The sink is itself an equalizer" },
+                    "404": { "description": "The sink or preset does not exist" }
+                }
+            }
+        },
+        "/privacy/recordings": {
+            "get": {
+                "summary": "List the finished recordings, oldest first",
+                "responses": {
+                    "200": {
+                        "description": "The last 1000 recordings",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<privacy::Recording>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/devices": {
+            "get": {
+                "summary": "List the remembered devices",
+                "description": "cctl remembers the last volume and mute state of every source and sink, and \
+                    the last profile of every bluetooth card, and restores them when the device reappears.",
+                "responses": {
+                    "200": {
+                        "description": "The remembered devices",
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "array", "items": schema_ref::<RememberedDevice>(&mut gen) }
+                            }
+                        }
+                    }
+                }
+            }
+        },
+        "/devices/{key}": {
+            "parameters": [{ "name": "key", "in": "path", "required": true, "schema": { "type": "string" } }],
+            "put": {
+                "summary": "Set whether a remembered device is restored when it reappears",
+                "requestBody": { "required": true, "content": json_content::<DeviceRequest>(&mut gen) },
+                "responses": {
+                    "200": { "description": "The setting was saved" },
+                    "404": { "description": "The device is not remembered" }
+                }
+            },
+            "delete": {
+                "summary": "Forget a device",
+                "responses": {
+                    "200": { "description": "The device was forgotten" },
+                    "404": { "description": "The device is not remembered" }
+                }
+            }
+        },
+        "/openapi.json": {
+            "get": {
+                "summary": "Fetch this document",
+                "responses": {
+                    "200": { "description": "The OpenAPI document of this API" }
+                }
+            }
+        }
+    });
+
+    gen.subschema_for::<Level>();
+    gen.subschema_for::<MessageRecording>();
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "cctl",
+            "version": API_VERSION,
+            "description": "Controls the audio devices of the host cctl runs on. Within /api/v1, fields and \
+                endpoints are only ever added; renaming or removing either, or changing their meaning, \
+                happens in a new /api/vN prefix, which is served alongside the previous one for at least one \
+                release."
+        },
+        "servers": [{ "url": "/api/v1" }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "admin": { "type": "http", "scheme": "bearer", "description": "The --admin-token of cctl" }
+            },
+            "schemas": gen.take_definitions()
+        }
+    })
+}
+
+fn command_path<T: JsonSchema>(gen: &mut SchemaGenerator, summary: &str) -> Value {
+    json!({
+        "post": {
+            "summary": summary,
+            "requestBody": {
+                "required": true,
+                "content": json_content::<T>(gen)
+            },
+            "responses": {
+                "200": { "description": "The command was executed and the new state was published" }
+            }
+        }
+    })
+}
+
+fn admin_command_path<T: JsonSchema>(gen: &mut SchemaGenerator, summary: &str) -> Value {
+    json!({
+        "post": {
+            "summary": summary,
+            "security": [{ "admin": [] }],
+            "requestBody": {
+                "required": true,
+                "content": json_content::<T>(gen)
+            },
+            "responses": {
+                "200": { "description": "The command was executed and the new state was published" },
+                "401": { "description": "The admin token is missing or invalid" },
+                "403": { "description": "cctl was started without --admin-token" }
+            }
+        }
+    })
+}
+
+fn scene_name_parameter() -> Value {
+    json!({ "name": "name", "in": "path", "required": true, "schema": { "type": "string" } })
+}
+
+fn json_content<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    json!({ "application/json": { "schema": schema_ref::<T>(gen) } })
+}
+
+fn schema_ref<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    json!(gen.subschema_for::<T>())
+}
@@ -1,49 +1,58 @@
-use std::sync::Arc;
+use std::{io::ErrorKind, sync::Arc};
 
 use axum::{
+    body::Bytes,
     extract::{
         ws::{self, WebSocket},
-        WebSocketUpgrade,
+        ContentLengthLimit, Path, Query, WebSocketUpgrade,
     },
-    handler::options,
+    handler::{get, options},
+    http::{HeaderMap, StatusCode},
     response::{Headers, IntoResponse},
     Json, Router,
 };
+use schemars::JsonSchema;
 use serde::Deserialize;
-use tokio::sync::{
-    mpsc::{self, UnboundedReceiver, UnboundedSender},
-    Mutex,
+use tokio::{
+    io,
+    sync::{mpsc::UnboundedSender, Mutex},
 };
 use tracing::{info, instrument, log::debug};
 
 use crate::{
     config::Config,
-    device::{audio, card_device_type::CardDeviceType, card_profile::CardProfile},
+    device::{
+        card_device_type::CardDeviceType, card_profile::CardProfile, equalizer::Band, module,
+        virtual_device::VirtualDevice,
+    },
+    device_memory, device_monitor,
+    device_test::{self, CardDeviceSelector},
+    equalizer, levels,
+    listen::{self, ListenFormat, ListenTicket},
+    metrics::METRICS,
+    noise_suppression, privacy,
+    protocol::{
+        command::{self, Command},
+        event::Event,
+    },
     pubsub::{
+        self,
         message::{Message, MessagePayload},
-        message_register::MessageRegister,
-        message_state::MessageState,
         message_topic::MessageTopic,
-        try_downcast_ref::try_downcast_ref,
     },
+    recorder::{self, Recording, RecordingFile, RecordingOptions},
+    routing_graph::{self, RoutingGraphFormat},
+    scene::{self, Scene},
+    scheduler::{self, Schedule},
+    soundboard::{self, Clip},
+    virtual_devices,
 };
 
+use super::openapi;
+
 #[instrument]
 pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>) {
-    let (tx, rx) = mpsc::unbounded_channel::<Message>();
-    let tx = Arc::new(tx);
-    let rx = Arc::new(Mutex::new(rx));
-
-    pubsub_tx
-        .lock()
-        .await
-        .send(Arc::new(MessageRegister::new(
-            MessageTopic::AudioState,
-            tx.clone(),
-        )))
-        .unwrap();
-
-    let app = Router::new()
+    let api = Router::new()
         .route(
             "/audio",
             options(async move || wrap_cors(())).get({
@@ -96,16 +105,573 @@ pub async fn start(config: Arc<Config>, pubsub_tx: Arc<Mutex<UnboundedSender<Mes
                 }
             }),
         )
+        .route(
+            "/audio/levels/ws",
+            options(async move || wrap_cors(()))
+                .get(async move |ws: WebSocketUpgrade| ws.on_upgrade(ws_handle_levels_socket)),
+        )
+        .route(
+            "/listen",
+            options(async move || wrap_cors(())).get({
+                let config = config.clone();
+
+                async move |headers: HeaderMap, Query(request): Query<ListenRequest>| {
+                    wrap_cors(handle_listen_request(&config, &headers, request).await)
+                }
+            }),
+        )
+        .route(
+            "/listen/tickets",
+            options(async move || wrap_cors(())).post({
+                let config = config.clone();
+
+                async move |headers: HeaderMap| {
+                    wrap_cors(handle_listen_ticket_request(&config, &headers).await)
+                }
+            }),
+        )
+        .route(
+            "/routing-graph",
+            options(async move || wrap_cors(())).get(
+                async move |Query(RoutingGraphRequest { format }): Query<RoutingGraphRequest>| {
+                    wrap_cors(handle_routing_graph_request(format).await)
+                },
+            ),
+        )
+        .route(
+            "/modules",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(module::fetch_modules().await)))
+                .post({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap, request: Json<ModuleRequest>| {
+                        wrap_cors(
+                            handle_load_module_request(&config, &headers, request, pubsub_tx).await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/modules/:index",
+            options(async move || wrap_cors(())).delete({
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |headers: HeaderMap, Path(index): Path<u64>| {
+                    wrap_cors(
+                        handle_unload_module_request(&config, &headers, index, pubsub_tx).await,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/virtual-devices",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(virtual_devices::list_persisted().await)))
+                .post({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap,
+                                Json(VirtualDeviceRequest { device, persist }): Json<
+                        VirtualDeviceRequest,
+                    >| {
+                        wrap_cors(
+                            execute_admin_command(
+                                &config,
+                                &headers,
+                                Command::CreateVirtualDevice { device, persist },
+                                pubsub_tx,
+                            )
+                            .await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/virtual-devices/:name",
+            options(async move || wrap_cors(())).delete({
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |headers: HeaderMap, Path(name): Path<String>| {
+                    wrap_cors(
+                        execute_admin_command(
+                            &config,
+                            &headers,
+                            Command::RemoveVirtualDevice { name },
+                            pubsub_tx,
+                        )
+                        .await,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/loopbacks",
+            options(async move || wrap_cors(())).post({
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |headers: HeaderMap,
+                            Json(LoopbackRequest {
+                                source_name,
+                                sink_name,
+                                latency_msec,
+                            }): Json<LoopbackRequest>| {
+                    wrap_cors(
+                        execute_admin_command(
+                            &config,
+                            &headers,
+                            Command::AddLoopback {
+                                source_name,
+                                sink_name,
+                                latency_msec,
+                            },
+                            pubsub_tx,
+                        )
+                        .await,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/loopbacks/:module_index",
+            options(async move || wrap_cors(())).delete({
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |headers: HeaderMap, Path(module_index): Path<u64>| {
+                    wrap_cors(
+                        execute_admin_command(
+                            &config,
+                            &headers,
+                            Command::RemoveLoopback { module_index },
+                            pubsub_tx,
+                        )
+                        .await,
+                    )
+                }
+            }),
+        )
+        .route(
+            "/recordings",
+            options(async move || wrap_cors(()))
+                .get({
+                    let config = config.clone();
+
+                    async move |headers: HeaderMap| {
+                        wrap_cors(handle_list_recordings_request(&config, &headers).await)
+                    }
+                })
+                .post({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap, request: Json<RecordingOptions>| {
+                        wrap_cors(
+                            handle_start_recording_request(&config, &headers, request, pubsub_tx)
+                                .await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/recordings/:file_name",
+            options(async move || wrap_cors(()))
+                .get({
+                    let config = config.clone();
+
+                    async move |headers: HeaderMap, Path(file_name): Path<String>| {
+                        wrap_cors(
+                            handle_download_recording_request(&config, &headers, &file_name).await,
+                        )
+                    }
+                })
+                .delete({
+                    let config = config.clone();
+
+                    async move |headers: HeaderMap, Path(file_name): Path<String>| {
+                        wrap_cors(
+                            handle_delete_recording_request(&config, &headers, &file_name).await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/recordings/:file_name/stop",
+            options(async move || wrap_cors(())).post({
+                let config = config.clone();
+
+                async move |headers: HeaderMap, Path(file_name): Path<String>| {
+                    wrap_cors(
+                        authorize(&config, &headers)
+                            .and_then(|_| to_response(recorder::stop(&file_name))),
+                    )
+                }
+            }),
+        )
+        .route(
+            "/tests/tone",
+            options(async move || wrap_cors(())).post({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |Json(request): Json<ToneRequest>| {
+                    wrap_cors(execute_command(
+                            Command::PlayTestTone {
+                                sink: request.sink,
+                                frequency: request.frequency,
+                                duration_seconds: request.duration_seconds,
+                            },
+                            pubsub_tx,
+                        ).await)
+                }
+            }),
+        )
+        .route(
+            "/tests/channels",
+            options(async move || wrap_cors(())).post({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |Json(ChannelsRequest { sink }): Json<ChannelsRequest>| {
+                    wrap_cors(execute_command(Command::IdentifyChannels { sink }, pubsub_tx).await)
+                }
+            }),
+        )
+        .route(
+            "/tests/microphone",
+            options(async move || wrap_cors(())).post({
+                let config = config.clone();
+
+                async move |headers: HeaderMap, Json(request): Json<MicrophoneTestRequest>| {
+                    wrap_cors(match authorize(&config, &headers) {
+                        Ok(()) => to_response(
+                            device_test::test_microphone(
+                                &request.source,
+                                request.sink.as_ref(),
+                                request.duration_seconds,
+                            )
+                            .await,
+                        ),
+                        Err(error) => Err(error),
+                    })
+                }
+            }),
+        )
+        .route(
+            "/soundboard",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(Json(soundboard::state().await))),
+        )
+        .route(
+            "/soundboard/clips",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(soundboard::list().await))),
+        )
+        .route(
+            "/soundboard/clips/:name",
+            options(async move || wrap_cors(()))
+                .get(async move |Path(name): Path<String>| {
+                    wrap_cors(handle_download_clip_request(&name).await)
+                })
+                .put({
+                    let config = config.clone();
+
+                    async move |headers: HeaderMap,
+                                Path(name): Path<String>,
+                                ContentLengthLimit(contents): ClipUpload| {
+                        wrap_cors(
+                            handle_upload_clip_request(&config, &headers, &name, &contents).await,
+                        )
+                    }
+                })
+                .delete({
+                    let config = config.clone();
+
+                    async move |headers: HeaderMap, Path(name): Path<String>| {
+                        wrap_cors(handle_delete_clip_request(&config, &headers, &name).await)
+                    }
+                }),
+        )
+        .route(
+            "/soundboard/clips/:name/play",
+            options(async move || wrap_cors(())).post({
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |headers: HeaderMap,
+                            Path(name): Path<String>,
+                            Json(request): Json<PlayClipRequest>| {
+                    let command = Command::PlayClip {
+                        name,
+                        sink_name: request.sink_name,
+                        volume: request.volume,
+                        microphone: request.microphone,
+                    };
+
+                    wrap_cors(if command.requires_admin() {
+                        execute_admin_command(&config, &headers, command, pubsub_tx).await
+                    } else {
+                        execute_command(command, pubsub_tx).await
+                    })
+                }
+            }),
+        )
+        .route(
+            "/soundboard/stop",
+            options(async move || wrap_cors(())).post({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move || {
+                    wrap_cors(execute_command(Command::StopClips, pubsub_tx).await)
+                }
+            }),
+        )
+        .route(
+            "/soundboard/microphone",
+            options(async move || wrap_cors(()))
+                .put({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap,
+                                Json(request): Json<SoundboardMicrophoneRequest>| {
+                        wrap_cors(
+                            execute_admin_command(
+                                &config,
+                                &headers,
+                                Command::SetSoundboardMicrophone {
+                                    source_name: Some(request.source_name),
+                                },
+                                pubsub_tx,
+                            )
+                            .await,
+                        )
+                    }
+                })
+                .delete({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap| {
+                        wrap_cors(
+                            execute_admin_command(
+                                &config,
+                                &headers,
+                                Command::SetSoundboardMicrophone { source_name: None },
+                                pubsub_tx,
+                            )
+                            .await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/scenes",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(scene::list().await)))
+                .post(
+                    async move |Json(SceneRequest { name }): Json<SceneRequest>| {
+                        wrap_cors(to_response(scene::capture(&name).await))
+                    },
+                ),
+        )
+        .route(
+            "/scenes/:name",
+            options(async move || wrap_cors(()))
+                .get(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(scene::get(&name).await))
+                })
+                .put(
+                    async move |Path(name): Path<String>, Json(scene): Json<Scene>| {
+                        wrap_cors(to_response(scene::save(Scene { name, ..scene }).await))
+                    },
+                )
+                .delete(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(scene::delete(&name).await))
+                }),
+        )
+        .route(
+            "/scenes/:name/restore",
+            options(async move || wrap_cors(())).post({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |Path(name): Path<String>| {
+                    wrap_cors(execute_command(Command::RestoreScene { name }, pubsub_tx).await)
+                }
+            }),
+        )
+        .route(
+            "/schedules",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(scheduler::list().await))),
+        )
+        .route(
+            "/schedules/:name",
+            options(async move || wrap_cors(()))
+                .get(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(scheduler::get(&name).await))
+                })
+                .put(
+                    async move |Path(name): Path<String>, Json(schedule): Json<Schedule>| {
+                        wrap_cors(to_response(
+                            scheduler::save(Schedule { name, ..schedule }).await,
+                        ))
+                    },
+                )
+                .delete(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(scheduler::delete(&name).await))
+                }),
+        )
+        .route(
+            "/privacy",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(Json(privacy::state())))
+                .put({
+                    let config = config.clone();
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |headers: HeaderMap,
+                                Json(PrivacyRequest { enabled }): Json<PrivacyRequest>| {
+                        wrap_cors(
+                            execute_admin_command(
+                                &config,
+                                &headers,
+                                Command::SetPrivacy { enabled },
+                                pubsub_tx,
+                            )
+                            .await,
+                        )
+                    }
+                }),
+        )
+        .route(
+            "/noise-suppression",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(noise_suppression::state().await))),
+        )
+        .route(
+            "/noise-suppression/:source_name",
+            options(async move || wrap_cors(())).put({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |Path(source_name): Path<String>,
+                            Json(request): Json<NoiseSuppressionRequest>| {
+                    wrap_cors(execute_command(
+                            Command::SetNoiseSuppression {
+                                source_name,
+                                enabled: request.enabled,
+                            },
+                            pubsub_tx,
+                        ).await)
+                }
+            }),
+        )
+        .route(
+            "/equalizer",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(equalizer::state().await))),
+        )
+        .route(
+            "/equalizer/presets",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(equalizer::list_presets().await))),
+        )
+        .route(
+            "/equalizer/presets/:name",
+            options(async move || wrap_cors(()))
+                .get(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(equalizer::get_preset(&name).await))
+                })
+                .put({
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |Path(name): Path<String>, Json(request): Json<PresetRequest>| {
+                        wrap_cors(execute_command(
+                                Command::SaveEqualizerPreset {
+                                    name,
+                                    bands: request.bands,
+                                },
+                                pubsub_tx,
+                            ).await)
+                    }
+                })
+                .delete(async move |Path(name): Path<String>| {
+                    wrap_cors(to_response(equalizer::delete_preset(&name).await))
+                }),
+        )
+        .route(
+            "/equalizer/devices/:sink_name",
+            options(async move || wrap_cors(())).put({
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |Path(sink_name): Path<String>, Json(request): Json<EqualizerRequest>| {
+                    wrap_cors(execute_command(
+                            Command::SetEqualizer {
+                                sink_name,
+                                preset: request.preset,
+                            },
+                            pubsub_tx,
+                        ).await)
+                }
+            }),
+        )
+        .route(
+            "/privacy/recordings",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(privacy::recordings().await))),
+        )
+        .route(
+            "/devices",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(to_response(device_memory::list().await))),
+        )
+        .route(
+            "/devices/:key",
+            options(async move || wrap_cors(()))
+                .put({
+                    let pubsub_tx = pubsub_tx.clone();
+
+                    async move |Path(key): Path<String>,
+                                Json(DeviceRequest { restore }): Json<DeviceRequest>| {
+                        wrap_cors(execute_command(Command::SetDeviceRestore { key, restore }, pubsub_tx).await)
+                    }
+                })
+                .delete(async move |Path(key): Path<String>| {
+                    wrap_cors(to_response(device_memory::forget(&key).await))
+                }),
+        )
         .route(
             "/audio/ws",
             options(async move || wrap_cors(())).get({
-                let rx = rx.clone();
+                let config = config.clone();
+                let pubsub_tx = pubsub_tx.clone();
+
+                async move |ws, headers: HeaderMap| {
+                    let admin = authorize(&config, &headers).is_ok();
 
-                async move |ws| ws_handle_upgrade_messages(ws, rx).await
+                    ws_handle_upgrade_messages(ws, admin, pubsub_tx).await
+                }
             }),
+        )
+        .route(
+            "/openapi.json",
+            options(async move || wrap_cors(()))
+                .get(async move || wrap_cors(Json(openapi::document()))),
         );
 
-    info!("Listening on http://{}", config.server_addr);
+    let app = api
+        .clone()
+        .nest("/api/v1", api)
+        .route("/metrics", get(async move || metrics_handler().await));
+
+    info!(
+        "Listening on http://{} (API version {})",
+        config.server_addr,
+        openapi::API_VERSION
+    );
 
     axum::Server::bind(&config.server_addr)
         .serve(app.into_make_service())
@@ -117,55 +683,161 @@ fn wrap_cors(response: impl IntoResponse) -> impl IntoResponse {
     (
         Headers([
             ("Access-Control-Allow-Origin", "*"),
-            ("Access-Control-Allow-Headers", "Content-Type"),
+            (
+                "Access-Control-Allow-Headers",
+                "Content-Type, Authorization",
+            ),
+            (
+                "Access-Control-Allow-Methods",
+                "GET, POST, PUT, DELETE, OPTIONS",
+            ),
         ]),
         response,
     )
 }
 
+async fn execute_command(
+    command: Command,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    to_response(command::execute(command, pubsub_tx).await.map(|_| ()))
+}
+
+async fn execute_admin_command(
+    config: &Config,
+    headers: &HeaderMap,
+    command: Command,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    execute_command(command, pubsub_tx).await
+}
+
+fn to_response<T>(result: io::Result<T>) -> Result<Json<T>, (StatusCode, String)> {
+    result.map(Json).map_err(|error| {
+        let status = match error.kind() {
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+            ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, error.to_string())
+    })
+}
+
+fn authorize(config: &Config, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let admin_token = config.admin_token.as_ref().ok_or((
+        StatusCode::FORBIDDEN,
+        "The admin endpoints are disabled without --admin-token".to_owned(),
+    ))?;
+
+    let authorization = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if authorization != Some(admin_token.as_str()) {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid admin token".to_owned()));
+    }
+
+    Ok(())
+}
+
+fn authorize_listener(
+    config: &Config,
+    headers: &HeaderMap,
+    ticket: Option<&str>,
+) -> Result<(), (StatusCode, String)> {
+    match ticket {
+        Some(ticket) if config.admin_token.is_some() && listen::redeem_ticket(ticket) => Ok(()),
+        _ => authorize(config, headers),
+    }
+}
+
 async fn audio_handler(
     pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) -> impl IntoResponse {
     debug!("Fetching the state of audio devices in web server");
 
-    let (cards, sources, sinks) = audio::fetch_devices().await;
-    let message_state = MessageState::new(Arc::new(cards), Arc::new(sources), Arc::new(sinks));
-
-    pubsub_tx
-        .lock()
-        .await
-        .send(Arc::new((
-            MessageTopic::AudioState,
-            Arc::new(message_state.clone()),
-        )))
-        .unwrap();
+    Json(device_monitor::publish_state(&pubsub_tx).await)
+}
 
-    Json(message_state)
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        Headers([("Content-Type", "text/plain; version=0.0.4")]),
+        METRICS.render().await,
+    )
 }
 
 async fn ws_handle_upgrade_messages(
     ws: WebSocketUpgrade,
-    rx: Arc<Mutex<UnboundedReceiver<Arc<dyn MessagePayload + Send + Sync>>>>,
+    admin: bool,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(async move |socket| ws_handle_messages_socket(socket, rx).await)
+    ws.on_upgrade(async move |socket| ws_handle_messages_socket(socket, admin, pubsub_tx).await)
 }
 
 async fn ws_handle_messages_socket(
     mut socket: WebSocket,
-    rx: Arc<Mutex<UnboundedReceiver<Arc<dyn MessagePayload + Send + Sync>>>>,
+    admin: bool,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) {
+    let mut rx = pubsub::subscribe_topics(
+        &pubsub_tx,
+        &[MessageTopic::AudioState, MessageTopic::Recording],
+        "websocket",
+    )
+    .await;
+    let _websocket_client = METRICS.websocket_client();
+
     loop {
-        if let Some(message) = rx.lock().await.recv().await {
-            let message = try_downcast_ref!(message, MessageState).cloned();
+        tokio::select! {
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(ws::Message::Text(text))) => text,
+                    Some(Ok(_)) => continue,
+                    _ => return,
+                };
+
+                debug!("Executing a command from the websocket");
+
+                let event = command::execute_json(&text, admin, pubsub_tx.clone()).await;
+
+                if socket.send(ws::Message::Text(event.to_json())).await.is_err() {
+                    return;
+                }
+            }
+            Some(message) = rx.recv() => {
+                if let Some(event) = Event::from_message(&message) {
+                    debug!("Sending message down the websocket");
+
+                    if socket.send(ws::Message::Text(event.to_json())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn ws_handle_levels_socket(mut socket: WebSocket) {
+    let mut levels = levels::subscribe();
+    let _websocket_client = METRICS.websocket_client();
 
-            if let Some(message) = message {
-                debug!("Sending message down the websocket");
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                match message {
+                    Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => return,
+                    _ => continue,
+                }
+            }
+            Some(levels) = levels.recv() => {
+                let event = Event::Levels { levels: levels.to_vec() };
 
-                if socket
-                    .send(ws::Message::Text(serde_json::to_string(&message).unwrap()))
-                    .await
-                    .is_err()
-                {
+                if socket.send(ws::Message::Text(event.to_json())).await.is_err() {
                     return;
                 }
             }
@@ -173,11 +845,12 @@ async fn ws_handle_messages_socket(
     }
 }
 
-#[derive(Deserialize, Debug)]
-struct VolumeRequest {
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct VolumeRequest {
     #[serde(rename(deserialize = "type"))]
     _type: CardDeviceType,
     index: u64,
+    #[schemars(range(min = 0, max = 100))]
     volume: f64,
 }
 
@@ -189,18 +862,20 @@ async fn handle_volume_request(
     }): Json<VolumeRequest>,
     pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) {
-    debug!(
-        "Setting the volume of {} device index {} to {}",
-        _type, index, volume
-    );
-
-    audio::set_volume(_type, index, volume).await.unwrap();
-
-    audio_handler(pubsub_tx.clone()).await;
+    command::execute(
+        Command::SetVolume {
+            _type,
+            index,
+            volume,
+        },
+        pubsub_tx,
+    )
+    .await
+    .unwrap();
 }
 
-#[derive(Deserialize, Debug)]
-struct MuteRequest {
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct MuteRequest {
     #[serde(rename(deserialize = "type"))]
     _type: CardDeviceType,
     index: u64,
@@ -211,18 +886,13 @@ async fn handle_mute_request(
     Json(MuteRequest { _type, index, mute }): Json<MuteRequest>,
     pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) {
-    debug!(
-        "Setting the mute state of {} device index {} to {}",
-        _type, index, mute
-    );
-
-    audio::toggle_mute(_type, index, mute).await.unwrap();
-
-    audio_handler(pubsub_tx.clone()).await;
+    command::execute(Command::SetMute { _type, index, mute }, pubsub_tx)
+        .await
+        .unwrap();
 }
 
-#[derive(Deserialize, Debug)]
-struct DefaultRequest {
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct DefaultRequest {
     #[serde(rename(deserialize = "type"))]
     _type: CardDeviceType,
     index: u64,
@@ -233,27 +903,296 @@ async fn handle_default_request(
     Json(DefaultRequest { _type, index, name }): Json<DefaultRequest>,
     pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) {
-    debug!(
-        "Setting the default {} device to index {} (name = {})",
-        _type, index, name
-    );
+    command::execute(Command::SetDefault { _type, index, name }, pubsub_tx)
+        .await
+        .unwrap();
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct ModuleRequest {
+    name: String,
+    #[serde(default)]
+    argument: String,
+}
 
-    audio::set_default_card_device(_type, index).await.unwrap();
+async fn handle_load_module_request(
+    config: &Config,
+    headers: &HeaderMap,
+    Json(ModuleRequest { name, argument }): Json<ModuleRequest>,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> Result<Json<u64>, (StatusCode, String)> {
+    authorize(config, headers)?;
 
-    debug!(
-        "Moving audio clients to {} device index {} (name = {})",
-        _type, index, name
-    );
+    let index = to_response(module::load_module(&name, &argument).await)?;
 
-    audio::move_audio_clients(_type, index, &name)
-        .await
-        .unwrap();
+    device_monitor::publish_state(&pubsub_tx).await;
 
-    audio_handler(pubsub_tx.clone()).await;
+    Ok(index)
+}
+
+async fn handle_unload_module_request(
+    config: &Config,
+    headers: &HeaderMap,
+    index: u64,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> Result<Json<()>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(module::unload_module(index).await)?;
+
+    device_monitor::publish_state(&pubsub_tx).await;
+
+    Ok(Json(()))
+}
+
+async fn handle_listen_ticket_request(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<Json<ListenTicket>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(listen::issue_ticket())
+}
+
+async fn handle_listen_request(
+    config: &Config,
+    headers: &HeaderMap,
+    ListenRequest {
+        _type,
+        name,
+        format,
+        ticket,
+    }: ListenRequest,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize_listener(config, headers, ticket.as_deref())?;
+
+    let listener = listen::acquire(config.max_listeners).ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        format!("There are already {} listeners", config.max_listeners),
+    ))?;
+    let format = format.unwrap_or(ListenFormat::Wav);
+
+    let Json(body) = to_response(listen::stream(listener, _type, &name, format).await)?;
+
+    Ok((
+        Headers([
+            ("Content-Type", format.content_type()),
+            ("Cache-Control", "no-store"),
+        ]),
+        body,
+    ))
+}
+
+async fn handle_routing_graph_request(
+    format: Option<RoutingGraphFormat>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let Json(graph) = to_response(routing_graph::fetch().await)?;
+
+    Ok(match format.unwrap_or_default() {
+        RoutingGraphFormat::Json => (
+            Headers([("Content-Type", "application/json")]),
+            serde_json::to_string(&graph).unwrap(),
+        ),
+        RoutingGraphFormat::Dot => (
+            Headers([("Content-Type", "text/vnd.graphviz")]),
+            graph.to_dot(),
+        ),
+    })
+}
+
+async fn handle_start_recording_request(
+    config: &Config,
+    headers: &HeaderMap,
+    Json(options): Json<RecordingOptions>,
+    pubsub_tx: Arc<Mutex<UnboundedSender<Message>>>,
+) -> Result<Json<Recording>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(recorder::start(options, pubsub_tx).await)
+}
+
+async fn handle_list_recordings_request(
+    config: &Config,
+    headers: &HeaderMap,
+) -> Result<Json<Vec<RecordingFile>>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(recorder::list().await)
+}
+
+async fn handle_download_recording_request(
+    config: &Config,
+    headers: &HeaderMap,
+    file_name: &str,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    let Json((format, size_bytes, body)) = to_response(recorder::read(file_name).await)?;
+
+    Ok((
+        Headers(vec![
+            ("Content-Type", format.content_type().to_owned()),
+            ("Content-Length", size_bytes.to_string()),
+            (
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ]),
+        body,
+    ))
+}
+
+async fn handle_delete_recording_request(
+    config: &Config,
+    headers: &HeaderMap,
+    file_name: &str,
+) -> Result<Json<()>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(recorder::delete(file_name).await)
+}
+
+type ClipUpload = ContentLengthLimit<Bytes, { soundboard::MAX_CLIP_SIZE }>;
+
+async fn handle_download_clip_request(
+    name: &str,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let Json((clip, contents)) = to_response(soundboard::read(name).await)?;
+
+    Ok((
+        Headers(vec![
+            ("Content-Type", clip.content_type().to_owned()),
+            (
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", clip.file_name),
+            ),
+        ]),
+        contents,
+    ))
+}
+
+async fn handle_upload_clip_request(
+    config: &Config,
+    headers: &HeaderMap,
+    name: &str,
+    contents: &[u8],
+) -> Result<Json<Clip>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(soundboard::save(name, contents).await)
+}
+
+async fn handle_delete_clip_request(
+    config: &Config,
+    headers: &HeaderMap,
+    name: &str,
+) -> Result<Json<()>, (StatusCode, String)> {
+    authorize(config, headers)?;
+
+    to_response(soundboard::delete(name).await)
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct PlayClipRequest {
+    sink_name: Option<String>,
+    /// Between 0 and 150, 100 by default
+    volume: Option<f64>,
+    /// Whether to play into the soundboard microphone
+    #[serde(default)]
+    microphone: bool,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct SoundboardMicrophoneRequest {
+    source_name: String,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct ToneRequest {
+    sink: CardDeviceSelector,
+    /// 440 by default
+    frequency: Option<f64>,
+    /// 2 by default
+    duration_seconds: Option<f64>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct ChannelsRequest {
+    sink: CardDeviceSelector,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct MicrophoneTestRequest {
+    source: CardDeviceSelector,
+    /// The default sink when null
+    sink: Option<CardDeviceSelector>,
+    /// 5 by default
+    duration_seconds: Option<f64>,
 }
 
 #[derive(Deserialize, Debug)]
-struct ProfileRequest {
+struct RoutingGraphRequest {
+    format: Option<RoutingGraphFormat>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ListenRequest {
+    #[serde(rename = "type")]
+    _type: CardDeviceType,
+    name: String,
+    format: Option<ListenFormat>,
+    ticket: Option<String>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct VirtualDeviceRequest {
+    device: VirtualDevice,
+    /// Whether to create the device again whenever cctl starts
+    #[serde(default)]
+    persist: bool,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct LoopbackRequest {
+    source_name: String,
+    sink_name: String,
+    latency_msec: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct SceneRequest {
+    name: String,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct DeviceRequest {
+    restore: bool,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct NoiseSuppressionRequest {
+    enabled: bool,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[schemars(rename = "EqualizerPresetRequest")]
+pub(super) struct PresetRequest {
+    bands: Vec<Band>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct EqualizerRequest {
+    /// Null to stop equalizing the sink
+    preset: Option<String>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct PrivacyRequest {
+    enabled: bool,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+pub(super) struct ProfileRequest {
     index: u64,
     profile: CardProfile,
 }
@@ -262,12 +1201,7 @@ async fn handle_profile_request(
     Json(ProfileRequest { index, profile }): Json<ProfileRequest>,
     pubsub_tx: Arc<Mutex<UnboundedSender<Arc<dyn MessagePayload + Send + Sync>>>>,
 ) {
-    debug!(
-        "Setting the default bluetooth card index {} profile to {}",
-        index, profile
-    );
-
-    audio::set_card_profile(index, profile).await.unwrap();
-
-    audio_handler(pubsub_tx.clone()).await;
+    command::execute(Command::SetProfile { index, profile }, pubsub_tx)
+        .await
+        .unwrap();
 }
@@ -1,6 +1,6 @@
 import type { Writable } from 'svelte/store';
 
-const API_URL = <string>import.meta.env.VITE_API_URL;
+export const API_URL = `${<string>import.meta.env.VITE_API_URL}/api/v1`;
 
 export type ApiError = { status: number; statusText: string; url: string; data?: { [key: string]: unknown } };
 
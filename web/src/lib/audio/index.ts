@@ -1,9 +1,7 @@
 import type { AudioDevices, BluetoothAudioDeviceProfile } from './types';
 
 import { writable } from 'svelte/store';
-import { get, post } from '$lib/api';
-
-const API_URL = <string>import.meta.env.VITE_API_URL;
+import { API_URL, get, post } from '$lib/api';
 
 export const devices = writable<AudioDevices>(undefined);
 
@@ -42,7 +40,11 @@ export function connectAudioWS(): () => void {
   };
 
   ws.onmessage = ({ data }: MessageEvent) => {
-    const _devices = JSON.parse(data);
+    const { event, ..._devices } = JSON.parse(data);
+
+    if (event !== 'state') {
+      return;
+    }
 
     // if (_devices.timestamp > latestStateTimestamp) {
     //   latestStateTimestamp = _devices.timestamp;
@@ -55,17 +57,17 @@ export function connectAudioWS(): () => void {
 }
 
 export async function setVolume(type: 'source' | 'sink', index: number, volume: number): Promise<void> {
-  await post(`${API_URL}/audio/volume`, JSON.stringify({ type, index, volume }));
+  await post('/audio/volume', JSON.stringify({ type, index, volume }));
 }
 
 export async function toggleMute(type: 'source' | 'sink', index: number, mute: boolean): Promise<void> {
-  await post(`${API_URL}/audio/mute`, JSON.stringify({ type, index, mute }));
+  await post('/audio/mute', JSON.stringify({ type, index, mute }));
 }
 
 export async function setProfile(index: number, profile: BluetoothAudioDeviceProfile): Promise<void> {
-  await post(`${API_URL}/audio/profile`, JSON.stringify({ index, profile }));
+  await post('/audio/profile', JSON.stringify({ index, profile }));
 }
 
 export async function setDefault(type: 'source' | 'sink', index: number, name: string): Promise<void> {
-  await post(`${API_URL}/audio/default`, JSON.stringify({ type, index, name }));
+  await post('/audio/default', JSON.stringify({ type, index, name }));
 }
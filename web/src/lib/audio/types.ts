@@ -1,29 +1,64 @@
+// Mirrors the schemas of `/api/v1/openapi.json`
 export type AudioDevices = {
   cards: Card[];
   sources: CardDevice[];
   sinks: CardDevice[];
+  loopbacks: Loopback[];
+  recordings: Recording[];
+  // Milliseconds since the UNIX epoch
   timestamp: number;
 };
 
 export type Card = {
   index: number;
+  name: string;
+  driver: string;
   description: string;
-  bus: AudioDeviceBus;
-  form_factor: AudioDeviceFormFactor;
-  source_ids: Pick<CardDevice, 'index'>;
-  sink_ids: Pick<CardDevice, 'index'>;
   profiles: BluetoothAudioDeviceProfile[];
   active_profile: BluetoothAudioDeviceProfile;
+  source_ids: number[];
+  sink_ids: number[];
+  form_factor: AudioDeviceFormFactor;
+  bus: AudioDeviceBus;
 };
 
 export type CardDevice = {
   index: number;
   name: string;
-  description: string;
+  driver: string;
+  state: AudioDeviceState;
   is_default: boolean;
   volume: number;
   is_muted: boolean;
-  bluetooth_protocol: BluetoothProtocol;
+  card_index: number;
+  description: string;
+  bluetooth_protocol: BluetoothProtocol | null;
+  a2dp_codec: A2DPCodec | null;
+  form_factor: AudioDeviceFormFactor;
+  bus: AudioDeviceBus;
+  is_virtual: boolean;
+  channel_map: string[];
+  module_index: number | null;
+};
+
+export type Loopback = {
+  module_index: number;
+  source_name: string | null;
+  sink_name: string | null;
+  latency_msec: number | null;
+  is_managed: boolean;
+};
+
+export type Recording = {
+  file_name: string;
+  type: 'source' | 'sink';
+  card_device_name: string;
+  format: 'wav' | 'flac' | 'opus';
+  started_at: string;
+  duration_seconds: number;
+  size_bytes: number;
+  max_duration_seconds: number | null;
+  max_size_bytes: number | null;
 };
 
 export enum AudioDeviceBus {
@@ -39,6 +74,12 @@ export enum AudioDeviceFormFactor {
   Headset = 4,
 }
 
+export enum AudioDeviceState {
+  Running = 1,
+  Idle = 2,
+  Suspended = 3,
+}
+
 export enum BluetoothAudioDeviceProfile {
   HeadsetHeadUnit = 1,
   A2DPSinkSBC = 2,
@@ -54,6 +95,14 @@ export enum BluetoothProtocol {
   A2DPSink = 2,
 }
 
+export enum A2DPCodec {
+  SBC = 1,
+  AAC = 2,
+  AptX = 3,
+  AptXHD = 4,
+  LDAC = 5,
+}
+
 export function bluetoothAudioDeviceProfileToString(profile: BluetoothAudioDeviceProfile): string {
   switch (profile) {
     case BluetoothAudioDeviceProfile.HeadsetHeadUnit: